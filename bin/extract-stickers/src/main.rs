@@ -1,15 +1,19 @@
 use std::{fs::File, io::BufReader, path::Path};
 
 use anyhow::Context;
-use image::DynamicImage;
+use tex_convert::atlas::SheetLayout;
 
 fn main() -> anyhow::Result<()> {
     let args = std::env::args().collect::<Vec<String>>();
     if args.len() < 2 {
-        println!("Usage: {} <input_dir>", args[0]);
+        println!(
+            "Usage: {} <input_dir> [<tile_w> <tile_h> <rows> <cols> [padding]]",
+            args[0]
+        );
         return Ok(());
     }
     let input_dir = &args[1];
+    let layout = parse_layout(&args[2..])?;
 
     let mut input_paths = vec![];
     for entry in std::fs::read_dir(input_dir).context("无法打开输入目录")? {
@@ -28,11 +32,6 @@ fn main() -> anyhow::Result<()> {
     for input_path in input_paths {
         let mut reader = BufReader::new(File::open(&input_path)?);
         let img = tex_convert::load_tex_image(&mut reader)?;
-        let mut img = DynamicImage::ImageRgba8(img);
-
-        let width = 120;
-        let height = 86;
-        let n_tile = 5;
 
         let filestem = input_path
             .file_stem()
@@ -40,8 +39,16 @@ fn main() -> anyhow::Result<()> {
             .to_str()
             .unwrap_or_default();
         // crop and output
-        for row_index in 0..n_tile {
-            let tile = img.crop(0, row_index * height, width, height);
+        let Ok(tiles) = layout.slice(&img) else {
+            println!(
+                "Skipping {}: {}x{} is smaller than the layout",
+                input_path.display(),
+                img.width(),
+                img.height()
+            );
+            continue;
+        };
+        for (row_index, tile) in tiles.enumerate() {
             let file_output = output_dir.join(format!("{}_{}.png", filestem, row_index));
             println!("Writing {}...", file_output.display());
             tile.save(&file_output).context("无法保存图片")?;
@@ -50,3 +57,29 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// 未指定时使用游戏内聊天贴纸的布局，图集尺寸只在合成时使用，这里沿用默认值
+fn parse_layout(args: &[String]) -> anyhow::Result<SheetLayout> {
+    if args.is_empty() {
+        return Ok(SheetLayout::MHW_STICKER);
+    }
+    if args.len() < 4 {
+        anyhow::bail!("布局参数不足：需要 <tile_w> <tile_h> <rows> <cols> [padding]");
+    }
+    let values = args
+        .iter()
+        .map(|arg| arg.parse::<u32>().context("布局参数必须为整数"))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let layout = SheetLayout {
+        tile_w: values[0],
+        tile_h: values[1],
+        rows: values[2],
+        cols: values[3],
+        padding: values.get(4).copied().unwrap_or(0),
+        ..SheetLayout::MHW_STICKER
+    };
+    layout.validate()?;
+
+    Ok(layout)
+}
@@ -0,0 +1,181 @@
+//! 单文件工作区 (.mhwsws)
+//!
+//! 将工作区中需要保留的文件打包为一个 zip 格式的文件，便于与他人交换未完成的贴纸包。
+//! 压缩包内的目录结构与工作区相同，另有 `mhwsws.json` 记录导出时的信息。
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{i18n::t, output::OutputProfile, workspace::Workspace};
+
+/// 单文件工作区的扩展名
+pub const EXTENSION: &str = "mhwsws";
+const MANIFEST_FILE: &str = "mhwsws.json";
+
+/// 导出时的信息
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    /// 工作区名称，导入时作为默认目录名
+    name: String,
+    /// 导出所用的程序版本
+    app_version: String,
+    /// 导出时间，Unix 时间戳（秒）
+    exported: u64,
+}
+
+/// 将工作区导出为单个文件，返回包含的文件数量
+pub fn export<P: AsRef<Path>>(workspace: &Workspace, path: P) -> anyhow::Result<usize> {
+    let root_path = Path::new(workspace.root_path());
+    let manifest = Manifest {
+        name: root_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut zip_writer = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip_writer.start_file(MANIFEST_FILE, options)?;
+    zip_writer.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+
+    let files = workspace.source_files()?;
+    for file in &files {
+        // 压缩包内统一使用 / 分隔
+        let name = file
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip_writer.start_file(name, options)?;
+        zip_writer.write_all(&std::fs::read(root_path.join(file))?)?;
+    }
+    zip_writer.finish()?;
+
+    Ok(files.len())
+}
+
+/// 读取单文件工作区中记录的工作区名称
+pub fn workspace_name<P: AsRef<Path>>(path: P) -> anyhow::Result<String> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let manifest: Manifest = serde_json::from_reader(
+        archive
+            .by_name(MANIFEST_FILE)
+            .map_err(|_| anyhow::anyhow!(t!("不是有效的单文件工作区：缺少 {}", MANIFEST_FILE)))?,
+    )?;
+
+    Ok(manifest.name)
+}
+
+/// 将单文件工作区解压到 `root_path`，返回导入的工作区
+///
+/// 目标目录必须不存在，解压或读取失败时删除已解压的内容。
+/// 安装目录是导出者电脑上的路径，导入后清除，需要时重新设置。
+pub fn import<P: AsRef<Path>, Q: AsRef<Path>>(path: P, root_path: Q) -> anyhow::Result<Workspace> {
+    let root_path = root_path.as_ref();
+    if root_path.exists() {
+        anyhow::bail!(t!("目录已存在: {}", root_path.display()));
+    }
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    for required in [MANIFEST_FILE, "workspace.json"] {
+        if archive.index_for_name(required).is_none() {
+            anyhow::bail!(t!("不是有效的单文件工作区：缺少 {}", required));
+        }
+    }
+
+    let result = (|| {
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() || file.name() == MANIFEST_FILE {
+                continue;
+            }
+            // 拒绝解压到工作区目录之外的路径
+            let Some(name) = file.enclosed_name() else {
+                anyhow::bail!(t!("压缩包中包含无效的路径：{}", file.name()));
+            };
+            let output_path: PathBuf = root_path.join(name);
+            if let Some(parent) = output_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(&mut file, &mut File::create(output_path)?)?;
+        }
+
+        let mut workspace = Workspace::open(root_path)?;
+        if workspace.info().output().install_dir.is_some() {
+            workspace.set_output(OutputProfile {
+                install_dir: None,
+                ..workspace.info().output().clone()
+            })?;
+        }
+
+        Ok(workspace)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(root_path);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::TempDir, workspace::WorkspaceInfo};
+
+    use super::*;
+
+    #[test]
+    fn test_import_rejects_invalid_archive() {
+        let root = TempDir::new("archive-test");
+        let archive_path = root.join("example.mhwsws");
+        let mut zip_writer = ZipWriter::new(File::create(&archive_path).unwrap());
+        zip_writer
+            .start_file("chat_stamp00_ID.png", SimpleFileOptions::default())
+            .unwrap();
+        zip_writer.finish().unwrap();
+
+        assert!(workspace_name(&archive_path).is_err());
+        assert!(import(&archive_path, root.join("example")).is_err());
+        assert!(!root.join("example").exists());
+    }
+
+    #[test]
+    fn test_import_clears_install_dir() {
+        let root = TempDir::new("archive-import");
+        let archive_path = root.join("example.mhwsws");
+        let mut info = serde_json::to_value(WorkspaceInfo::default()).unwrap();
+        info["output"]["install_dir"] = "C:/Games/MonsterHunterWorld".into();
+        let manifest = Manifest {
+            name: "example".to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported: 0,
+        };
+        let mut zip_writer = ZipWriter::new(File::create(&archive_path).unwrap());
+        for (name, data) in [
+            (MANIFEST_FILE, serde_json::to_vec(&manifest).unwrap()),
+            ("workspace.json", serde_json::to_vec(&info).unwrap()),
+        ] {
+            zip_writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            zip_writer.write_all(&data).unwrap();
+        }
+        zip_writer.finish().unwrap();
+
+        let workspace = import(&archive_path, root.join("example")).unwrap();
+        assert!(workspace.info().output().install_dir.is_none());
+        let reopened = Workspace::open(root.join("example")).unwrap();
+        assert!(reopened.info().output().install_dir.is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! 对所有工作区执行同一操作
+//!
+//! 逐个打开当前目录下的工作区，单个工作区出错不影响其他工作区，最后以表格汇总结果。
+
+use std::path::PathBuf;
+
+use console::{measure_text_width, pad_str, Alignment};
+
+use crate::i18n::t;
+
+/// 单个工作区的执行结果
+pub struct Row {
+    pub workspace: String,
+    pub status: String,
+    pub detail: String,
+    pub failed: bool,
+}
+
+impl Row {
+    pub fn new(workspace: &str, status: &str, detail: impl Into<String>) -> Self {
+        Self {
+            workspace: workspace.to_string(),
+            status: status.to_string(),
+            detail: detail.into(),
+            failed: false,
+        }
+    }
+
+    pub fn failed(workspace: &str, error: &anyhow::Error) -> Self {
+        Self {
+            failed: true,
+            ..Self::new(workspace, t!("失败"), format!("{:#}", error))
+        }
+    }
+}
+
+/// 当前目录下包含 workspace.json 的目录，按名称排序
+///
+/// 与 [Workspace::list_all_workspaces](crate::workspace::Workspace::list_all_workspaces) 不同，
+/// 这里不打开工作区，损坏的工作区留到执行时单独报告。
+pub fn discover() -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in std::env::current_dir()?.read_dir()? {
+        let path = entry?.path();
+        if path.is_dir() && path.join("workspace.json").exists() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// 按显示宽度对齐的汇总表格，中文字符占两列
+pub fn summary_table(rows: &[Row]) -> String {
+    let header = [t!("工作区"), t!("结果"), t!("说明")];
+    let widths = [
+        column_width(header[0], rows.iter().map(|row| row.workspace.as_str())),
+        column_width(header[1], rows.iter().map(|row| row.status.as_str())),
+    ];
+    let line = |cells: [&str; 3]| {
+        format!(
+            "{}  {}  {}",
+            pad_str(cells[0], widths[0], Alignment::Left, None),
+            pad_str(cells[1], widths[1], Alignment::Left, None),
+            cells[2]
+        )
+        .trim_end()
+        .to_string()
+    };
+
+    let mut lines = vec![line(header)];
+    lines.push(line([
+        &"-".repeat(widths[0]),
+        &"-".repeat(widths[1]),
+        &"-".repeat(measure_text_width(header[2])),
+    ]));
+    for row in rows {
+        lines.push(line([&row.workspace, &row.status, &row.detail]));
+    }
+
+    lines.join("\n")
+}
+
+fn column_width<'a>(header: &str, cells: impl Iterator<Item = &'a str>) -> usize {
+    cells
+        .map(measure_text_width)
+        .chain([measure_text_width(header)])
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_table() {
+        let rows = [
+            Row::new("猫贴纸", "成功", "共打包 3 个贴纸"),
+            Row::failed("stickers", &anyhow::anyhow!("broken")),
+        ];
+        assert!(rows[1].failed);
+
+        let table = summary_table(&rows);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        let widths = lines
+            .iter()
+            .map(|line| measure_text_width(line.rsplit_once("  ").unwrap().0))
+            .collect::<Vec<_>>();
+        assert!(widths.iter().all(|&width| width == widths[0]));
+        assert!(lines[3].ends_with("broken"));
+    }
+}
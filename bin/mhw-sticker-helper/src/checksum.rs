@@ -0,0 +1,254 @@
+//! 贴纸文件的校验值
+//!
+//! 新解压的贴纸使用 BLAKE3，旧工作区中的 SHA-256 校验值继续有效。
+//! 计算结果按文件大小和修改时间缓存在 `.hashcache.json`，文件未变化时无需重新读取。
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util;
+
+/// 校验值缓存文件
+pub const HASH_CACHE_FILE: &str = ".hashcache.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HashString(Vec<u8>);
+
+impl HashString {
+    pub fn to_hex(&self) -> String {
+        use std::fmt::Write;
+
+        let hex_string = String::with_capacity(self.0.len() * 2);
+        self.0.iter().fold(hex_string, |mut hex_string, byte| {
+            write!(hex_string, "{:02x}", byte).unwrap();
+            hex_string
+        })
+    }
+
+    pub fn from_hex(hex_str: &str) -> Result<HashString, hex::FromHexError> {
+        let bytes = hex::decode(hex_str)?;
+        Ok(HashString(bytes))
+    }
+}
+
+impl Serialize for HashString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for HashString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        HashString::from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 带算法的校验值，序列化为 `{"blake3": "<hex>"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Checksum {
+    /// v5 之前的工作区
+    Sha256(HashString),
+    Blake3(HashString),
+}
+
+impl Checksum {
+    pub fn blake3<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_reader(reader)?;
+
+        Ok(Checksum::Blake3(HashString(
+            hasher.finalize().as_bytes().to_vec(),
+        )))
+    }
+
+    /// 使用与 `self` 相同的算法计算校验值
+    pub fn same_kind<R: Read>(&self, reader: &mut R) -> std::io::Result<Self> {
+        match self {
+            Checksum::Sha256(_) => Ok(Checksum::Sha256(HashString(
+                util::sha256_digest(reader)?.as_ref().to_vec(),
+            ))),
+            Checksum::Blake3(_) => Self::blake3(reader),
+        }
+    }
+
+    fn is_same_kind(&self, other: &Checksum) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    /// 修改时间，Unix 时间戳（纳秒）
+    modified: u128,
+    checksum: Checksum,
+}
+
+/// 按文件名记录的校验值缓存
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HashCache {
+    entries: HashMap<String, CacheEntry>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl HashCache {
+    /// 读取工作区的缓存，文件不存在或损坏时返回空缓存
+    pub fn load<P: AsRef<Path>>(root_path: P) -> Self {
+        std::fs::read(root_path.as_ref().join(HASH_CACHE_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// 有新的计算结果时写回缓存
+    pub fn save<P: AsRef<Path>>(&self, root_path: P) -> anyhow::Result<()> {
+        if self.changed {
+            std::fs::write(
+                root_path.as_ref().join(HASH_CACHE_FILE),
+                serde_json::to_vec(self)?,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// 计算工作区中文件的校验值，算法与 `like` 相同，文件大小和修改时间未变时直接使用缓存
+    pub fn checksum<P: AsRef<Path>>(
+        &self,
+        root_path: P,
+        filename: &str,
+        like: &Checksum,
+    ) -> std::io::Result<Checksum> {
+        let path = root_path.as_ref().join(filename);
+        let (size, modified) = file_stamp(&path)?;
+        match self.entries.get(filename) {
+            Some(entry)
+                if entry.size == size
+                    && entry.modified == modified
+                    && entry.checksum.is_same_kind(like) =>
+            {
+                Ok(entry.checksum.clone())
+            }
+            _ => like.same_kind(&mut BufReader::with_capacity(64 * 1024, File::open(&path)?)),
+        }
+    }
+
+    /// 与文件不一致的缓存项，即文件已删除，或大小和修改时间未变但重新计算的校验值不同，返回文件名
+    pub fn inconsistent_entries<P: AsRef<Path>>(&self, root_path: P) -> Vec<String> {
+        let root_path = root_path.as_ref();
+        let mut filenames = self
+            .entries
+            .iter()
+            .filter(|(filename, entry)| {
+                let path = root_path.join(filename);
+                match file_stamp(&path) {
+                    Ok(stamp) if stamp != (entry.size, entry.modified) => false,
+                    Ok(_) => !File::open(&path)
+                        .and_then(|file| {
+                            entry
+                                .checksum
+                                .same_kind(&mut BufReader::with_capacity(64 * 1024, file))
+                        })
+                        .is_ok_and(|checksum| checksum == entry.checksum),
+                    Err(_) => true,
+                }
+            })
+            .map(|(filename, _)| filename.clone())
+            .collect::<Vec<_>>();
+        filenames.sort();
+
+        filenames
+    }
+
+    /// 记录计算结果，文件在计算后被修改时记录的时间较新，下次会重新计算
+    pub fn insert<P: AsRef<Path>>(&mut self, root_path: P, filename: &str, checksum: Checksum) {
+        let Ok((size, modified)) = file_stamp(&root_path.as_ref().join(filename)) else {
+            return;
+        };
+        let entry = CacheEntry {
+            size,
+            modified,
+            checksum,
+        };
+        let unchanged = self.entries.get(filename).is_some_and(|old| {
+            old.size == entry.size
+                && old.modified == entry.modified
+                && old.checksum == entry.checksum
+        });
+        if !unchanged {
+            self.entries.insert(filename.to_string(), entry);
+            self.changed = true;
+        }
+    }
+}
+
+fn file_stamp(path: &Path) -> std::io::Result<(u64, u128)> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let root = TempDir::new("checksum-test");
+        std::fs::write(root.join("sticker.png"), b"sticker").unwrap();
+
+        let like = Checksum::blake3(&mut &b""[..]).unwrap();
+        let mut cache = HashCache::load(&root);
+        let checksum = cache.checksum(&root, "sticker.png", &like).unwrap();
+        assert_eq!(checksum, Checksum::blake3(&mut &b"sticker"[..]).unwrap());
+        cache.insert(&root, "sticker.png", checksum.clone());
+        cache.save(&root).unwrap();
+
+        let cache = HashCache::load(&root);
+        assert_eq!(
+            cache.checksum(&root, "sticker.png", &like).unwrap(),
+            checksum
+        );
+        // 旧工作区的 SHA-256 不使用 BLAKE3 的缓存
+        let sha256 = Checksum::Sha256(HashString(vec![]));
+        assert!(matches!(
+            cache.checksum(&root, "sticker.png", &sha256).unwrap(),
+            Checksum::Sha256(_)
+        ));
+        assert!(cache.inconsistent_entries(&root).is_empty());
+
+        // 缓存中的校验值与文件内容不符
+        let mut cache = HashCache::load(&root);
+        cache.insert(&root, "sticker.png", like);
+        std::fs::write(root.join("deleted.png"), b"deleted").unwrap();
+        cache.insert(&root, "deleted.png", checksum);
+        std::fs::remove_file(root.join("deleted.png")).unwrap();
+        assert_eq!(
+            cache.inconsistent_entries(&root),
+            ["deleted.png", "sticker.png"]
+        );
+    }
+}
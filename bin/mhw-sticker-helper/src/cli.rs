@@ -0,0 +1,171 @@
+//! 命令行参数
+//!
+//! 不带子命令启动时进入交互式菜单，子命令用于脚本和批处理。
+//! 帮助信息取自文档注释中的中文原文，由 [command] 按界面语言翻译。
+
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::{convert::FileKind, i18n};
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "MHW 贴纸助手",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// 直接转换的文件，将文件拖放到程序上时使用：.tex 转为 .png，.png 和 .dds 转为 .tex
+    pub file: Option<PathBuf>,
+    /// 显示调试信息
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    pub verbose: bool,
+    /// 只显示警告和错误
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// 子命令失败时在标准输出打印 JSON 格式的错误信息，包含错误码和退出码
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 打包工作区中已更改的贴纸
+    Package {
+        /// 工作区目录
+        workspace: PathBuf,
+        /// 只打包指定的贴纸，以逗号分隔，例如 chat_stamp00_ID,chat_stamp03_ID
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// 只列出将生成的文件和预计大小，不写入任何内容
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 撤销最近一次打包：删除生成的文件，并从回收站恢复被覆盖的文件
+    UndoPackage {
+        /// 工作区目录
+        workspace: PathBuf,
+    },
+    /// 打包当前目录下的所有工作区，最后汇总每个工作区的结果
+    PackageAll,
+    /// 检查当前目录下所有工作区中已更改的贴纸，最后汇总每个工作区的结果
+    ValidateAll,
+    /// 从游戏目录卸载工作区安装的文件，并恢复被覆盖的文件
+    Uninstall {
+        /// 工作区目录
+        workspace: PathBuf,
+    },
+    /// 检查工作区和运行环境，并给出修复建议
+    Doctor {
+        /// 工作区目录，不指定时只检查游戏目录、输出目录和磁盘空间
+        workspace: Option<PathBuf>,
+    },
+    /// 将工作区导出为单文件工作区 (.mhwsws)
+    Export {
+        /// 工作区目录
+        workspace: PathBuf,
+        /// 输出文件，默认为工作区旁的 <工作区名>.mhwsws
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 显示游戏目录，未设置时通过 Steam 查找
+    GameDir {
+        /// 手动指定游戏目录，保存到全局配置
+        path: Option<PathBuf>,
+    },
+    /// 在 tex、dds 和 png 之间转换文件，根据扩展名决定转换方向
+    ///
+    /// 输入为目录或带通配符的文件名（例如 "stickers/*.tex"）时，输出为目录。
+    Convert {
+        /// 输入文件、目录或带通配符的文件名
+        input: PathBuf,
+        /// 输出文件或目录
+        output: PathBuf,
+        /// 输出为目录时的目标格式，默认 tex 转为 png，其他转为 tex
+        #[arg(long, value_enum)]
+        to: Option<FileKind>,
+    },
+    /// 显示 tex 文件的尺寸、格式、mipmap 数量、数据偏移和大小，并检查文件是否有效
+    Inspect {
+        /// tex 文件
+        file: PathBuf,
+    },
+    /// 输出 shell 补全脚本
+    ///
+    /// 例如 `mhw-sticker-helper completions bash > /etc/bash_completion.d/mhw-sticker-helper`
+    Completions {
+        /// 目标 shell
+        shell: Shell,
+    },
+    /// 导入单文件工作区 (.mhwsws)
+    Import {
+        /// 单文件工作区路径
+        archive: PathBuf,
+        /// 解压到的目录，默认为当前目录下以导出时的工作区名称命名的目录
+        #[arg(short, long)]
+        target: Option<PathBuf>,
+    },
+}
+
+/// 按当前界面语言翻译帮助信息后的命令定义
+///
+/// 文档注释无法使用 `t!`，这里以原文在消息表中查找，多段的说明逐段查找。
+pub fn command() -> clap::Command {
+    translate(Cli::command())
+}
+
+fn translate(mut command: clap::Command) -> clap::Command {
+    if let Some(about) = command.get_about().map(translate_help) {
+        command = command.about(about);
+    }
+    if let Some(long_about) = command.get_long_about().map(translate_help) {
+        command = command.long_about(long_about);
+    }
+    command
+        .mut_args(|mut arg| {
+            if let Some(help) = arg.get_help().map(translate_help) {
+                arg = arg.help(help);
+            }
+            if let Some(long_help) = arg.get_long_help().map(translate_help) {
+                arg = arg.long_help(long_help);
+            }
+            arg
+        })
+        .mut_subcommands(translate)
+}
+
+fn translate_help(help: &clap::builder::StyledStr) -> String {
+    help.to_string()
+        .split("\n\n")
+        .map(|paragraph| i18n::lookup(paragraph).unwrap_or(paragraph))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 帮助信息中需要翻译的段落
+#[cfg(test)]
+pub fn help_paragraphs(command: &clap::Command) -> Vec<String> {
+    let mut texts = vec![command.get_about(), command.get_long_about()];
+    for arg in command.get_arguments() {
+        texts.extend([arg.get_help(), arg.get_long_help()]);
+    }
+    let mut paragraphs = texts
+        .into_iter()
+        .flatten()
+        .flat_map(|text| {
+            text.to_string()
+                .split("\n\n")
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for subcommand in command.get_subcommands() {
+        paragraphs.extend(help_paragraphs(subcommand));
+    }
+
+    paragraphs
+}
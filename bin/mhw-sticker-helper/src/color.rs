@@ -0,0 +1,268 @@
+//! 颜色配置检测
+//!
+//! Photoshop 等软件导出 PNG 时可能嵌入 ICC 颜色配置 (iCCP) 或 gAMA，解码时这些信息被忽略，
+//! 游戏内的颜色会与编辑器中看到的不同。检测到后给出警告，或在打包前转换到 sRGB。
+
+use std::{fmt::Display, io::Cursor, path::Path};
+
+use image::{codecs::png::PngDecoder, ImageDecoder, ImageFormat, RgbaImage};
+use tracing::warn;
+
+use crate::{error_code::ErrorCode, i18n::t};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// gAMA 为 1/2.2 时与 sRGB 基本一致，允许一定误差
+const SRGB_GAMMA: std::ops::RangeInclusive<u32> = 45000..=46000;
+/// ICC 配置转换到 sRGB 后各通道允许的误差
+const SRGB_ICC_TOLERANCE: u8 = 2;
+
+/// PNG 中嵌入的非 sRGB 颜色信息
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorProfile {
+    /// ICC 颜色配置的原始数据
+    Icc(Vec<u8>),
+    /// gAMA 的值，为编码伽马乘以 100000
+    Gamma(u32),
+}
+
+impl Display for ColorProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorProfile::Icc(_) => write!(f, "{}", t!("ICC 颜色配置")),
+            ColorProfile::Gamma(gamma) => write!(f, "gAMA {:.5}", *gamma as f32 / 100000.0),
+        }
+    }
+}
+
+/// 检测 PNG 数据中的颜色信息，标明为 sRGB 或不是 PNG 时返回 `None`
+pub fn detect(data: &[u8]) -> anyhow::Result<Option<ColorProfile>> {
+    let Some(mut chunks) = data.strip_prefix(PNG_SIGNATURE) else {
+        return Ok(None);
+    };
+
+    let mut has_icc = false;
+    let mut gamma = None;
+    // 颜色相关的块都位于图像数据之前
+    while chunks.len() >= 12 {
+        let length = u32::from_be_bytes(chunks[0..4].try_into()?) as usize;
+        let chunk_type = &chunks[4..8];
+        let Some(chunk_data) = chunks.get(8..8 + length) else {
+            break;
+        };
+        match chunk_type {
+            b"sRGB" => return Ok(None),
+            b"iCCP" => has_icc = true,
+            b"gAMA" if length == 4 => gamma = Some(u32::from_be_bytes(chunk_data.try_into()?)),
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        chunks = chunks.get(12 + length..).unwrap_or_default();
+    }
+
+    if has_icc {
+        let icc = PngDecoder::new(Cursor::new(data))?.icc_profile()?;
+        if let Some(icc) = icc.filter(|icc| !is_srgb_icc(icc)) {
+            return Ok(Some(ColorProfile::Icc(icc)));
+        }
+        return Ok(None);
+    }
+
+    Ok(gamma
+        .filter(|gamma| !SRGB_GAMMA.contains(gamma))
+        .map(ColorProfile::Gamma))
+}
+
+/// ICC 配置是否与 sRGB 等效，例如 Photoshop 常嵌入的 `sRGB IEC61966-2.1`
+///
+/// 不看配置的描述，而是将灰阶和各原色的渐变从该配置转换到 sRGB，结果基本不变时视为 sRGB。
+/// 无法解析的配置按非 sRGB 处理。
+fn is_srgb_icc(icc: &[u8]) -> bool {
+    let Some(input) = qcms::Profile::new_from_slice(icc, false) else {
+        return false;
+    };
+    let mut output = qcms::Profile::new_sRGB();
+    output.precache_output_transform();
+    let Some(transform) = qcms::Transform::new(
+        &input,
+        &output,
+        qcms::DataType::RGB8,
+        qcms::Intent::Perceptual,
+    ) else {
+        return false;
+    };
+
+    let samples = (0..=255u8)
+        .step_by(15)
+        .flat_map(|v| [v, v, v, v, 0, 0, 0, v, 0, 0, 0, v])
+        .collect::<Vec<_>>();
+    let mut converted = samples.clone();
+    transform.apply(&mut converted);
+    samples
+        .iter()
+        .zip(&converted)
+        .all(|(expected, actual)| expected.abs_diff(*actual) <= SRGB_ICC_TOLERANCE)
+}
+
+/// 将图片从嵌入的颜色配置转换到 sRGB
+pub fn convert_to_srgb(image: &mut RgbaImage, profile: &ColorProfile) -> anyhow::Result<()> {
+    match profile {
+        ColorProfile::Icc(icc) => {
+            let input = qcms::Profile::new_from_slice(icc, false)
+                .ok_or_else(|| ErrorCode::BadInput.with(t!("无法解析 ICC 颜色配置")))?;
+            let mut output = qcms::Profile::new_sRGB();
+            output.precache_output_transform();
+            let transform = qcms::Transform::new(
+                &input,
+                &output,
+                qcms::DataType::RGBA8,
+                qcms::Intent::Perceptual,
+            )
+            .ok_or_else(|| ErrorCode::BadInput.with(t!("不支持该 ICC 颜色配置")))?;
+            transform.apply(image.as_mut());
+        }
+        ColorProfile::Gamma(gamma) => {
+            // 先按文件的伽马还原为线性值，再按 sRGB 近似的 2.2 编码
+            let exponent = 100000.0 / (*gamma as f32 * 2.2);
+            let table: Vec<u8> = (0..=255)
+                .map(|v| ((v as f32 / 255.0).powf(exponent) * 255.0).round() as u8)
+                .collect();
+            for pixel in image.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = table[*channel as usize];
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取图片，PNG 中嵌入了颜色配置时按 `to_srgb` 转换到 sRGB，否则给出警告
+pub fn load_image<P: AsRef<Path>>(
+    path: P,
+    format: ImageFormat,
+    to_srgb: bool,
+) -> anyhow::Result<RgbaImage> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    let mut image = tex_convert::load_image(&mut Cursor::new(&data), format)?;
+    if format != ImageFormat::Png {
+        return Ok(image);
+    }
+
+    match detect(&data)? {
+        Some(profile) if to_srgb => convert_to_srgb(&mut image, &profile)?,
+        Some(profile) => warn!(
+            "{}",
+            t!(
+                "{} 嵌入了{}，游戏内颜色可能与编辑器中不同，可在输出设置中开启转换为 sRGB",
+                path.display(),
+                profile
+            )
+        ),
+        None => {}
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder, Rgba};
+
+    use super::*;
+
+    /// 在 IHDR 之后插入一个块
+    fn insert_chunk(png: &[u8], chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let ihdr_end = PNG_SIGNATURE.len() + 12 + 13;
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(chunk_type);
+        chunk.extend_from_slice(data);
+        // 检测时不校验 CRC
+        chunk.extend_from_slice(&[0; 4]);
+        [&png[..ihdr_end], &chunk, &png[ihdr_end..]].concat()
+    }
+
+    /// 使用 sRGB 原色的 ICC v2 配置，`curve` 为 parametricCurve 的参数
+    fn icc_profile(description: &str, curve: &[f32]) -> Vec<u8> {
+        let s15 = |value: f32| ((value * 65536.0).round() as i32).to_be_bytes();
+        let xyz = |values: [f32; 3]| {
+            let mut tag = b"XYZ \0\0\0\0".to_vec();
+            tag.extend(values.into_iter().flat_map(s15));
+            tag
+        };
+        // 函数类型 0 只有 gamma，类型 3 为 sRGB 的分段曲线
+        let function_type: u16 = if curve.len() == 1 { 0 } else { 3 };
+        let mut trc = b"para\0\0\0\0".to_vec();
+        trc.extend(function_type.to_be_bytes());
+        trc.extend([0, 0]);
+        trc.extend(curve.iter().flat_map(|&value| s15(value)));
+        let mut desc = b"desc\0\0\0\0".to_vec();
+        desc.extend((description.len() as u32 + 1).to_be_bytes());
+        desc.extend(description.as_bytes());
+        desc.push(0);
+        let tags = [
+            (b"desc", desc),
+            (b"rXYZ", xyz([0.4361, 0.2225, 0.0139])),
+            (b"gXYZ", xyz([0.3851, 0.7169, 0.0971])),
+            (b"bXYZ", xyz([0.1431, 0.0606, 0.7141])),
+            (b"rTRC", trc.clone()),
+            (b"gTRC", trc.clone()),
+            (b"bTRC", trc),
+        ];
+
+        let mut icc = vec![0; 128];
+        icc[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+        icc[12..16].copy_from_slice(b"mntr");
+        icc[16..20].copy_from_slice(b"RGB ");
+        icc[20..24].copy_from_slice(b"XYZ ");
+        icc[36..40].copy_from_slice(b"acsp");
+        icc.extend((tags.len() as u32).to_be_bytes());
+        let data_start = icc.len() + tags.len() * 12;
+        let mut data = vec![];
+        for (signature, tag) in &tags {
+            icc.extend(*signature);
+            icc.extend(((data_start + data.len()) as u32).to_be_bytes());
+            icc.extend((tag.len() as u32).to_be_bytes());
+            // 各标签按 4 字节对齐
+            data.extend(tag);
+            data.resize(data.len().next_multiple_of(4), 0);
+        }
+        icc.extend(data);
+        let size = icc.len() as u32;
+        icc[0..4].copy_from_slice(&size.to_be_bytes());
+        icc
+    }
+
+    #[test]
+    fn test_is_srgb_icc() {
+        let srgb = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045];
+        assert!(is_srgb_icc(&icc_profile("Custom RGB", &srgb)));
+        // 描述中带有 sRGB 字样但曲线是线性的
+        assert!(!is_srgb_icc(&icc_profile("sRGB IEC61966-2.1", &[1.0])));
+        assert!(!is_srgb_icc(b"sRGB"));
+    }
+
+    #[test]
+    fn test_detect_gamma() {
+        let mut png = vec![];
+        PngEncoder::new(&mut png)
+            .write_image(&[128, 128, 128, 255], 1, 1, ExtendedColorType::Rgba8)
+            .unwrap();
+        assert_eq!(detect(&png).unwrap(), None);
+        assert_eq!(
+            detect(&insert_chunk(&png, b"gAMA", &45455u32.to_be_bytes())).unwrap(),
+            None
+        );
+        assert_eq!(
+            detect(&insert_chunk(&png, b"gAMA", &100000u32.to_be_bytes())).unwrap(),
+            Some(ColorProfile::Gamma(100000))
+        );
+
+        // 线性编码的中灰转换到 sRGB 后变亮
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([128, 128, 128, 255]));
+        convert_to_srgb(&mut image, &ColorProfile::Gamma(100000)).unwrap();
+        assert!(image.get_pixel(0, 0)[0] > 128);
+        assert_eq!(image.get_pixel(0, 0)[3], 255);
+    }
+}
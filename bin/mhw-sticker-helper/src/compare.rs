@@ -0,0 +1,67 @@
+//! 已更改贴纸与原版的比较
+//!
+//! 每个贴纸生成一张对比图写入 `diff/` 目录，从左到右依次为原版、修改后、差异标注（品红色）。
+
+use std::path::{Path, PathBuf};
+
+use image::{GenericImage, RgbaImage};
+use tex_convert::diff::{diff_images, DiffOptions, DiffReport};
+use tracing::warn;
+
+use crate::{i18n::t, workspace::Workspace};
+
+const DIFF_DIR: &str = "diff";
+
+/// 单个贴纸的比较结果
+pub struct StickerDiff {
+    pub name: String,
+    pub report: DiffReport,
+    /// 对比图的路径
+    pub image_path: PathBuf,
+}
+
+/// 比较所有已更改的贴纸，导入的贴纸没有原版，不参与比较
+pub fn compare_modified(workspace: &Workspace) -> anyhow::Result<Vec<StickerDiff>> {
+    let diff_dir = Path::new(workspace.root_path()).join(DIFF_DIR);
+    std::fs::create_dir_all(&diff_dir)?;
+
+    let mut diffs = vec![];
+    for sticker in workspace.get_modified_stickers()? {
+        let Some(original) = workspace.load_original_image(&sticker)? else {
+            continue;
+        };
+        let edited = workspace.load_sticker_image(&sticker)?;
+        let mut report = match diff_images(
+            &original,
+            &edited,
+            &DiffOptions {
+                annotate: true,
+                ..Default::default()
+            },
+        ) {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("{}", t!("无法比较：{}：{}", sticker.filename, e));
+                continue;
+            }
+        };
+
+        let (width, height) = original.dimensions();
+        let mut side_by_side = RgbaImage::new(width * 3, height);
+        side_by_side.copy_from(&original, 0, 0)?;
+        side_by_side.copy_from(&edited, width, 0)?;
+        if let Some(diff_image) = report.diff_image.take() {
+            side_by_side.copy_from(&diff_image, width * 2, 0)?;
+        }
+        let image_path = diff_dir.join(format!("{}.png", sticker.name));
+        side_by_side.save(&image_path)?;
+
+        diffs.push(StickerDiff {
+            name: sticker.name,
+            report,
+            image_path,
+        });
+    }
+
+    Ok(diffs)
+}
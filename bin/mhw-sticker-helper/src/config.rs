@@ -0,0 +1,363 @@
+//! 配置文件
+//!
+//! 启动时读取全局的 `config.toml`，位于程序所在目录，没有时使用用户配置目录
+//! （Windows 下为 `%APPDATA%\mhw-sticker-helper\config.toml`）。
+//! 工作区目录下的 `config.toml` 可以覆盖其中的任意一项，例如：
+//!
+//! ```toml
+//! output_dir = "D:/mods"
+//! compression = "zstd"
+//! compression_level = 19
+//! image_format = "png"
+//! game_dir = "C:/Program Files (x86)/Steam/steamapps/common/Monster Hunter World"
+//! language = "zh-CN"
+//! check_updates = true
+//! ```
+
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+use tracing::{info, warn};
+
+use crate::{
+    game::TargetGame,
+    i18n::{t, Language},
+    steam,
+    workspace::{StickerPackType, Workspace},
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// 压缩包的压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Deflated,
+    /// 不压缩，tex 文件压缩率不高时可以加快打包
+    Stored,
+    /// 压缩率更高，但部分 MOD 管理器和旧版解压软件不支持
+    Zstd,
+}
+
+impl Compression {
+    pub const ALL: [Compression; 3] = [
+        Compression::Deflated,
+        Compression::Stored,
+        Compression::Zstd,
+    ];
+
+    /// 支持的压缩级别，不支持设置级别时为 None
+    pub fn level_range(self) -> Option<RangeInclusive<i64>> {
+        match self {
+            Compression::Deflated => Some(0..=9),
+            Compression::Stored => None,
+            Compression::Zstd => Some(1..=22),
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compression::Deflated => write!(f, "deflated"),
+            Compression::Stored => write!(f, "{}", t!("stored (不压缩)")),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl From<Compression> for zip::CompressionMethod {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Deflated => zip::CompressionMethod::Deflated,
+            Compression::Stored => zip::CompressionMethod::Stored,
+            Compression::Zstd => zip::CompressionMethod::Zstd,
+        }
+    }
+}
+
+/// 配置项，未设置的项使用默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// 打包输出目录，相对路径相对于工作区所在的目录，默认为 `dist`
+    pub output_dir: Option<PathBuf>,
+    /// 压缩包的压缩方式，默认为 deflated
+    pub compression: Option<Compression>,
+    /// 压缩级别，deflated 为 0-9，zstd 为 1-22，未设置时使用各压缩方式的默认级别
+    pub compression_level: Option<i64>,
+    /// 新建工作区时默认选择的文件格式
+    pub image_format: Option<StickerPackType>,
+    /// 游戏目录，设置直接安装时作为默认值，未设置时通过 Steam 查找
+    pub game_dir: Option<PathBuf>,
+    /// 界面语言，`zh-CN` 或 `en-US`，未设置时根据系统语言选择
+    pub language: Option<String>,
+    /// 启动时检查 GitHub 上是否有新版本，默认不检查
+    pub check_updates: Option<bool>,
+    /// 禁止访问网络，设置后不会检查更新
+    pub offline: Option<bool>,
+}
+
+impl Config {
+    /// 读取配置文件，文件不存在时为空
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let config: Config = toml::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow::anyhow!(t!("{} 格式错误：{}", path.display(), e)))?;
+        if let Some(language) = &config.language {
+            if Language::from_code(language).is_none() {
+                anyhow::bail!(t!("{} 中的语言不受支持：{}", path.display(), language));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// 以 `other` 中设置了的项覆盖当前配置
+    pub fn merge(self, other: Config) -> Self {
+        Self {
+            output_dir: other.output_dir.or(self.output_dir),
+            compression: other.compression.or(self.compression),
+            compression_level: other.compression_level.or(self.compression_level),
+            image_format: other.image_format.or(self.image_format),
+            game_dir: other.game_dir.or(self.game_dir),
+            language: other.language.or(self.language),
+            check_updates: other.check_updates.or(self.check_updates),
+            offline: other.offline.or(self.offline),
+        }
+    }
+
+    /// 是否在启动时检查更新，`offline` 优先
+    pub fn check_updates(&self) -> bool {
+        !self.offline.unwrap_or_default() && self.check_updates.unwrap_or_default()
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression.unwrap_or(Compression::Deflated)
+    }
+
+    /// 压缩级别，超出当前压缩方式的范围时给出警告并使用默认级别
+    pub fn compression_level(&self) -> Option<i64> {
+        let level = self.compression_level?;
+        let compression = self.compression();
+        match compression.level_range() {
+            Some(range) if range.contains(&level) => Some(level),
+            _ => {
+                warn!(
+                    "{}",
+                    t!(
+                        "压缩方式 {} 不支持压缩级别 {}，使用默认级别",
+                        compression,
+                        level
+                    )
+                );
+                None
+            }
+        }
+    }
+
+    /// 游戏目录，配置中没有时通过 Steam 查找
+    ///
+    /// 查找结果不会保存，用户确认使用后由调用方通过 [save_game_dir] 保存。
+    pub fn game_dir(&self, game: TargetGame) -> Option<PathBuf> {
+        if let Some(game_dir) = &self.game_dir {
+            return Some(game_dir.clone());
+        }
+        let game_dir = steam::find_game_dir(game)?;
+        info!(
+            "{}",
+            t!("已通过 Steam 找到游戏目录：{}", game_dir.display())
+        );
+
+        Some(game_dir)
+    }
+}
+
+/// 全局配置文件的路径，程序所在目录的优先
+pub fn global_path() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(CONFIG_FILE));
+    match beside_exe {
+        Some(path) if path.exists() => Some(path),
+        _ => dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(CONFIG_FILE)),
+    }
+}
+
+/// 修改配置文件中的项，值为 None 时删除该项，其他配置项以及注释和格式保持不变
+fn update_file(path: &Path, values: &[(&str, Option<toml_edit::Value>)]) -> anyhow::Result<()> {
+    let mut document = if path.exists() {
+        std::fs::read_to_string(path)?
+            .parse::<DocumentMut>()
+            .map_err(|e| anyhow::anyhow!(t!("{} 格式错误：{}", path.display(), e)))?
+    } else {
+        DocumentMut::new()
+    };
+    for (key, value) in values {
+        match value {
+            Some(value) => document[key] = toml_edit::Item::Value(value.clone()),
+            None => {
+                document.remove(key);
+            }
+        }
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, document.to_string())?;
+
+    Ok(())
+}
+
+/// 将游戏目录写入全局配置，返回配置文件的路径
+pub fn save_game_dir<P: AsRef<Path>>(game_dir: P) -> anyhow::Result<PathBuf> {
+    let path = global_path().ok_or_else(|| anyhow::anyhow!(t!("无法确定配置文件的位置")))?;
+    let game_dir = game_dir.as_ref().display().to_string();
+    update_file(&path, &[("game_dir", Some(game_dir.into()))])?;
+    // 之后读取的全局配置包含保存的目录
+    *GLOBAL.write().unwrap() = None;
+
+    Ok(path)
+}
+
+/// 已读取的全局配置，修改全局配置文件后清空以便重新读取
+static GLOBAL: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+/// 全局配置，第一次调用时读取，格式错误时给出警告并使用默认值
+pub fn global() -> Arc<Config> {
+    if let Some(config) = GLOBAL.read().unwrap().as_ref() {
+        return config.clone();
+    }
+    let config = match global_path() {
+        Some(path) => Config::load(path).unwrap_or_else(|e| {
+            warn!("{:#}", e);
+            Config::default()
+        }),
+        None => Config::default(),
+    };
+    let config = Arc::new(config);
+    *GLOBAL.write().unwrap() = Some(config.clone());
+
+    config
+}
+
+/// 不给出警告地读取全局配置，在日志初始化之前使用，配置文件的错误留到读取全局配置时提示
+fn load_early() -> Option<Config> {
+    global_path().and_then(|path| Config::load(path).ok())
+}
+
+/// 全局配置中的界面语言，在解析命令行参数之前使用
+pub fn language() -> Option<String> {
+    load_early().and_then(|config| config.language)
+}
+
+/// 当前目录下工作区的打包输出目录，在读取工作区之前使用
+pub fn current_dist_dir() -> PathBuf {
+    let output_dir = load_early()
+        .and_then(|config| config.output_dir)
+        .unwrap_or_else(|| PathBuf::from("dist"));
+
+    std::env::current_dir().unwrap_or_default().join(output_dir)
+}
+
+impl Workspace {
+    /// 全局配置与工作区目录下 `config.toml` 合并后的配置，工作区的格式错误时给出警告并忽略
+    pub fn config(&self) -> Config {
+        let workspace_config = Config::load(Path::new(self.root_path()).join(CONFIG_FILE))
+            .unwrap_or_else(|e| {
+                warn!("{:#}", e);
+                Config::default()
+            });
+
+        Config::clone(&global()).merge(workspace_config)
+    }
+
+    /// 将压缩方式和级别写入工作区的配置文件，`level` 为 None 时使用默认级别
+    pub fn save_compression(
+        &self,
+        compression: Compression,
+        level: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let compression = toml::Value::try_from(compression)?;
+        let compression = compression.as_str().unwrap_or_default();
+        update_file(
+            &Path::new(self.root_path()).join(CONFIG_FILE),
+            &[
+                ("compression", Some(compression.into())),
+                ("compression_level", level.map(Into::into)),
+            ],
+        )
+    }
+
+    /// 打包输出目录，工作区的打包设置优先于配置文件
+    pub fn dist_dir(&self) -> PathBuf {
+        let parent = Path::new(self.root_path()).parent().unwrap();
+        let output_dir = self.info().packaging().output_dir.clone();
+        match output_dir.or(self.config().output_dir) {
+            Some(output_dir) => parent.join(output_dir),
+            None => parent.join("dist"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let global: Config = toml::from_str(
+            r#"
+            output_dir = "mods"
+            compression = "stored"
+            image_format = "png"
+            "#,
+        )
+        .unwrap();
+        let workspace: Config = toml::from_str(r#"compression = "deflated""#).unwrap();
+
+        let config = global.merge(workspace);
+        assert_eq!(config.output_dir, Some(PathBuf::from("mods")));
+        assert_eq!(config.compression(), Compression::Deflated);
+        assert_eq!(config.image_format, Some(StickerPackType::Png));
+        let config = config.merge(toml::from_str("compression_level = 12").unwrap());
+        assert_eq!(config.compression_level(), None);
+        let config = config.merge(toml::from_str(r#"compression = "zstd""#).unwrap());
+        assert_eq!(config.compression_level(), Some(12));
+        assert!(!config.check_updates());
+        let config = config.merge(toml::from_str("check_updates = true").unwrap());
+        assert!(config.check_updates());
+        let config = config.merge(toml::from_str("offline = true").unwrap());
+        assert!(!config.check_updates());
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_update_file() {
+        let dir = TempDir::new("config-test");
+        let path = dir.join(CONFIG_FILE);
+        let original = "# 输出目录\noutput_dir = \"mods\" # 相对路径\ncompression = \"zstd\"\n";
+        std::fs::write(&path, original).unwrap();
+
+        update_file(
+            &path,
+            &[("game_dir", Some("C:/Games".into())), ("compression", None)],
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# 输出目录\noutput_dir = \"mods\" # 相对路径\n"));
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.game_dir, Some(PathBuf::from("C:/Games")));
+        assert_eq!(config.compression, None);
+    }
+}
@@ -0,0 +1,278 @@
+//! 单个文件的格式转换
+//!
+//! 不依赖工作区，根据扩展名在 tex、dds 和 png 之间转换。文件名与原版贴纸相同时
+//! 沿用原版的格式和文件头，与打包时的结果一致，否则根据图片内容自动选择格式。输入可以是目录或带通配符的文件名，
+//! 例如 `stickers/*.tex`，此时转换其中所有匹配的文件。
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use image::{ImageFormat, RgbaImage};
+use tex_convert::{options::ConvertOptions, spec::TexHeader};
+use tracing::{info, warn};
+
+use crate::{asset, color, error_code::ErrorCode, game::TargetGame, i18n::t};
+
+/// 支持转换的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FileKind {
+    Tex,
+    Dds,
+    Png,
+}
+
+impl std::fmt::Display for FileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FileKind {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "tex" => Some(FileKind::Tex),
+            "dds" => Some(FileKind::Dds),
+            "png" => Some(FileKind::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileKind::Tex => "tex",
+            FileKind::Dds => "dds",
+            FileKind::Png => "png",
+        }
+    }
+
+    /// 未指定目标格式时的转换方向：tex 转为 png，其他转为 tex
+    pub fn default_target(self) -> Self {
+        match self {
+            FileKind::Tex => FileKind::Png,
+            FileKind::Dds | FileKind::Png => FileKind::Tex,
+        }
+    }
+}
+
+/// 转换文件并写入 `output`
+pub fn convert_file<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> anyhow::Result<()> {
+    let output = output.as_ref();
+    let to = FileKind::from_path(output).ok_or_else(|| unsupported(output))?;
+    let data = convert(input.as_ref(), to)?;
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output, data)?;
+
+    Ok(())
+}
+
+/// 将文件转换为 `to` 格式，返回转换后的数据
+pub fn convert(input: &Path, to: FileKind) -> anyhow::Result<Vec<u8>> {
+    let from = FileKind::from_path(input).ok_or_else(|| unsupported(input))?;
+    let name = input.file_stem().unwrap_or_default().to_string_lossy();
+    let filename = input.file_name().unwrap_or_default().to_string_lossy();
+    let data = std::fs::read(input)?;
+    match (from, to) {
+        (FileKind::Tex, FileKind::Png) => {
+            encode_png(&tex_convert::load_tex_image(&mut Cursor::new(data))?)
+        }
+        (FileKind::Tex, FileKind::Dds) => Ok(tex_convert::tex2dds::convert_to_dds(
+            &mut Cursor::new(data),
+        )?),
+        (FileKind::Dds, FileKind::Png) => {
+            encode_png(&tex_convert::load_dds_image(&mut Cursor::new(data))?)
+        }
+        (FileKind::Dds, FileKind::Tex) => {
+            let tex_data = dds_to_tex(data, &name)?;
+            validate_tex(&filename, &tex_data)?;
+            Ok(tex_data)
+        }
+        (FileKind::Png, FileKind::Tex | FileKind::Dds) => {
+            let image = color::load_image(input, ImageFormat::Png, false)?;
+            let original = original_header(&name)?;
+            let options = ConvertOptions::default().preset(TargetGame::default().preset());
+            let options = match &original {
+                Some(reference) => options.same_format_as(reference.format),
+                None => {
+                    let decision = tex_convert::auto::select_format(&image);
+                    info!("{}", t!("{}：自动选择格式 {}", filename, decision));
+                    options.format(decision.format)
+                }
+            };
+            if to == FileKind::Dds {
+                return Ok(tex_convert::convert_image_to_dds(&image, &options)?);
+            }
+            let tex_data = match &original {
+                Some(reference) => {
+                    tex_convert::convert_image_to_tex_like(&image, &options, reference)?
+                }
+                None => tex_convert::convert_image_to_tex(&image, &options)?,
+            };
+            validate_tex(&filename, &tex_data)?;
+            Ok(tex_data)
+        }
+        _ => anyhow::bail!(ErrorCode::Usage.with(t!("{} 已经是 {} 文件", input.display(), to))),
+    }
+}
+
+/// 输入为目录或文件名中带有通配符
+pub fn is_batch<P: AsRef<Path>>(input: P) -> bool {
+    let input = input.as_ref();
+    input.is_dir()
+        || input
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().contains(['*', '?']))
+}
+
+/// 目录中所有支持的文件，或与通配符匹配的文件，不包括子目录，按文件名排序
+pub fn batch_inputs<P: AsRef<Path>>(input: P) -> anyhow::Result<Vec<PathBuf>> {
+    let input = input.as_ref();
+    let (dir, pattern) = if input.is_dir() {
+        (input, "*".to_string())
+    } else {
+        let dir = input
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let pattern = input.file_name().unwrap_or_default().to_string_lossy();
+        (dir, pattern.to_string())
+    };
+
+    let mut inputs = vec![];
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_file() && FileKind::from_path(&path).is_some() && wildcard_match(&pattern, &name)
+        {
+            inputs.push(path);
+        }
+    }
+    inputs.sort();
+
+    Ok(inputs)
+}
+
+/// `*` 匹配任意多个字符，`?` 匹配一个字符，忽略大小写
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当前匹配到的文本位置
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 写入前检查转换结果，避免打包损坏的文件
+pub fn validate_tex(filename: &str, tex_data: &[u8]) -> anyhow::Result<()> {
+    let report = tex_convert::validate(&mut Cursor::new(tex_data))?;
+    for warning in report.warnings() {
+        warn!("{}：{}", filename, warning);
+    }
+    if !report.is_valid() {
+        let errors = report
+            .errors()
+            .map(|e| format!("  - {}", e))
+            .collect::<Vec<_>>()
+            .join("\n");
+        anyhow::bail!(ErrorCode::ConversionFailed.with(t!(
+            "转换结果校验失败：{}\n{}",
+            filename,
+            errors
+        )));
+    }
+
+    Ok(())
+}
+
+/// 内置原始贴纸的文件头，重建时沿用其中的未知字段
+pub fn original_header(name: &str) -> anyhow::Result<Option<TexHeader>> {
+    let Some(file) = asset::Asset::get(&format!("{}.tex", name)) else {
+        return Ok(None);
+    };
+
+    Ok(Some(TexHeader::from_reader(&mut Cursor::new(file.data))?))
+}
+
+/// `name` 为原版贴纸的名称时沿用其文件头
+pub fn dds_to_tex(dds_data: Vec<u8>, name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut reader = Cursor::new(dds_data);
+    let tex_data = match original_header(name)? {
+        Some(reference) => tex_convert::dds2tex::convert_to_tex_like(&mut reader, &reference)?,
+        None => tex_convert::dds2tex::convert_to_tex(&mut reader)?,
+    };
+
+    Ok(tex_data)
+}
+
+fn encode_png(image: &RgbaImage) -> anyhow::Result<Vec<u8>> {
+    let mut png_data = Cursor::new(vec![]);
+    image.write_to(&mut png_data, ImageFormat::Png)?;
+
+    Ok(png_data.into_inner())
+}
+
+/// 不支持的文件类型
+pub fn unsupported(path: &Path) -> anyhow::Error {
+    ErrorCode::BadInput
+        .with(t!(
+            "不支持的文件类型：{}，支持 .tex、.png 和 .dds",
+            path.display()
+        ))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_batch_inputs() {
+        assert!(wildcard_match("*.tex", "chat_stamp00_ID.TEX"));
+        assert!(wildcard_match("chat_stamp0?_*", "chat_stamp03_ID.png"));
+        assert!(!wildcard_match("chat_stamp0?_*", "chat_stamp10_ID.png"));
+        assert!(!wildcard_match("*.tex", "a.tex.png"));
+
+        let dir = TempDir::new("convert-test");
+        std::fs::create_dir_all(dir.join("sub.tex")).unwrap();
+        for name in ["b.tex", "a.tex", "a.png", "notes.txt"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        assert!(is_batch(&dir));
+        assert!(is_batch(dir.join("*.tex")));
+        assert!(!is_batch(dir.join("a.tex")));
+        assert_eq!(
+            batch_inputs(&dir).unwrap(),
+            [dir.join("a.png"), dir.join("a.tex"), dir.join("b.tex")]
+        );
+        assert_eq!(
+            batch_inputs(dir.join("*.TEX")).unwrap(),
+            [dir.join("a.tex"), dir.join("b.tex")]
+        );
+        assert_eq!(
+            FileKind::from_path("a.TEX").map(FileKind::default_target),
+            Some(FileKind::Png)
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! 环境与工作区诊断
+//!
+//! 检查工作区信息、缺失和多余的文件、校验值缓存、游戏目录、输出目录和磁盘空间，
+//! 除了在输出目录中试写一个临时文件外不修改任何文件，每项失败时给出修复建议。
+
+use std::{path::Path, process::Command};
+
+use crate::{
+    checksum::{HashCache, HASH_CACHE_FILE},
+    config,
+    game::TargetGame,
+    i18n::t,
+    util,
+    workspace::{Workspace, WorkspaceInfo, DEFAULT_IMAGE_PATTERN, ORIGINAL_DIR},
+};
+
+/// 输出目录所在磁盘的可用空间低于该值时视为失败
+const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+/// 单项检查的结果
+pub struct Check {
+    pub name: String,
+    /// 通过时为说明，失败时为问题和修复建议
+    pub result: Result<String, Failure>,
+}
+
+pub struct Failure {
+    pub problem: String,
+    pub suggestion: String,
+}
+
+impl Check {
+    fn passed(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            result: Ok(detail.into()),
+        }
+    }
+
+    fn failed(name: &str, problem: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            result: Err(Failure {
+                problem: problem.into(),
+                suggestion: suggestion.into(),
+            }),
+        }
+    }
+}
+
+/// 检查工作区和运行环境，`root_path` 为 None 时只检查运行环境
+pub fn diagnose(root_path: Option<&Path>) -> Vec<Check> {
+    let mut checks = vec![];
+    let mut workspace = None;
+    if let Some(root_path) = root_path {
+        checks.push(check_info(root_path));
+        if checks.iter().all(|check| check.result.is_ok()) {
+            match Workspace::open_read_only(root_path) {
+                Ok(opened) => workspace = Some(opened),
+                Err(e) => checks.push(Check::failed(
+                    t!("工作区信息"),
+                    format!("{:#}", e),
+                    t!("检查工作区目录的权限"),
+                )),
+            }
+        }
+    }
+    if let Some(workspace) = &workspace {
+        checks.push(check_missing_files(workspace));
+        checks.push(check_extra_files(workspace));
+        checks.push(check_hash_cache(workspace));
+    }
+
+    let game = workspace
+        .as_ref()
+        .map(|workspace| workspace.info().target_game())
+        .unwrap_or_default();
+    checks.push(check_game_dir(game));
+    let dist_dir = match &workspace {
+        Some(workspace) => workspace.dist_dir(),
+        None => config::current_dist_dir(),
+    };
+    checks.push(check_dist_dir(&dist_dir));
+    checks.push(check_disk_space(&dist_dir));
+
+    checks
+}
+
+fn check_info(root_path: &Path) -> Check {
+    let name = t!("工作区信息");
+    let info_path = root_path.join("workspace.json");
+    let result = std::fs::read_to_string(&info_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|info_str| WorkspaceInfo::from_json(&info_str));
+    match result {
+        Ok((info, _)) => Check::passed(
+            name,
+            t!(
+                "版本 {}，{} 个贴纸包",
+                info.version(),
+                info.sticker_packs().len()
+            ),
+        ),
+        Err(e) => {
+            let backup_path = util::append_extension(&info_path, "bak");
+            let backup_valid = std::fs::read_to_string(&backup_path)
+                .is_ok_and(|backup_str| WorkspaceInfo::from_json(&backup_str).is_ok());
+            let suggestion = if !WorkspaceInfo::is_corrupt(&e) {
+                t!("请更新程序，或检查 {} 中的版本号", info_path.display())
+            } else if backup_valid {
+                t!("打开工作区时会自动从备份恢复：{}", backup_path.display())
+            } else {
+                t!(
+                    "没有可用的备份，请检查 {} 的内容，或从导出的工作区压缩包重新导入",
+                    info_path.display()
+                )
+            };
+            Check::failed(
+                name,
+                t!("无法读取 {}：{:#}", info_path.display(), e),
+                suggestion,
+            )
+        }
+    }
+}
+
+/// workspace.json 中记录但不存在的贴纸文件和原版副本
+fn check_missing_files(workspace: &Workspace) -> Check {
+    let name = t!("缺失文件");
+    let root_path = Path::new(workspace.root_path());
+    let mut missing = vec![];
+    for sticker in workspace.info().sticker_packs() {
+        let tga_name = format!("{}.tga", sticker.name);
+        if !root_path.join(&sticker.filename).exists() && !root_path.join(tga_name).exists() {
+            missing.push(sticker.filename.clone());
+        }
+        let original = Path::new(ORIGINAL_DIR).join(format!("{}.tex", sticker.name));
+        if !sticker.imported && !root_path.join(&original).exists() {
+            missing.push(original.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    if missing.is_empty() {
+        return Check::passed(name, t!("没有缺失的文件"));
+    }
+
+    Check::failed(
+        name,
+        t!("{} 个文件不存在：{}", missing.len(), summarize(&missing)),
+        t!("在工作区菜单中选择“修复缺失文件”，导入的贴纸需要重新导入"),
+    )
+}
+
+/// 工作区目录中未登记的图片，打包时会被忽略
+fn check_extra_files(workspace: &Workspace) -> Check {
+    let name = t!("多余文件");
+    match workspace.unknown_images(DEFAULT_IMAGE_PATTERN) {
+        Ok(images) if images.is_empty() => Check::passed(name, t!("没有未登记的图片")),
+        Ok(images) => Check::failed(
+            name,
+            t!(
+                "{} 个图片未登记，打包时会被忽略：{}",
+                images.len(),
+                summarize(&images)
+            ),
+            t!("在工作区菜单中选择“登记新图片”，或将其移出工作区目录"),
+        ),
+        Err(e) => Check::failed(
+            name,
+            t!("无法读取工作区目录：{}", e),
+            t!("检查工作区目录的权限"),
+        ),
+    }
+}
+
+fn check_hash_cache(workspace: &Workspace) -> Check {
+    let name = t!("校验值缓存");
+    let cache_path = Path::new(workspace.root_path()).join(HASH_CACHE_FILE);
+    let suggestion = t!("删除 {}，下次检查时会重新计算", cache_path.display());
+    let Ok(data) = std::fs::read(&cache_path) else {
+        return Check::passed(name, t!("尚未生成"));
+    };
+    let cache = match serde_json::from_slice::<HashCache>(&data) {
+        Ok(cache) => cache,
+        Err(e) => return Check::failed(name, t!("缓存文件损坏：{}", e), suggestion),
+    };
+    let inconsistent = cache.inconsistent_entries(workspace.root_path());
+    if inconsistent.is_empty() {
+        return Check::passed(name, t!("与文件一致"));
+    }
+
+    Check::failed(
+        name,
+        t!(
+            "{} 个缓存项与文件不一致：{}",
+            inconsistent.len(),
+            summarize(&inconsistent)
+        ),
+        suggestion,
+    )
+}
+
+fn check_game_dir(game: TargetGame) -> Check {
+    let name = t!("游戏目录");
+    let suggestion = t!("使用 game-dir <目录> 命令指定游戏目录");
+    let Some(game_dir) = config::global().game_dir(game) else {
+        return Check::failed(name, t!("未找到游戏目录"), suggestion);
+    };
+    if !game_dir.join(game.exe_name()).exists() {
+        return Check::failed(
+            name,
+            t!("目录中没有 {}：{}", game.exe_name(), game_dir.display()),
+            suggestion,
+        );
+    }
+
+    Check::passed(name, game_dir.display().to_string())
+}
+
+/// 在输出目录中写入并删除一个临时文件，为此创建的目录随后一并删除
+fn check_dist_dir(dist_dir: &Path) -> Check {
+    let name = t!("输出目录");
+    let probe_path = dist_dir.join(".doctor");
+    let created = dist_dir
+        .ancestors()
+        .take_while(|path| !path.as_os_str().is_empty() && !path.exists())
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+    let result = std::fs::create_dir_all(dist_dir)
+        .and_then(|_| std::fs::write(&probe_path, b""))
+        .and_then(|_| std::fs::remove_file(&probe_path));
+    // 从最深的目录开始删除，只删除空目录
+    for path in &created {
+        let _ = std::fs::remove_dir(path);
+    }
+    match result {
+        Ok(()) => Check::passed(name, dist_dir.display().to_string()),
+        Err(e) => Check::failed(
+            name,
+            t!("无法写入 {}：{}", dist_dir.display(), e),
+            t!("检查目录权限，或在“打包设置”中更换输出目录"),
+        ),
+    }
+}
+
+fn check_disk_space(dist_dir: &Path) -> Check {
+    let name = t!("磁盘空间");
+    // 输出目录可能还不存在，检查最近的已存在的上级目录
+    let existing = dist_dir
+        .ancestors()
+        .find(|path| path.exists())
+        .unwrap_or(dist_dir);
+    match available_space(existing) {
+        Ok(free) if free < MIN_FREE_SPACE => Check::failed(
+            name,
+            t!("可用空间不足：{}", util::format_size(free)),
+            t!(
+                "清理磁盘，保留至少 {} 的可用空间",
+                util::format_size(MIN_FREE_SPACE)
+            ),
+        ),
+        Ok(free) => Check::passed(name, t!("可用 {}", util::format_size(free))),
+        Err(e) => Check::failed(
+            name,
+            t!("无法获取可用空间：{:#}", e),
+            t!("手动确认输出目录所在的磁盘有足够的空间"),
+        ),
+    }
+}
+
+/// 目录所在磁盘的可用空间（字节），通过系统命令获取
+fn available_space(path: &Path) -> anyhow::Result<u64> {
+    let path = std::path::absolute(path)?;
+    let output = space_command(&path)
+        .output()
+        .map_err(|e| anyhow::anyhow!(t!("无法运行命令：{}", e)))?;
+    if !output.status.success() {
+        anyhow::bail!(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    parse_space(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow::anyhow!(t!("无法识别命令输出")))
+}
+
+#[cfg(windows)]
+fn space_command(path: &Path) -> Command {
+    let mut command = Command::new("powershell");
+    let path = path.display().to_string().replace('\'', "''");
+    command.args([
+        "-NoProfile",
+        "-Command",
+        &format!("(Get-Item -LiteralPath '{}').PSDrive.Free", path),
+    ]);
+    command
+}
+
+#[cfg(not(windows))]
+fn space_command(path: &Path) -> Command {
+    let mut command = Command::new("df");
+    command.arg("-Pk").arg(path);
+    command
+}
+
+/// 解析 PowerShell 输出的字节数
+#[cfg(windows)]
+fn parse_space(output: &str) -> Option<u64> {
+    output.trim().parse().ok()
+}
+
+/// 解析 `df -Pk` 输出中的可用空间，单位为 KiB
+#[cfg(not(windows))]
+fn parse_space(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let available: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+
+    Some(available * 1024)
+}
+
+/// 最多列出前 5 项，避免输出过长
+fn summarize(items: &[String]) -> String {
+    const MAX_ITEMS: usize = 5;
+    let mut summary = items
+        .iter()
+        .take(MAX_ITEMS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if items.len() > MAX_ITEMS {
+        summary.push_str(&t!("等 {} 项", items.len()));
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_diagnose() {
+        #[cfg(not(windows))]
+        assert_eq!(
+            parse_space(
+                "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                 /dev/sda1 1000 400 600 40% /\n"
+            ),
+            Some(600 * 1024)
+        );
+
+        let items = (0..7).map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(summarize(&items[..2]), "0, 1");
+        assert!(summarize(&items).starts_with("0, 1, 2, 3, 4"));
+
+        let root = TempDir::new("doctor-test");
+        std::fs::write(root.join("workspace.json"), b"{").unwrap();
+        assert!(check_info(&root).result.is_err());
+        assert!(check_dist_dir(&root.join("dist/nested")).result.is_ok());
+        assert!(!root.join("dist").exists());
+    }
+}
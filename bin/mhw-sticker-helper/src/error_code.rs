@@ -0,0 +1,179 @@
+//! 命令行模式的退出码
+//!
+//! 子命令失败时按错误类型返回不同的退出码，脚本可以据此区分失败原因。
+//! 使用 `--json` 时同时在标准输出打印 JSON 格式的错误信息，`code` 为下表中的字符串：
+//!
+//! | 退出码 | code                | 说明                                   |
+//! | ------ | ------------------- | -------------------------------------- |
+//! | 1      | `other`             | 其他错误                               |
+//! | 2      | `bad_input`         | 输入的图片或 tex 文件无法读取或不符合要求 |
+//! | 3      | `conversion_failed` | 转换失败或转换结果校验失败             |
+//! | 4      | `io`                | 读写文件失败                           |
+//! | 5      | `workspace_invalid` | 工作区不存在或 workspace.json 损坏     |
+//! | 6      | `workspace_locked`  | 工作区正在被其他程序使用               |
+//! | 7      | `check_failed`      | 检查或诊断发现问题                     |
+//! | 8      | `partial_failure`   | 批量操作中部分文件或工作区失败         |
+//! | 64     | `usage`             | 命令行参数错误                         |
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// 错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Other,
+    BadInput,
+    ConversionFailed,
+    Io,
+    WorkspaceInvalid,
+    WorkspaceLocked,
+    CheckFailed,
+    PartialFailure,
+    Usage,
+}
+
+impl ErrorCode {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorCode::Other => 1,
+            ErrorCode::BadInput => 2,
+            ErrorCode::ConversionFailed => 3,
+            ErrorCode::Io => 4,
+            ErrorCode::WorkspaceInvalid => 5,
+            ErrorCode::WorkspaceLocked => 6,
+            ErrorCode::CheckFailed => 7,
+            ErrorCode::PartialFailure => 8,
+            ErrorCode::Usage => 64,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Other => "other",
+            ErrorCode::BadInput => "bad_input",
+            ErrorCode::ConversionFailed => "conversion_failed",
+            ErrorCode::Io => "io",
+            ErrorCode::WorkspaceInvalid => "workspace_invalid",
+            ErrorCode::WorkspaceLocked => "workspace_locked",
+            ErrorCode::CheckFailed => "check_failed",
+            ErrorCode::PartialFailure => "partial_failure",
+            ErrorCode::Usage => "usage",
+        }
+    }
+
+    /// 带有该类型的错误，用于无法从错误来源推断类型的情况
+    pub fn with(self, message: impl Into<String>) -> CodedError {
+        CodedError {
+            code: self,
+            message: message.into(),
+        }
+    }
+
+    /// 错误的类型，明确指定的类型优先，其次取错误链中最外层能识别的错误
+    pub fn of(error: &anyhow::Error) -> Self {
+        // 作为 context 添加的错误无法从 chain 中取得，只能通过 anyhow::Error 查找
+        if let Some(coded) = error.downcast_ref::<CodedError>() {
+            return coded.code;
+        }
+        error
+            .chain()
+            .find_map(|cause| {
+                if let Some(e) = cause.downcast_ref::<tex_convert::error::Error>() {
+                    Some(Self::of_tex(e))
+                } else if let Some(e) = cause.downcast_ref::<image::ImageError>() {
+                    match e {
+                        image::ImageError::IoError(_) => Some(ErrorCode::Io),
+                        _ => Some(ErrorCode::BadInput),
+                    }
+                } else if cause.is::<clap::Error>() {
+                    Some(ErrorCode::Usage)
+                } else if cause.is::<std::io::Error>() || cause.is::<zip::result::ZipError>() {
+                    Some(ErrorCode::Io)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorCode::Other)
+    }
+
+    fn of_tex(error: &tex_convert::error::Error) -> Self {
+        use tex_convert::error::Error;
+
+        match error {
+            Error::IO(_) => ErrorCode::Io,
+            Error::CreateDdsFromImage(_) | Error::DataSizeMismatch(..) => {
+                ErrorCode::ConversionFailed
+            }
+            _ => ErrorCode::BadInput,
+        }
+    }
+}
+
+/// 带有错误类型的错误，显示时只显示消息
+#[derive(Debug)]
+pub struct CodedError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+/// `--json` 时输出的错误信息
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub code: &'static str,
+    pub exit_code: u8,
+    pub message: String,
+}
+
+impl ErrorReport {
+    pub fn new(error: &anyhow::Error) -> Self {
+        let code = ErrorCode::of(error);
+        Self {
+            code: code.as_str(),
+            exit_code: code.exit_code(),
+            message: format!("{:#}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        assert_eq!(ErrorCode::of(&anyhow::Error::new(io)), ErrorCode::Io);
+        assert_eq!(
+            ErrorCode::of(&anyhow::anyhow!("plain message")),
+            ErrorCode::Other
+        );
+
+        // 明确指定的类型优先
+        let error = anyhow::Error::new(tex_convert::error::Error::UnknownTexFormat)
+            .context(ErrorCode::ConversionFailed.with("校验失败"));
+        assert_eq!(ErrorCode::of(&error), ErrorCode::ConversionFailed);
+        let error = Err::<(), _>(tex_convert::error::Error::UnknownTexFormat)
+            .context("读取失败")
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&error), ErrorCode::BadInput);
+
+        let report = ErrorReport::new(&anyhow::Error::new(
+            ErrorCode::WorkspaceLocked.with("locked"),
+        ));
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"code":"workspace_locked","exit_code":6,"message":"locked"}"#
+        );
+    }
+}
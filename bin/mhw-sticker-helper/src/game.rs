@@ -0,0 +1,62 @@
+//! 目标游戏
+//!
+//! 决定工作区使用的内置贴纸、打包时的游戏内路径和编码参数。
+
+use serde::{Deserialize, Serialize};
+use tex_convert::options::Preset;
+
+use crate::asset;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetGame {
+    /// Monster Hunter: World 本体
+    #[default]
+    Mhw,
+    /// Iceborne 资料片，贴纸的路径和格式与本体相同
+    Iceborne,
+}
+
+impl std::fmt::Display for TargetGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetGame::Mhw => write!(f, "Monster Hunter: World"),
+            TargetGame::Iceborne => write!(f, "Monster Hunter World: Iceborne"),
+        }
+    }
+}
+
+impl TargetGame {
+    pub const ALL: [TargetGame; 2] = [TargetGame::Mhw, TargetGame::Iceborne];
+
+    /// 贴纸在游戏目录中的位置
+    pub fn stamp_dir(&self) -> &'static str {
+        match self {
+            TargetGame::Mhw | TargetGame::Iceborne => "nativePC/ui/chat/tex/stamp",
+        }
+    }
+
+    /// 游戏主程序的文件名，用于确认游戏目录
+    pub fn exe_name(&self) -> &'static str {
+        match self {
+            TargetGame::Mhw | TargetGame::Iceborne => "MonsterHunterWorld.exe",
+        }
+    }
+
+    /// 打包时的编码预设
+    pub fn preset(&self) -> Preset {
+        match self {
+            TargetGame::Mhw | TargetGame::Iceborne => Preset::Release,
+        }
+    }
+
+    /// 内置资源中属于该游戏的贴纸文件名
+    pub fn assets(&self) -> Vec<String> {
+        match self {
+            TargetGame::Mhw | TargetGame::Iceborne => asset::Asset::iter()
+                .filter(|name| name.ends_with(".tex"))
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! 工作区的 git 版本管理
+//!
+//! 直接调用系统中的 git 命令，未安装 git 时相关功能不可用，不影响其他操作。
+
+use std::{path::Path, process::Command};
+
+use crate::i18n::t;
+
+/// 工作区生成的中间文件，不需要纳入版本管理
+const GITIGNORE: &str = "\
+# 打包输出
+dist/
+# 快照、预览页面与对比图
+.snapshots/
+preview/
+preview.html
+diff/
+templates/
+# 工作区锁、校验值缓存与 workspace.json 的备份
+.lock
+.hashcache.json
+# 安装到游戏目录的记录与被覆盖文件的备份
+install.json
+.install_backup/
+*.bak
+*.tmp
+*.corrupt
+";
+
+/// 在工作区目录初始化 git 仓库并写入 .gitignore，已有的 .gitignore 不会被覆盖
+pub fn init<P: AsRef<Path>>(root_path: P) -> anyhow::Result<()> {
+    let root_path = root_path.as_ref();
+    run(root_path, &["init", "--quiet"])?;
+
+    let gitignore_path = root_path.join(".gitignore");
+    if !gitignore_path.exists() {
+        std::fs::write(gitignore_path, GITIGNORE)?;
+    }
+
+    Ok(())
+}
+
+/// 工作区是否为 git 仓库
+pub fn is_repository<P: AsRef<Path>>(root_path: P) -> bool {
+    root_path.as_ref().join(".git").exists()
+}
+
+/// 工作区中贴纸文件的 git 状态，格式同 `git status --short`
+pub fn status<P: AsRef<Path>>(root_path: P) -> anyhow::Result<String> {
+    run(
+        root_path.as_ref(),
+        &[
+            "status", "--short", "--", "*.png", "*.dds", "*.tga", "*.json",
+        ],
+    )
+}
+
+fn run(root_path: &Path, args: &[&str]) -> anyhow::Result<String> {
+    tracing::debug!("git {}", args.join(" "));
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root_path)
+        .output()
+        .map_err(|e| anyhow::anyhow!(t!("无法运行 git，请确认已安装 git：{}", e)))?;
+    if !output.status.success() {
+        anyhow::bail!(t!(
+            "git {} 失败：{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
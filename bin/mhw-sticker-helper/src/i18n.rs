@@ -0,0 +1,289 @@
+//! 界面语言
+//!
+//! 代码中的消息直接使用中文原文，由 [t!] 宏按当前语言查表翻译。
+//! 其他语言的消息表位于 `locales/` 目录，以中文原文为键，表中没有的消息显示中文原文。
+//!
+//! 语言取自全局配置中的 `language`，未设置时根据系统语言选择。
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        OnceLock,
+    },
+};
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    ZhCn,
+    EnUs,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::ZhCn, Language::EnUs];
+
+    /// 配置文件中使用的语言代码
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::ZhCn => "zh-CN",
+            Language::EnUs => "en-US",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(code))
+    }
+
+    /// 根据系统语言选择，只比较主语言，例如 `en-GB`、`zh-Hans-CN`
+    fn from_locale(locale: &str) -> Option<Self> {
+        match locale
+            .split(['-', '_'])
+            .next()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "zh" => Some(Language::ZhCn),
+            "en" => Some(Language::EnUs),
+            _ => None,
+        }
+    }
+
+    /// 消息表，中文原文不需要
+    fn messages(self) -> Option<&'static HashMap<String, String>> {
+        static EN_US: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Language::ZhCn => None,
+            Language::EnUs => Some(EN_US.get_or_init(|| {
+                toml::from_str(include_str!("../locales/en-US.toml"))
+                    .expect("invalid locales/en-US.toml")
+            })),
+        }
+    }
+}
+
+/// 0 表示尚未确定，其余为 [Language::ALL] 中的位置加 1
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// 设置界面语言，`language` 为配置中的语言代码，未设置或无效时根据系统语言选择
+pub fn init(language: Option<&str>) {
+    let language = language
+        .and_then(Language::from_code)
+        .unwrap_or_else(detect);
+    set(language);
+}
+
+fn set(language: Language) {
+    let index = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .unwrap_or(0);
+    CURRENT.store(index as u8 + 1, Ordering::Relaxed);
+}
+
+fn detect() -> Language {
+    sys_locale::get_locale()
+        .and_then(|locale| Language::from_locale(&locale))
+        .unwrap_or(Language::ZhCn)
+}
+
+/// 当前的界面语言，未初始化时根据系统语言选择
+pub fn current() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        0 => {
+            let language = detect();
+            set(language);
+            language
+        }
+        index => Language::ALL[index as usize - 1],
+    }
+}
+
+/// 查找消息的翻译，当前语言为中文或没有翻译时返回 None
+pub fn lookup(key: &str) -> Option<&'static str> {
+    current()
+        .messages()
+        .and_then(|messages| messages.get(key))
+        .map(String::as_str)
+}
+
+/// 按翻译后的模板格式化
+///
+/// 支持 `{}`、`{0}` 等位置参数，以及 `{:#}` 和 `{:.2}` 形式的格式，其余格式按 `{}` 处理。
+pub fn render(template: &str, args: &[&dyn Display]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                output.push(c);
+            }
+            '{' => {
+                let spec = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                let (position, format) = spec.split_once(':').unwrap_or((&spec, ""));
+                let index = if position.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    position.parse().unwrap_or(usize::MAX)
+                };
+                let Some(arg) = args.get(index) else {
+                    continue;
+                };
+                let precision = format.strip_prefix('.').and_then(|p| p.parse().ok());
+                let _ = match (format, precision) {
+                    ("#", _) => write!(output, "{:#}", arg),
+                    (_, Some(precision)) => write!(output, "{:.*}", precision, arg),
+                    _ => write!(output, "{}", arg),
+                };
+            }
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// 翻译消息，用法同 `format!`，格式字符串即中文原文
+///
+/// 没有参数时返回 `&'static str`，有参数时返回 `String`。
+macro_rules! t {
+    ($key:literal $(,)?) => {
+        $crate::i18n::lookup($key).unwrap_or($key)
+    };
+    ($key:literal, $($arg:expr),+ $(,)?) => {
+        match $crate::i18n::lookup($key) {
+            Some(template) => {
+                $crate::i18n::render(template, &[$(&$arg as &dyn ::std::fmt::Display),+])
+            }
+            None => format!($key, $($arg),+),
+        }
+    };
+}
+pub(crate) use t;
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    /// 模板中的参数位置
+    fn placeholders(template: &str) -> Vec<usize> {
+        let mut positions = vec![];
+        let mut next = 0;
+        let mut rest = template.replace("{{", "").replace("}}", "");
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}').unwrap();
+            let position = rest[start + 1..end].split(':').next().unwrap();
+            positions.push(if position.is_empty() {
+                next += 1;
+                next - 1
+            } else {
+                position.parse().unwrap()
+            });
+            rest = rest[end + 1..].to_string();
+        }
+        positions.sort();
+        positions.dedup();
+        positions
+    }
+
+    #[test]
+    fn test_render() {
+        let e = anyhow::anyhow!("inner").context("outer");
+        assert_eq!(render("{1} of {0} {{x}}", &[&1, &"b"]), "b of 1 {x}");
+        assert_eq!(
+            render("{:.2} / {:#}", &[&0.5f32, &e]),
+            "0.50 / outer: inner"
+        );
+        assert_eq!(Language::from_locale("en_GB.UTF-8"), Some(Language::EnUs));
+        assert_eq!(Language::from_locale("zh-Hans-CN"), Some(Language::ZhCn));
+    }
+
+    /// 源文件中 `t!` 的中文原文
+    fn source_keys() -> Vec<String> {
+        let sources = [
+            include_str!("main.rs"),
+            include_str!("archive.rs"),
+            include_str!("batch.rs"),
+            include_str!("color.rs"),
+            include_str!("compare.rs"),
+            include_str!("config.rs"),
+            include_str!("convert.rs"),
+            include_str!("doctor.rs"),
+            include_str!("git.rs"),
+            include_str!("install.rs"),
+            include_str!("lock.rs"),
+            include_str!("logging.rs"),
+            include_str!("migration.rs"),
+            include_str!("names.rs"),
+            include_str!("output.rs"),
+            include_str!("picker.rs"),
+            include_str!("preview.rs"),
+            include_str!("release.rs"),
+            include_str!("snapshot.rs"),
+            include_str!("steam.rs"),
+            include_str!("trash.rs"),
+            include_str!("update.rs"),
+            include_str!("validation.rs"),
+            include_str!("workspace.rs"),
+        ];
+        let mut keys = vec![];
+        for source in sources {
+            let source = source.split("#[cfg(test)]").next().unwrap();
+            for (start, _) in source.match_indices("t!(") {
+                // 排除 format!( 等
+                let before = source[..start].chars().next_back().unwrap_or(' ');
+                if before.is_alphanumeric() || before == '_' {
+                    continue;
+                }
+                let rest = source[start + 3..].trim_start();
+                let Some(rest) = rest.strip_prefix('"') else {
+                    continue;
+                };
+                let mut key = String::new();
+                let mut chars = rest.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => key.push('\n'),
+                            Some(c) => key.push(c),
+                            None => break,
+                        },
+                        c => key.push(c),
+                    }
+                }
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn test_messages() {
+        for language in Language::ALL {
+            let Some(messages) = language.messages() else {
+                continue;
+            };
+            for (key, value) in messages {
+                assert_eq!(placeholders(key), placeholders(value), "{}", key);
+            }
+            let help = crate::cli::help_paragraphs(&crate::cli::Cli::command());
+            for key in source_keys().into_iter().chain(help) {
+                assert!(
+                    messages.contains_key(&key),
+                    "{} 缺少翻译：{}",
+                    language.code(),
+                    key
+                );
+            }
+        }
+    }
+}
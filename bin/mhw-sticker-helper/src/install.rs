@@ -0,0 +1,281 @@
+//! 安装到游戏目录
+//!
+//! 打包的 tex 文件直接复制到游戏目录的 nativePC 中。被覆盖的同名文件（例如其他 MOD 的贴纸）
+//! 先备份到工作区的 `.install_backup/`，安装的文件记录在工作区的 `install.json`。
+//! 卸载时只删除记录中的文件并恢复备份，游戏目录中的其他文件保持不变。
+
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checksum::Checksum, i18n::t, output::Package};
+
+/// 安装记录文件
+pub const INSTALL_MANIFEST: &str = "install.json";
+/// 被覆盖文件的备份目录，目录结构与游戏目录相同
+pub const BACKUP_DIR: &str = ".install_backup";
+
+/// 工作区安装到游戏目录的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallManifest {
+    pub game_dir: PathBuf,
+    pub files: Vec<InstalledFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledFile {
+    /// 相对于游戏目录的路径
+    pub path: String,
+    /// 安装的文件内容的校验值
+    pub checksum: Checksum,
+    /// 安装前是否已有同名文件，有则已备份
+    #[serde(default)]
+    pub backed_up: bool,
+}
+
+impl InstallManifest {
+    /// 读取安装记录，没有安装过时为 None
+    pub fn load<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Option<Self>> {
+        let path = root_path.as_ref().join(INSTALL_MANIFEST);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    fn save<P: AsRef<Path>>(&self, root_path: P) -> anyhow::Result<()> {
+        std::fs::write(
+            root_path.as_ref().join(INSTALL_MANIFEST),
+            serde_json::to_string_pretty(self)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// 将打包的文件安装到游戏目录，返回安装的文件
+///
+/// 已由本工作区安装的文件直接覆盖，不再备份。
+pub fn install<P: AsRef<Path>, Q: AsRef<Path>>(
+    root_path: P,
+    game_dir: Q,
+    package: &Package,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let (root_path, game_dir) = (root_path.as_ref(), game_dir.as_ref());
+    let mut manifest = match InstallManifest::load(root_path)? {
+        Some(manifest) if manifest.game_dir == game_dir => manifest,
+        Some(manifest) => anyhow::bail!(t!(
+            "工作区已安装到 {}，请先卸载",
+            manifest.game_dir.display()
+        )),
+        None => InstallManifest {
+            game_dir: game_dir.to_path_buf(),
+            files: vec![],
+        },
+    };
+
+    let mut installed = vec![];
+    for file in package.files {
+        let path = format!("{}/{}", package.stamp_dir, file.file_name);
+        let target = game_dir.join(&path);
+        let checksum = Checksum::blake3(&mut file.data.as_slice())?;
+        match manifest.files.iter_mut().find(|f| f.path == path) {
+            Some(entry) => entry.checksum = checksum,
+            None => {
+                let backed_up = target.is_file();
+                if backed_up {
+                    let backup_path = root_path.join(BACKUP_DIR).join(&path);
+                    std::fs::create_dir_all(backup_path.parent().unwrap())?;
+                    std::fs::copy(&target, backup_path)?;
+                }
+                manifest.files.push(InstalledFile {
+                    path,
+                    checksum,
+                    backed_up,
+                });
+            }
+        }
+        std::fs::create_dir_all(target.parent().unwrap())?;
+        std::fs::write(&target, &file.data)?;
+        // 每个文件写入后立即记录，中途失败也能卸载已安装的文件
+        manifest.save(root_path)?;
+        installed.push(target);
+    }
+
+    Ok(installed)
+}
+
+/// 卸载的结果
+#[derive(Debug, Default)]
+pub struct Uninstalled {
+    /// 删除的文件数量
+    pub removed: usize,
+    /// 恢复的备份数量
+    pub restored: usize,
+    /// 安装后被修改过的文件，例如被其他 MOD 覆盖，保持不变
+    pub skipped: Vec<PathBuf>,
+    /// 被跳过的文件在安装前的备份，连同安装记录一起保留
+    pub kept_backups: Vec<PathBuf>,
+}
+
+/// 删除工作区安装到游戏目录的文件并恢复备份，没有安装过时返回 None
+///
+/// 被跳过的文件如有备份，备份和对应的安装记录保留，其余的备份和记录删除。
+pub fn uninstall<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Option<Uninstalled>> {
+    let root_path = root_path.as_ref();
+    let Some(manifest) = InstallManifest::load(root_path)? else {
+        return Ok(None);
+    };
+
+    let backup_dir = root_path.join(BACKUP_DIR);
+    let mut result = Uninstalled::default();
+    let mut kept = vec![];
+    for file in &manifest.files {
+        let target = manifest.game_dir.join(&file.path);
+        if target.exists() {
+            if !is_unchanged(&target, &file.checksum) {
+                result.skipped.push(target);
+                if file.backed_up {
+                    result.kept_backups.push(backup_dir.join(&file.path));
+                    kept.push(file.clone());
+                }
+                continue;
+            }
+            std::fs::remove_file(&target)?;
+            result.removed += 1;
+        }
+        if file.backed_up {
+            std::fs::copy(backup_dir.join(&file.path), &target)?;
+            result.restored += 1;
+        }
+    }
+
+    if kept.is_empty() {
+        if backup_dir.exists() {
+            std::fs::remove_dir_all(backup_dir)?;
+        }
+        std::fs::remove_file(root_path.join(INSTALL_MANIFEST))?;
+    } else {
+        // 全部处理完再删除已恢复的备份，中途失败时可以重新卸载
+        for file in manifest.files.iter().filter(|file| file.backed_up) {
+            if !kept.iter().any(|kept| kept.path == file.path) {
+                std::fs::remove_file(backup_dir.join(&file.path))?;
+            }
+        }
+        InstallManifest {
+            files: kept,
+            ..manifest
+        }
+        .save(root_path)?;
+    }
+
+    Ok(Some(result))
+}
+
+/// 文件当前的内容是否与安装时相同
+fn is_unchanged(path: &Path, checksum: &Checksum) -> bool {
+    File::open(path)
+        .and_then(|mut file| checksum.same_kind(&mut file))
+        .is_ok_and(|current| &current == checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        output::{ModInfo, PackagedFile},
+        util::TempDir,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_install() {
+        let root = TempDir::new("install-test");
+        let (workspace_dir, game_dir) = (root.join("workspace"), root.join("game"));
+        let stamp_dir = game_dir.join("nativePC/ui/chat/tex/stamp");
+        std::fs::create_dir_all(&workspace_dir).unwrap();
+        std::fs::create_dir_all(&stamp_dir).unwrap();
+        std::fs::write(stamp_dir.join("a.tex"), b"other mod").unwrap();
+
+        let files = [
+            PackagedFile {
+                file_name: "a.tex".to_string(),
+                data: b"a".to_vec(),
+            },
+            PackagedFile {
+                file_name: "b.tex".to_string(),
+                data: b"b".to_vec(),
+            },
+        ];
+        let package = Package {
+            name: "workspace",
+            stamp_dir: "nativePC/ui/chat/tex/stamp",
+            files: &files,
+            readme: None,
+            mod_info: &ModInfo::new("workspace"),
+            compression: zip::CompressionMethod::Stored,
+            compression_level: None,
+        };
+        assert_eq!(
+            install(&workspace_dir, &game_dir, &package).unwrap().len(),
+            2
+        );
+        // 重复安装不会覆盖备份
+        install(&workspace_dir, &game_dir, &package).unwrap();
+        assert_eq!(
+            std::fs::read(
+                workspace_dir
+                    .join(BACKUP_DIR)
+                    .join("nativePC/ui/chat/tex/stamp/a.tex")
+            )
+            .unwrap(),
+            b"other mod"
+        );
+
+        let manifest = InstallManifest::load(&workspace_dir).unwrap().unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.files[0].backed_up && !manifest.files[1].backed_up);
+        assert!(install(&workspace_dir, root.join("other"), &package).is_err());
+
+        // 被其他 MOD 覆盖的文件保持不变
+        std::fs::write(stamp_dir.join("b.tex"), b"newer mod").unwrap();
+        let result = uninstall(&workspace_dir).unwrap().unwrap();
+        assert_eq!((result.removed, result.restored), (1, 1));
+        assert_eq!(result.skipped, [stamp_dir.join("b.tex")]);
+        assert_eq!(
+            std::fs::read(stamp_dir.join("a.tex")).unwrap(),
+            b"other mod"
+        );
+        assert_eq!(
+            std::fs::read(stamp_dir.join("b.tex")).unwrap(),
+            b"newer mod"
+        );
+        assert!(InstallManifest::load(&workspace_dir).unwrap().is_none());
+
+        // 被跳过的文件保留备份和安装记录
+        install(&workspace_dir, &game_dir, &package).unwrap();
+        std::fs::write(stamp_dir.join("a.tex"), b"third mod").unwrap();
+        let result = uninstall(&workspace_dir).unwrap().unwrap();
+        assert_eq!((result.removed, result.restored), (1, 1));
+        let backup_dir = workspace_dir.join(BACKUP_DIR);
+        assert_eq!(
+            result.kept_backups,
+            [backup_dir.join("nativePC/ui/chat/tex/stamp/a.tex")]
+        );
+        assert_eq!(
+            std::fs::read(&result.kept_backups[0]).unwrap(),
+            b"other mod"
+        );
+        assert!(!backup_dir.join("nativePC/ui/chat/tex/stamp/b.tex").exists());
+        assert_eq!(
+            std::fs::read(stamp_dir.join("b.tex")).unwrap(),
+            b"newer mod"
+        );
+        let manifest = InstallManifest::load(&workspace_dir).unwrap().unwrap();
+        assert_eq!(manifest.files.len(), 1);
+    }
+}
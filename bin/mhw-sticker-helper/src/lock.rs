@@ -0,0 +1,148 @@
+//! 工作区锁
+//!
+//! 打开工作区时在目录下创建 `.lock` 文件，记录进程 ID 和心跳时间，
+//! 持有锁期间由后台线程定期刷新心跳。程序异常退出后心跳不再更新，超时的锁可以直接接管。
+
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error_code::ErrorCode, i18n::t};
+
+const LOCK_FILE: &str = ".lock";
+/// 心跳刷新间隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// 超过该时间未刷新心跳的锁视为失效
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    /// 最近一次心跳，Unix 时间戳（秒）
+    heartbeat: u64,
+}
+
+impl LockInfo {
+    fn current() -> anyhow::Result<Self> {
+        Ok(Self {
+            pid: std::process::id(),
+            heartbeat: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        })
+    }
+
+    fn is_stale(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default();
+        now.saturating_sub(self.heartbeat) > STALE_AFTER.as_secs()
+    }
+}
+
+/// 工作区锁，离开作用域时释放
+pub struct WorkspaceLock {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl WorkspaceLock {
+    /// 锁定工作区目录，已被其他进程锁定时返回错误
+    pub fn acquire<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Self> {
+        let path = root_path.as_ref().join(LOCK_FILE);
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(&serde_json::to_vec(&LockInfo::current()?)?)?;
+                    break;
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    // 读取失败说明对方刚创建还未写入，同样视为占用
+                    let holder = std::fs::read(&path)
+                        .ok()
+                        .and_then(|data| serde_json::from_slice::<LockInfo>(&data).ok());
+                    match holder {
+                        Some(holder) if holder.is_stale() => std::fs::remove_file(&path)?,
+                        Some(holder) => anyhow::bail!(ErrorCode::WorkspaceLocked.with(t!("工作区正在被其他程序使用（PID {}）：{}\n如果确认没有其他程序在使用，请在 {} 秒后重试或删除 {}", holder.pid, root_path.as_ref().display(), STALE_AFTER.as_secs(), path.display()))),
+                        None => anyhow::bail!(ErrorCode::WorkspaceLocked.with(t!("工作区正在被其他程序使用：{}", root_path.as_ref().display()))),
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let heartbeat = std::thread::spawn({
+            let path = path.clone();
+            let stopped = stopped.clone();
+            move || loop {
+                std::thread::park_timeout(HEARTBEAT_INTERVAL);
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                // 工作区目录被删除后写入失败，忽略即可
+                if let Ok(info) =
+                    LockInfo::current().and_then(|info| Ok(serde_json::to_vec(&info)?))
+                {
+                    let _ = std::fs::write(&path, info);
+                }
+            }
+        });
+
+        Ok(Self {
+            path,
+            stopped,
+            heartbeat: Some(heartbeat),
+        })
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.thread().unpark();
+            let _ = heartbeat.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let root_path = TempDir::new("lock-test");
+
+        let lock = WorkspaceLock::acquire(&root_path).unwrap();
+        assert!(WorkspaceLock::acquire(&root_path).is_err());
+        drop(lock);
+        assert!(!root_path.join(LOCK_FILE).exists());
+
+        // 心跳超时的锁可以接管
+        let stale = LockInfo {
+            pid: 0,
+            heartbeat: 0,
+        };
+        std::fs::write(
+            root_path.join(LOCK_FILE),
+            serde_json::to_vec(&stale).unwrap(),
+        )
+        .unwrap();
+        drop(WorkspaceLock::acquire(&root_path).unwrap());
+    }
+}
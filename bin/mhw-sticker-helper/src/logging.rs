@@ -0,0 +1,168 @@
+//! 日志
+//!
+//! 控制台只显示提示信息，`--verbose` 显示调试信息，`--quiet` 只显示警告和错误，
+//! 用 [note] 打印的提示同样不显示。
+//! 同时在打包输出目录的 `logs/` 下按天记录完整日志，保留最近 7 天，报告转换失败时可附上日志。
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::{format, FmtContext, FormatEvent, FormatFields},
+    prelude::*,
+    registry::LookupSpan,
+};
+
+use crate::i18n::t;
+
+pub const LOG_DIR: &str = "logs";
+const MAX_LOG_FILES: usize = 7;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// 在标准输出打印提示，`--quiet` 时不打印
+///
+/// 查询结果和确认前列出的内容仍使用 `println!`。
+macro_rules! note {
+    ($($arg:tt)*) => {
+        if !$crate::logging::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use note;
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// 控制台的输出级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    fn level(self) -> LevelFilter {
+        match self {
+            Verbosity::Quiet => LevelFilter::WARN,
+            Verbosity::Normal => LevelFilter::INFO,
+            Verbosity::Verbose => LevelFilter::DEBUG,
+        }
+    }
+}
+
+/// 控制台格式，与此前的提示保持一致，只在警告和错误前加上前缀
+struct ConsoleFormat;
+
+impl<S, N> FormatEvent<S, N> for ConsoleFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        match *event.metadata().level() {
+            Level::ERROR => write!(writer, "{}", t!("错误："))?,
+            Level::WARN => write!(writer, "{}", t!("警告："))?,
+            Level::INFO => {}
+            level => write!(writer, "[{}] ", level)?,
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// 首次写入日志时才创建日志目录，没有日志输出时不在输出目录留下任何文件
+///
+/// 无法创建日志文件时提示一次，之后的日志只输出到控制台。
+struct LazyAppender {
+    log_dir: PathBuf,
+    appender: Option<RollingFileAppender>,
+    failed: bool,
+}
+
+impl LazyAppender {
+    fn new(log_dir: PathBuf) -> Self {
+        Self {
+            log_dir,
+            appender: None,
+            failed: false,
+        }
+    }
+}
+
+impl Write for LazyAppender {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.appender.is_none() && !self.failed {
+            // 目录不存在时清理旧日志会输出错误，先创建目录
+            let appender = std::fs::create_dir_all(&self.log_dir)
+                .map_err(|e| e.to_string())
+                .and_then(|_| {
+                    RollingFileAppender::builder()
+                        .rotation(Rotation::DAILY)
+                        .filename_prefix(env!("CARGO_PKG_NAME"))
+                        .filename_suffix("log")
+                        .max_log_files(MAX_LOG_FILES)
+                        .build(&self.log_dir)
+                        .map_err(|e| e.to_string())
+                });
+            match appender {
+                Ok(appender) => self.appender = Some(appender),
+                Err(e) => {
+                    // 在写日志的线程中，不能再通过 tracing 输出
+                    eprintln!("{}{}", t!("警告："), t!("无法创建日志文件：{}", e));
+                    self.failed = true;
+                }
+            }
+        }
+        match &mut self.appender {
+            Some(appender) => appender.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.appender {
+            Some(appender) => appender.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 初始化日志，返回值需要保持到程序退出，否则日志文件中缺少最后的内容
+pub fn init<P: AsRef<Path>>(verbosity: Verbosity, dist_dir: P) -> WorkerGuard {
+    QUIET.store(verbosity == Verbosity::Quiet, Ordering::Relaxed);
+    let console = tracing_subscriber::fmt::layer()
+        .event_format(ConsoleFormat)
+        .with_writer(std::io::stderr)
+        .with_filter(verbosity.level());
+
+    let appender = LazyAppender::new(dist_dir.as_ref().join(LOG_DIR));
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let file = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(writer)
+        .with_filter(LevelFilter::DEBUG);
+    tracing_subscriber::registry()
+        .with(console)
+        .with(file)
+        .init();
+
+    guard
+}
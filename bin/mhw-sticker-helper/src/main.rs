@@ -1,26 +1,150 @@
 use std::{
     fmt::Display,
-    fs::OpenOptions,
-    io::{Cursor, Write},
-    path::Path,
+    io::{Cursor, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use dialoguer::{theme::ColorfulTheme, Input, Select};
-use image::DynamicImage;
-use workspace::{StickerPackType, Workspace};
-use zip::{write::SimpleFileOptions, ZipWriter};
+use clap::FromArgMatches;
+use cli::{Cli, Command};
+use config::Compression;
+use convert::FileKind;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use error_code::{ErrorCode, ErrorReport};
+use game::TargetGame;
+use i18n::t;
+use image::{ImageFormat, RgbaImage};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use install::InstallManifest;
+use lock::WorkspaceLock;
+use logging::{note, Verbosity};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use output::{ModInfo, OutputProfile, Package, PackageRecord, PackagedFile, PackagingSettings};
+use picker::Picker;
+use tex_convert::{options::ConvertOptions, spec::TexHeader};
+use tracing::{debug, error, info, warn};
+use trash::{Trash, TRASH_DIR};
+use workspace::{
+    ImageSlot, StickerMetadata, StickerPack, StickerPackType, Workspace, WorkspaceInfo,
+    DEFAULT_IMAGE_PATTERN,
+};
 
+mod archive;
 mod asset;
+mod batch;
+mod checksum;
+mod cli;
+mod color;
+mod compare;
+mod config;
+mod convert;
+mod doctor;
+mod error_code;
+mod game;
+mod git;
+mod i18n;
+mod install;
+mod lock;
+mod logging;
+mod migration;
+mod names;
+mod output;
+mod picker;
+mod preview;
+mod release;
+mod snapshot;
+mod steam;
+mod template;
+mod trash;
+mod update;
 mod util;
+mod validation;
 mod workspace;
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
+    // 帮助信息需要在解析参数前确定语言，配置文件的错误留到读取全局配置时提示
+    i18n::init(config::language().as_deref());
+    let cli = cli::command()
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| {
+            // 帮助和版本信息使用 clap 的默认处理，参数错误使用统一的退出码
+            if !e.use_stderr() {
+                e.exit();
+            }
+            if std::env::args().any(|arg| arg == "--json") {
+                print_error_report(&e.into());
+            } else {
+                let _ = e.print();
+            }
+            std::process::exit(ErrorCode::Usage.exit_code().into());
+        });
+    let verbosity = if cli.verbose {
+        Verbosity::Verbose
+    } else if cli.quiet {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    };
+    let _log_guard = logging::init(verbosity, config::current_dist_dir());
+    // 启动时读取配置，格式错误时立即提示
+    i18n::init(config::global().language.as_deref());
+    if let Some(command) = cli.command {
+        let Err(e) = App::run_command(command) else {
+            return ExitCode::SUCCESS;
+        };
+        if cli.json {
+            print_error_report(&e);
+        }
+        error!("{:#}", e);
+        return ExitCode::from(ErrorCode::of(&e).exit_code());
+    }
+    // 拖放启动时窗口在程序退出后立即关闭，转换后等待按键
+    if let Some(file) = cli.file {
+        // 不是已存在的文件时多半是拼错了子命令，不作为拖放处理
+        let result = if file.is_file() {
+            App::quick_convert(&file)
+        } else {
+            Err(ErrorCode::Usage
+                .with(t!("未知的子命令或文件不存在：{}", file.display()))
+                .into())
+        };
+        match &result {
+            Ok(output) => info!("{}", t!("已转换：{}", output.display())),
+            Err(e) => {
+                if cli.json {
+                    print_error_report(e);
+                }
+                error!("{:#}", e);
+            }
+        }
+        if file.is_file() {
+            App::pause();
+        }
+        return match result {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(e) => ExitCode::from(ErrorCode::of(&e).exit_code()),
+        };
+    }
+
     let mut app = App::new();
     if let Err(e) = app.run() {
-        eprintln!("{:#}", e);
+        error!("{:#}", e);
     };
 
-    Ok(())
+    ExitCode::SUCCESS
+}
+
+/// 在标准输出打印 JSON 格式的错误信息，见 [error_code]
+fn print_error_report(error: &anyhow::Error) {
+    if let Ok(json) = serde_json::to_string(&ErrorReport::new(error)) {
+        println!("{}", json);
+    }
 }
 
 enum AppState {
@@ -30,6 +154,9 @@ enum AppState {
     Exit,
 }
 
+/// 要打包的贴纸，以及合成图块后的图集
+type PackagedSticker = (StickerPack, Option<RgbaImage>);
+
 struct App {
     state: AppState,
 }
@@ -42,7 +169,10 @@ impl App {
     }
 
     pub fn run(&mut self) -> anyhow::Result<()> {
-        println!("MHW 贴纸助手 v{}", env!("CARGO_PKG_VERSION"));
+        println!("{}", t!("MHW 贴纸助手 v{}", env!("CARGO_PKG_VERSION")));
+        if config::global().check_updates() {
+            Self::check_updates();
+        }
         loop {
             match self.state {
                 AppState::Enter => self.show_main_menu()?,
@@ -51,11 +181,138 @@ impl App {
         }
     }
 
+    /// 有新版本时提示，检查失败只记录日志
+    fn check_updates() {
+        match update::check() {
+            Ok(Some(release)) => info!(
+                "{}",
+                t!(
+                    "发现新版本 {}，下载地址：{}",
+                    release.tag_name,
+                    release.html_url
+                )
+            ),
+            Ok(None) => {}
+            Err(e) => debug!("{}", t!("检查更新失败：{:#}", e)),
+        }
+    }
+
+    /// 执行命令行子命令，不进入交互式菜单
+    fn run_command(command: Command) -> anyhow::Result<()> {
+        match command {
+            Command::Package {
+                workspace,
+                only,
+                dry_run,
+            } => {
+                let (_lock, mut workspace) = Self::open_locked(&std::path::absolute(workspace)?)?;
+                let mut stickers = Self::collect_packaged_stickers(&workspace)?;
+                if !only.is_empty() {
+                    if let Some(name) = only.iter().find(|name| {
+                        !workspace
+                            .info()
+                            .sticker_packs()
+                            .iter()
+                            .any(|sticker| &sticker.name == *name)
+                    }) {
+                        anyhow::bail!(ErrorCode::Usage.with(t!("工作区中没有该贴纸：{}", name)));
+                    }
+                    stickers.retain(|(sticker, _)| only.contains(&sticker.name));
+                }
+                if dry_run {
+                    Self::preview_package(&workspace, &stickers)?;
+                } else {
+                    Self::package_stickers(&mut workspace, stickers, false)?;
+                }
+            }
+            Command::Export { workspace, output } => {
+                let (_lock, workspace) = Self::open_locked(&std::path::absolute(workspace)?)?;
+                let output = match output {
+                    Some(output) => output,
+                    None => Self::default_archive_path(&workspace),
+                };
+                let count = archive::export(&workspace, &output)?;
+                info!("{}", t!("已导出 {} 个文件：{}", count, output.display()));
+            }
+            Command::Import { archive, target } => {
+                let target = match target {
+                    Some(target) => target,
+                    None => PathBuf::from(archive::workspace_name(&archive)?),
+                };
+                let workspace = archive::import(&archive, std::path::absolute(target)?)?;
+                info!("{}", t!("已导入工作区：{}", workspace.root_path()));
+            }
+            Command::PackageAll => {
+                Self::bail_on_failures(Self::package_all()?, ErrorCode::PartialFailure)?
+            }
+            Command::ValidateAll => {
+                Self::bail_on_failures(Self::validate_all()?, ErrorCode::CheckFailed)?
+            }
+            Command::Doctor { workspace } => {
+                let workspace = workspace.map(std::path::absolute).transpose()?;
+                // 目录不存在时由诊断报告，不需要锁定
+                let _lock = workspace
+                    .as_deref()
+                    .filter(|path| path.is_dir())
+                    .map(WorkspaceLock::acquire)
+                    .transpose()?;
+                let failed = Self::doctor(workspace.as_deref());
+                if failed > 0 {
+                    anyhow::bail!(ErrorCode::CheckFailed.with(t!("发现 {} 个问题", failed)));
+                }
+            }
+            Command::UndoPackage { workspace } => {
+                let (_lock, workspace) = Self::open_locked(&std::path::absolute(workspace)?)?;
+                Self::undo_package(&workspace)?;
+            }
+            Command::Uninstall { workspace } => {
+                let (_lock, workspace) = Self::open_locked(&std::path::absolute(workspace)?)?;
+                Self::uninstall(&workspace)?;
+            }
+            Command::GameDir { path: Some(path) } => {
+                let path = std::path::absolute(path)?;
+                let exe_name = TargetGame::default().exe_name();
+                if !path.join(exe_name).exists() {
+                    warn!("{}", t!("目录中没有 {}：{}", exe_name, path.display()));
+                }
+                let config_path = config::save_game_dir(&path)?;
+                info!("{}", t!("已保存游戏目录到 {}", config_path.display()));
+            }
+            Command::Convert { input, output, to } => Self::convert_files(&input, &output, to)?,
+            Command::Inspect { file } => Self::inspect_tex(&file)?,
+            Command::Completions { shell } => {
+                clap_complete::generate(
+                    shell,
+                    &mut cli::command(),
+                    env!("CARGO_PKG_NAME"),
+                    &mut std::io::stdout(),
+                );
+            }
+            Command::GameDir { path: None } => {
+                let game_dir = config::global()
+                    .game_dir(TargetGame::default())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(t!("未找到游戏目录，请使用 game-dir <目录> 手动指定"))
+                    })?;
+                println!("{}", game_dir.display());
+            }
+        }
+
+        Ok(())
+    }
+
     fn show_main_menu(&mut self) -> anyhow::Result<()> {
         let selection = MainSelection::show_interact()?;
         match selection {
             MainSelection::NewWorkspace => self.show_new_workspace()?,
             MainSelection::OpenWorkspace => self.show_open_workspace()?,
+            MainSelection::ImportWorkspace => self.show_import_workspace()?,
+            MainSelection::PackageAll => {
+                Self::package_all()?;
+            }
+            MainSelection::ValidateAll => {
+                Self::validate_all()?;
+            }
             MainSelection::Exit => self.state = AppState::Exit,
         };
 
@@ -66,19 +323,44 @@ impl App {
         let workspace_name: String = Input::with_theme(&ColorfulTheme::default())
             .with_initial_text("example")
             .allow_empty(false)
-            .with_prompt("请输入工作区名称： (将会在当前目录下建立工作区目录)")
+            .with_prompt(t!("请输入工作区名称： (将会在当前目录下建立工作区目录)"))
             .interact_text()?;
 
-        let workspace_mode = WorkspaceModeSelection::show_interact()?;
+        let workspace_mode = WorkspaceModeSelection::show_interact(
+            config::global()
+                .image_format
+                .unwrap_or(StickerPackType::Dds),
+        )?;
+        let target_game = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请选择目标游戏： (按↑↓选择，Enter确认)"))
+            .items(&TargetGame::ALL)
+            .default(0)
+            .interact()?;
+
+        let init_git = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("是否初始化 git 仓库？"))
+            .default(false)
+            .interact()?;
 
         let path = Path::new(&workspace_name);
-        if let Err(e) = Workspace::create_new(path, workspace_mode.into()) {
-            eprintln!("创建工作区失败：{}", e);
+        if let Err(e) =
+            Workspace::create_new(path, workspace_mode.into(), TargetGame::ALL[target_game])
+        {
+            error!("{}", t!("创建工作区失败：{}", e));
             return Ok(());
         };
+        if init_git {
+            match git::init(path) {
+                Ok(()) => info!("{}", t!("已初始化 git 仓库")),
+                Err(e) => error!("{}", t!("初始化 git 仓库失败：{:#}", e)),
+            }
+        }
 
-        println!("工作区创建成功！");
-        println!("目录：{}", std::env::current_dir()?.join(path).display());
+        info!("{}", t!("工作区创建成功！"));
+        info!(
+            "{}",
+            t!("目录：{}", std::env::current_dir()?.join(path).display())
+        );
 
         Ok(())
     }
@@ -87,17 +369,37 @@ impl App {
         // 读取所有工作区
         let workspaces = Workspace::list_all_workspaces()?;
         if workspaces.is_empty() {
-            println!("没有可用的工作区！");
+            println!("{}", t!("没有可用的工作区！"));
             return Ok(());
         };
 
         // 选择工作区
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("请选择工作区： (按↑↓选择，Enter确认)")
-            .items(&workspaces)
-            .default(0)
-            .interact()?;
-        let mut workspace = workspaces[selection].clone();
+        let selection =
+            Picker::new(t!("请选择工作区： (按↑↓选择，Enter确认)"), &workspaces).interact()?;
+        // 退出工作区菜单前一直持有锁，避免多个程序同时修改 workspace.json
+        // 列表中的数据可能已过期，锁定后重新读取
+        let (_lock, mut workspace) =
+            match Self::open_locked(Path::new(workspaces[selection].root_path())) {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("{:#}", e);
+                    return Ok(());
+                }
+            };
+
+        // 用户直接放入目录的图片不会被打包，打开时提示登记
+        let unknown_images = workspace.unknown_images(DEFAULT_IMAGE_PATTERN)?;
+        if !unknown_images.is_empty()
+            && Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!(
+                    "发现 {} 个未登记的图片，是否现在登记？",
+                    unknown_images.len()
+                ))
+                .default(true)
+                .interact()?
+        {
+            Self::show_register_images(&workspace, DEFAULT_IMAGE_PATTERN)?;
+        }
 
         // 进入工作区操作
         self.show_workspace_menu(&mut workspace)?;
@@ -114,22 +416,164 @@ impl App {
                 WorkspaceSelection::Info => {
                     let modified_stickers = workspace.get_modified_stickers()?;
 
-                    println!("工作区信息：");
-                    println!("版本：{}", workspace.info().version());
-                    println!("路径：{}", workspace.root_path());
-                    println!("贴纸包数量：{}", workspace.info().sticker_packs().len());
-                    println!("已更改贴纸包数量：{}", modified_stickers.len());
+                    println!("{}", t!("工作区信息："));
+                    println!("{}", t!("版本：{}", workspace.info().version()));
+                    println!("{}", t!("目标游戏：{}", workspace.info().target_game()));
+                    println!("{}", t!("路径：{}", workspace.root_path()));
+                    println!(
+                        "{}",
+                        t!("贴纸包数量：{}", workspace.info().sticker_packs().len())
+                    );
+                    println!("{}", t!("已更改贴纸包数量：{}", modified_stickers.len()));
+                    println!("{}", t!("输出方式：{}", workspace.info().output()));
+
+                    let ignored = workspace
+                        .info()
+                        .sticker_packs()
+                        .iter()
+                        .filter(|sticker| sticker.ignored)
+                        .collect::<Vec<_>>();
+                    if !ignored.is_empty() {
+                        let names = workspace.names();
+                        println!("{}", t!("已忽略贴纸包："));
+                        for sticker in ignored {
+                            println!("  - {}", names.display(&sticker.name));
+                        }
+                    }
 
                     if !modified_stickers.is_empty() {
-                        println!("已更改贴纸包：");
+                        let names = workspace.names();
+                        println!("{}", t!("已更改贴纸包："));
                         for sticker in modified_stickers {
-                            println!("  - {}/{}", sticker.name, sticker.filename);
+                            println!("  - {}/{}", names.display(&sticker.name), sticker.filename);
+                        }
+                    }
+                }
+                WorkspaceSelection::GitStatus => {
+                    if !git::is_repository(workspace.root_path()) {
+                        println!("{}", t!("工作区不是 git 仓库，可在创建工作区时选择初始化"));
+                    } else {
+                        let status = git::status(workspace.root_path())?;
+                        if status.is_empty() {
+                            println!("{}", t!("贴纸文件没有未提交的更改"));
+                        } else {
+                            print!("{}", status);
                         }
                     }
                 }
                 WorkspaceSelection::Package => {
-                    Self::package_modified_stickers(workspace)?;
-                    println!("打包完成！");
+                    if Self::show_package(workspace)? {
+                        info!("{}", t!("打包完成！"));
+                    }
+                }
+                WorkspaceSelection::PackagePreview => {
+                    if let Some(stickers) = Self::select_packaged_stickers(workspace)? {
+                        Self::preview_package(workspace, &stickers)?;
+                    }
+                }
+                WorkspaceSelection::NexusRelease => Self::show_nexus_release(workspace)?,
+                WorkspaceSelection::Uninstall => Self::show_uninstall(workspace)?,
+                WorkspaceSelection::Validate => {
+                    let files = validation::validate_workspace(workspace)?;
+                    if files.is_empty() {
+                        println!("{}", t!("没有发现问题"));
+                    }
+                    Self::print_problems(&files);
+                }
+                WorkspaceSelection::Output => Self::show_output_settings(workspace)?,
+                WorkspaceSelection::Packaging => Self::show_packaging_settings(workspace)?,
+                WorkspaceSelection::RestoreOutput => Self::show_restore_output(workspace)?,
+                WorkspaceSelection::UndoPackage => Self::show_undo_package(workspace)?,
+                WorkspaceSelection::Compare => {
+                    let diffs = compare::compare_modified(workspace)?;
+                    let names = workspace.names();
+                    if diffs.is_empty() {
+                        println!("{}", t!("没有可比较的已更改贴纸"));
+                    }
+                    for diff in diffs {
+                        let report = &diff.report;
+                        println!(
+                            "{}",
+                            t!(
+                                "{}：{}/{} 像素不同，最大差值 RGBA {}",
+                                names.display(&diff.name),
+                                report.differing_pixels,
+                                report.total_pixels,
+                                format!("{:?}", report.max_delta)
+                            )
+                        );
+                        println!("{}", t!("  对比图：{}", diff.image_path.display()));
+                    }
+                }
+                WorkspaceSelection::Preview => {
+                    let html_path = preview::write_preview(workspace)?;
+                    info!("{}", t!("预览页面已生成：{}", html_path.display()));
+                }
+                WorkspaceSelection::Templates => {
+                    let template_dir = template::write_templates(workspace)?;
+                    info!("{}", t!("编辑模板已导出：{}", template_dir.display()));
+                }
+                WorkspaceSelection::Watch => Self::watch_and_package(workspace)?,
+                WorkspaceSelection::Metadata => Self::show_edit_metadata(workspace)?,
+                WorkspaceSelection::ModInfo => Self::show_edit_mod_info(workspace)?,
+                WorkspaceSelection::Ignore => Self::show_ignore_list(workspace)?,
+                WorkspaceSelection::Snapshot => {
+                    let label: String = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt(t!("请输入快照名称："))
+                        .with_initial_text("snapshot")
+                        .interact_text()?;
+                    let snapshot = workspace.snapshot(&label)?;
+                    info!("{}", t!("已创建快照：{}", snapshot));
+                }
+                WorkspaceSelection::Rollback => Self::show_rollback(workspace)?,
+                WorkspaceSelection::Import => Self::show_import_tex(workspace)?,
+                WorkspaceSelection::Restore => Self::show_restore_sticker(workspace)?,
+                WorkspaceSelection::Register => {
+                    let pattern: String = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt(t!("请输入要扫描的文件名： (* 匹配任意多个字符)"))
+                        .with_initial_text(DEFAULT_IMAGE_PATTERN)
+                        .interact_text()?;
+                    Self::show_register_images(workspace, pattern.trim())?;
+                }
+                WorkspaceSelection::Repair => {
+                    let repaired = workspace.repair()?;
+                    if repaired.is_empty() {
+                        println!("{}", t!("没有缺失的文件"));
+                    } else {
+                        println!("{}", t!("已重新生成 {} 个缺失的文件：", repaired.len()));
+                        for name in repaired {
+                            println!("  - {}", name);
+                        }
+                    }
+                }
+                WorkspaceSelection::Doctor => {
+                    Self::doctor(Some(Path::new(workspace.root_path())));
+                }
+                WorkspaceSelection::Clone => {
+                    let name: String = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt(t!("请输入新工作区名称："))
+                        .allow_empty(false)
+                        .interact_text()?;
+                    let path = Path::new(workspace.root_path())
+                        .parent()
+                        .unwrap()
+                        .join(name.trim());
+                    match workspace.clone_to(&path) {
+                        Ok(clone) => info!("{}", t!("已复制工作区：{}", clone.root_path())),
+                        Err(e) => error!("{}", t!("复制工作区失败：{:#}", e)),
+                    }
+                }
+                WorkspaceSelection::Export => {
+                    let path = Self::default_archive_path(workspace);
+                    match archive::export(workspace, &path) {
+                        Ok(count) => info!("{}", t!("已导出 {} 个文件：{}", count, path.display())),
+                        Err(e) => error!("{}", t!("导出工作区失败：{:#}", e)),
+                    }
+                }
+                WorkspaceSelection::Delete => {
+                    if Self::show_delete_workspace(workspace)? {
+                        rerun = false;
+                    }
                 }
                 WorkspaceSelection::Back => {
                     rerun = false;
@@ -140,94 +584,1461 @@ impl App {
         Ok(())
     }
 
-    fn package_modified_stickers(workspace: &mut Workspace) -> anyhow::Result<()> {
-        let modified_stickers = workspace.get_modified_stickers()?;
+    /// 单文件工作区的默认路径，与工作区目录同级
+    fn default_archive_path(workspace: &Workspace) -> PathBuf {
+        let root_path = Path::new(workspace.root_path());
+        root_path.with_file_name(format!(
+            "{}.{}",
+            root_path.file_name().unwrap_or_default().to_string_lossy(),
+            archive::EXTENSION
+        ))
+    }
+
+    /// 导入单文件工作区到当前目录
+    fn show_import_workspace(&mut self) -> anyhow::Result<()> {
+        let path: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请输入单文件工作区 (.mhwsws) 的路径："))
+            .allow_empty(false)
+            .interact_text()?;
+        // 从资源管理器复制的路径可能带有引号
+        let path = Path::new(path.trim().trim_matches('"'));
+        let default_name = match archive::workspace_name(path) {
+            Ok(name) => name,
+            Err(e) => {
+                error!("{}", t!("读取单文件工作区失败：{:#}", e));
+                return Ok(());
+            }
+        };
+        let name: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请输入工作区名称： (将会在当前目录下建立工作区目录)"))
+            .with_initial_text(default_name)
+            .allow_empty(false)
+            .interact_text()?;
+
+        match archive::import(path, std::env::current_dir()?.join(name.trim())) {
+            Ok(workspace) => info!("{}", t!("已导入工作区：{}", workspace.root_path())),
+            Err(e) => error!("{}", t!("导入工作区失败：{:#}", e)),
+        }
+
+        Ok(())
+    }
+
+    /// 监视工作区目录，贴纸文件变化时自动重新打包，按 Enter 停止
+    fn watch_and_package(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let (tx, rx) = mpsc::channel();
+        // 编辑器保存文件时通常连续触发多个事件，合并后再打包
+        let mut debouncer = new_debouncer(Duration::from_millis(500), tx)?;
+        debouncer.watcher().watch(
+            Path::new(workspace.root_path()),
+            RecursiveMode::NonRecursive,
+        )?;
+        let overlay_dir = Path::new(workspace.root_path()).join(workspace::OVERLAY_DIR);
+        if overlay_dir.is_dir() {
+            debouncer
+                .watcher()
+                .watch(&overlay_dir, RecursiveMode::NonRecursive)?;
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let reader = std::thread::spawn({
+            let stopped = stopped.clone();
+            move || {
+                let _ = std::io::stdin().read_line(&mut String::new());
+                stopped.store(true, Ordering::Relaxed);
+            }
+        });
+        println!("{}", t!("正在监视工作区：{}", workspace.root_path()));
+        println!("{}", t!("保存贴纸后将自动重新打包，按 Enter 停止监视"));
+
+        let mut result = Ok(());
+        while !stopped.load(Ordering::Relaxed) {
+            let events = match rx.recv_timeout(Duration::from_millis(200)) {
+                Ok(Ok(events)) => events,
+                Ok(Err(e)) => {
+                    result = Err(e);
+                    break;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            let mut changed = events
+                .iter()
+                .filter(|event| {
+                    matches!(
+                        event.path.extension().and_then(|ext| ext.to_str()),
+                        Some("png" | "dds" | "tga")
+                    )
+                })
+                .filter_map(|event| event.path.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect::<Vec<_>>();
+            if changed.is_empty() {
+                continue;
+            }
+            changed.sort();
+            changed.dedup();
+
+            info!("{}", t!("检测到更改：{}", changed.join(", ")));
+            match Self::package_modified_stickers(workspace) {
+                Ok(true) => info!("{}", t!("重新打包完成！")),
+                Ok(false) => {}
+                Err(e) => error!("{}", t!("打包失败：{:#}", e)),
+            }
+        }
+        // 读取标准输入的线程无法取消，监视中断时等它读完这一行，以免吞掉菜单的下一次输入
+        if !stopped.load(Ordering::Relaxed) {
+            println!("{}", t!("监视已中断，按 Enter 返回"));
+        }
+        let _ = reader.join();
+        result?;
+        info!("{}", t!("已停止监视"));
+
+        Ok(())
+    }
+
+    /// 选择已更改的贴纸并还原为原版
+    fn show_restore_sticker(workspace: &mut Workspace) -> anyhow::Result<()> {
+        // 导入的贴纸没有原版可以还原
+        let modified_stickers = workspace
+            .get_modified_stickers()?
+            .into_iter()
+            .filter(|sticker| !sticker.imported)
+            .collect::<Vec<_>>();
         if modified_stickers.is_empty() {
-            eprintln!("没有发现需要打包的贴纸");
+            println!("{}", t!("没有已更改的贴纸"));
+            return Ok(());
+        }
+
+        let labels = workspace.names();
+        let names = modified_stickers
+            .iter()
+            .map(|sticker| format!("{}/{}", labels.display(&sticker.name), sticker.filename))
+            .collect::<Vec<_>>();
+        let selection = Picker::new(t!("请选择要还原的贴纸： (按↑↓选择，Enter确认)"), &names)
+            .tags(Self::sticker_tags(&modified_stickers))
+            .interact()?;
+        let sticker = &modified_stickers[selection];
+
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "确定要还原 {} 吗？对该贴纸的修改将丢失",
+                labels.display(&sticker.name)
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        workspace.restore_sticker(&sticker.name)?;
+        info!("{}", t!("已还原：{}", sticker.name));
+
+        Ok(())
+    }
+
+    /// 逐个登记文件名匹配 `pattern` 的未登记图片，选择替换的贴纸和位置
+    fn show_register_images(workspace: &Workspace, pattern: &str) -> anyhow::Result<()> {
+        let images = workspace.unknown_images(pattern)?;
+        if images.is_empty() {
+            println!("{}", t!("没有未登记的图片"));
             return Ok(());
         }
 
+        let stickers = workspace.info().sticker_packs();
+        let labels = workspace.names();
+        let names = stickers
+            .iter()
+            .map(|sticker| labels.display(&sticker.name))
+            .collect::<Vec<_>>();
+        let modified = Self::modified_flags(workspace)?;
+        for filename in images {
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("是否登记 {}？", filename))
+                .default(true)
+                .interact()?;
+            if !confirmed {
+                continue;
+            }
+
+            // 文件名以贴纸名称开头时默认选中该贴纸
+            let default_sticker = stickers
+                .iter()
+                .position(|sticker| filename.starts_with(&sticker.name))
+                .unwrap_or_default();
+            let selection = Picker::new(t!("请选择要替换的贴纸： (按↑↓选择，Enter确认)"), &names)
+                .default(default_sticker)
+                .modified(modified.clone())
+                .tags(Self::sticker_tags(stickers))
+                .interact()?;
+            let sticker = &stickers[selection];
+
+            let slots = std::iter::once(ImageSlot::Sheet)
+                .chain((0..sticker.layout.tile_count()).map(ImageSlot::Tile))
+                .collect::<Vec<_>>();
+            let default_slot = ImageSlot::guess(&filename)
+                .and_then(|slot| slots.iter().position(|s| *s == slot))
+                .unwrap_or_default();
+            let selection = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("请选择替换的位置： (按↑↓选择，Enter确认)"))
+                .items(&slots)
+                .default(default_slot)
+                .interact()?;
+            let slot = slots[selection];
+
+            let replaces_tile = workspace
+                .tile_files(sticker)
+                .iter()
+                .any(|(index, _)| slot == ImageSlot::Tile(*index));
+            if replaces_tile
+                && !Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(t!("{} 已有图块文件，是否覆盖？", slot))
+                    .default(false)
+                    .interact()?
+            {
+                continue;
+            }
+
+            match workspace.register_image(&filename, &sticker.name, slot) {
+                Ok(path) => info!("{}", t!("已登记：{} -> {}", filename, path.display())),
+                Err(e) => error!("{}", t!("登记失败：{:#}", e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 确认后删除工作区，可先备份已更改的贴纸，返回是否已删除
+    fn show_delete_workspace(workspace: &Workspace) -> anyhow::Result<bool> {
         let root_path = Path::new(workspace.root_path());
-        let dist_dir = root_path.parent().unwrap().join("dist");
-        let workspace_name = Path::new(workspace.root_path())
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap();
-        let output_dir = dist_dir.join(workspace_name);
-        println!("输出目录：{}", output_dir.display());
-        if !output_dir.exists() {
-            std::fs::create_dir_all(&output_dir)?;
-        }
-
-        // 创建zip文件
-        let zip_path = dist_dir.join(format!("{}.zip", workspace_name));
-        let zip_file = OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(&zip_path)?;
-        let mut zip_writer = ZipWriter::new(zip_file);
-        println!("导出 MOD 包：{}", zip_path.display());
-
-        for sticker in modified_stickers {
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "确定要删除工作区 {} 吗？该目录下的所有文件都将被删除",
+                root_path.display()
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Ok(false);
+        }
+
+        // 删除后安装记录和备份随之丢失
+        if InstallManifest::load(root_path)?.is_some() {
+            let uninstall = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("工作区已安装到游戏目录，是否先卸载？"))
+                .default(true)
+                .interact()?;
+            if uninstall {
+                Self::uninstall(workspace)?;
+            }
+        }
+
+        let backup = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("删除前是否备份已更改的贴纸？"))
+            .default(true)
+            .interact()?;
+        if backup {
+            let workspace_name = root_path.file_name().unwrap().to_string_lossy();
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let backup_path = root_path
+                .parent()
+                .unwrap()
+                .join("backup")
+                .join(format!("{}_{}.zip", workspace_name, timestamp));
+            let count = workspace.backup_modified(&backup_path)?;
+            info!(
+                "{}",
+                t!("已备份 {} 个贴纸：{}", count, backup_path.display())
+            );
+        }
+
+        workspace.clone().delete()?;
+        info!("{}", t!("工作区已删除"));
+
+        Ok(true)
+    }
+
+    fn package_modified_stickers(workspace: &mut Workspace) -> anyhow::Result<bool> {
+        let stickers = Self::collect_packaged_stickers(workspace)?;
+        Self::package_stickers(workspace, stickers, false)
+    }
+
+    /// 打包已更改的贴纸，返回是否进行了打包
+    ///
+    /// 第一次打包时先填写 MOD 信息，之后沿用工作区中保存的信息。
+    fn show_package(workspace: &mut Workspace) -> anyhow::Result<bool> {
+        if workspace.info().mod_info().is_none() {
+            Self::show_edit_mod_info(workspace)?;
+        }
+        match Self::select_packaged_stickers(workspace)? {
+            Some(stickers) => Self::package_stickers(workspace, stickers, true),
+            None => Ok(false),
+        }
+    }
+
+    /// 勾选要打包的贴纸，默认全选，用户取消了所有勾选时返回 None
+    ///
+    /// 每一项标出是整张图集被更改还是单独编辑了图块。没有需要打包的贴纸时直接返回空列表，
+    /// 由打包和预览给出提示。
+    fn select_packaged_stickers(
+        workspace: &Workspace,
+    ) -> anyhow::Result<Option<Vec<PackagedSticker>>> {
+        let stickers = Self::collect_packaged_stickers(workspace)?;
+        if stickers.len() <= 1 {
+            return Ok(Some(stickers));
+        }
+
+        let labels = workspace.names();
+        let items = stickers
+            .iter()
+            .map(|(sticker, composed)| {
+                let status = if composed.is_some() {
+                    t!("已编辑图块")
+                } else {
+                    t!("图集已更改")
+                };
+                format!("{} [{}]", labels.display(&sticker.name), status)
+            })
+            .collect::<Vec<_>>();
+        let selections = Picker::new(
+            t!("请选择要打包的贴纸： (按空格切换，A 全选/取消全选，Enter确认)"),
+            &items,
+        )
+        .tags(
+            stickers
+                .iter()
+                .map(|(sticker, _)| sticker.metadata.tags.clone())
+                .collect(),
+        )
+        .interact_multi(&vec![true; items.len()])?;
+        if selections.is_empty() {
+            warn!("{}", t!("没有选择任何贴纸"));
+            return Ok(None);
+        }
+
+        Ok(Some(
+            stickers
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| selections.contains(index))
+                .map(|(_, sticker)| sticker)
+                .collect(),
+        ))
+    }
+
+    /// 需要打包的贴纸，以及合成图块后的图集
+    fn collect_packaged_stickers(workspace: &Workspace) -> anyhow::Result<Vec<PackagedSticker>> {
+        let modified_stickers = workspace.get_modified_stickers()?;
+        // 单独编辑的图块合成到图集后打包，图集本身未更改的贴纸同样需要打包
+        let mut stickers = vec![];
+        for sticker in workspace
+            .info()
+            .sticker_packs()
+            .iter()
+            .filter(|s| !s.ignored)
+        {
+            let modified = modified_stickers.iter().find(|s| s.name == sticker.name);
+            let composed =
+                workspace.compose_tiles(modified.unwrap_or(sticker), modified.is_some())?;
+            match (modified, composed) {
+                (Some(modified), composed) => stickers.push((modified.clone(), composed)),
+                (None, Some(composed)) => stickers.push((sticker.clone(), Some(composed))),
+                (None, None) => {}
+            }
+        }
+
+        Ok(stickers)
+    }
+
+    /// 转换并输出贴纸，返回是否进行了打包
+    ///
+    /// `offer_install` 为真且未设置安装目录时询问是否安装到找到的游戏目录。
+    fn package_stickers(
+        workspace: &mut Workspace,
+        stickers: Vec<PackagedSticker>,
+        offer_install: bool,
+    ) -> anyhow::Result<bool> {
+        if stickers.is_empty() {
+            warn!("{}", t!("没有发现需要打包的贴纸"));
+            return Ok(false);
+        }
+
+        let output = workspace.info().output().clone();
+        if output.is_empty() {
+            warn!("{}", t!("没有选择任何输出方式，请在输出设置中选择"));
+            return Ok(false);
+        }
+        let started = Instant::now();
+        let root_path = Path::new(workspace.root_path());
+        let config = workspace.config();
+        let dist_dir = workspace.dist_dir();
+
+        let info = workspace.info();
+        let mod_info = Self::mod_info(workspace);
+        let package_name = Self::package_name(workspace, &mod_info);
+        let readme = Self::readme(&mod_info, &stickers);
+        let files = Self::convert_stickers(workspace, &stickers)?;
+
+        let package = Package {
+            name: &package_name,
+            stamp_dir: info.packaging().archive_path(info.target_game()),
+            files: &files,
+            readme: Some(&readme),
+            mod_info: &mod_info,
+            compression: config.compression().into(),
+            compression_level: config.compression_level(),
+        };
+        let mut trash = Trash::new(dist_dir.join(TRASH_DIR))?;
+        let writing = ProgressBar::new_spinner()
+            .with_message(t!("正在写入输出…"))
+            .with_finish(ProgressFinish::AndClear);
+        writing.enable_steady_tick(Duration::from_millis(100));
+        let outputs = output.write(root_path, &dist_dir, &package, &mut trash);
+        writing.finish_and_clear();
+        if trash.len() > 0 {
+            info!("{}", t!("已将 {} 个被覆盖的文件移入回收站", trash.len()));
+        }
+        for path in outputs? {
+            info!("{}", t!("已输出：{}", path.display()));
+        }
+
+        let game_dir = match output.install_dir {
+            Some(install_dir) => Some(install_dir),
+            None if offer_install => Self::confirm_install(&config, info.target_game())?,
+            None => None,
+        };
+        if let Some(game_dir) = game_dir {
+            let installed = install::install(root_path, &game_dir, &package)?;
+            info!(
+                "{}",
+                t!("已安装 {} 个文件到 {}", installed.len(), game_dir.display())
+            );
+        }
+        info!(
+            "{}",
+            t!(
+                "共打包 {} 个贴纸，用时 {:.1} 秒",
+                files.len(),
+                started.elapsed().as_secs_f32()
+            )
+        );
+
+        Ok(true)
+    }
+
+    /// 打包当前目录下的所有工作区，返回失败的工作区数量
+    fn package_all() -> anyhow::Result<usize> {
+        Self::run_all(|workspace, name| {
+            let stickers = Self::collect_packaged_stickers(workspace)?;
+            if stickers.is_empty() {
+                return Ok(batch::Row::new(
+                    name,
+                    t!("跳过"),
+                    t!("没有发现需要打包的贴纸"),
+                ));
+            }
+            if workspace.info().output().is_empty() {
+                return Ok(batch::Row::new(
+                    name,
+                    t!("跳过"),
+                    t!("没有选择任何输出方式"),
+                ));
+            }
+            let count = stickers.len();
+            Self::package_stickers(workspace, stickers, false)?;
+            Ok(batch::Row::new(
+                name,
+                t!("成功"),
+                t!("已打包 {} 个贴纸", count),
+            ))
+        })
+    }
+
+    /// 检查当前目录下的所有工作区，返回失败或存在问题的工作区数量
+    fn validate_all() -> anyhow::Result<usize> {
+        Self::run_all(|workspace, name| {
+            let files = validation::validate_workspace(workspace)?;
+            if files.is_empty() {
+                return Ok(batch::Row::new(name, t!("通过"), t!("没有发现问题")));
+            }
+            Self::print_problems(&files);
+            Ok(batch::Row {
+                failed: true,
+                ..batch::Row::new(name, t!("有问题"), t!("{} 个文件存在问题", files.len()))
+            })
+        })
+    }
+
+    /// 对当前目录下的每个工作区执行 `operation` 并汇总结果，返回失败的工作区数量
+    fn run_all<F>(operation: F) -> anyhow::Result<usize>
+    where
+        F: Fn(&mut Workspace, &str) -> anyhow::Result<batch::Row>,
+    {
+        let paths = batch::discover()?;
+        if paths.is_empty() {
+            note!("{}", t!("没有可用的工作区！"));
+            return Ok(0);
+        }
+
+        let mut rows = vec![];
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            note!("{}", t!("==== {} ====", name));
+            let result = Self::open_locked(&path)
+                .and_then(|(_lock, mut workspace)| operation(&mut workspace, &name));
+            let row = result.unwrap_or_else(|e| {
+                error!("{:#}", e);
+                batch::Row::failed(&name, &e)
+            });
+            rows.push(row);
+        }
+        println!();
+        println!("{}", batch::summary_table(&rows));
+
+        Ok(rows.iter().filter(|row| row.failed).count())
+    }
+
+    /// 锁定并打开工作区，先锁定再读取，避免读到其他程序正在修改的 workspace.json
+    fn open_locked(path: &Path) -> anyhow::Result<(WorkspaceLock, Workspace)> {
+        // 不是工作区时由 open 报告错误，不在目录中留下锁文件
+        if !path.join("workspace.json").is_file() {
+            Workspace::open(path)?;
+        }
+        let lock = WorkspaceLock::acquire(path)?;
+        let workspace = Workspace::open(path)?;
+        Ok((lock, workspace))
+    }
+
+    fn bail_on_failures(failed: usize, code: ErrorCode) -> anyhow::Result<()> {
+        if failed > 0 {
+            anyhow::bail!(code.with(t!("{} 个工作区未成功完成", failed)));
+        }
+
+        Ok(())
+    }
+
+    fn print_problems(files: &[validation::FileProblems]) {
+        for file in files {
+            println!("{}：", file.file);
+            for problem in &file.problems {
+                println!("  - {}", problem);
+            }
+        }
+    }
+
+    /// 列出打包将生成的文件、包内路径和预计大小，不写入任何内容
+    fn preview_package(workspace: &Workspace, stickers: &[PackagedSticker]) -> anyhow::Result<()> {
+        if stickers.is_empty() {
+            warn!("{}", t!("没有发现需要打包的贴纸"));
+            return Ok(());
+        }
+        let output = workspace.info().output();
+        if output.is_empty() {
+            warn!("{}", t!("没有选择任何输出方式，请在输出设置中选择"));
+            return Ok(());
+        }
+
+        let config = workspace.config();
+        let mod_info = Self::mod_info(workspace);
+        let package_name = Self::package_name(workspace, &mod_info);
+        let readme = Self::readme(&mod_info, stickers);
+        let files = Self::convert_stickers(workspace, stickers)?;
+        let package = Package {
+            name: &package_name,
+            stamp_dir: workspace
+                .info()
+                .packaging()
+                .archive_path(workspace.info().target_game()),
+            files: &files,
+            readme: Some(&readme),
+            mod_info: &mod_info,
+            compression: config.compression().into(),
+            compression_level: config.compression_level(),
+        };
+
+        println!("{}", t!("将转换 {} 个贴纸：", files.len()));
+        for (sticker, _) in stickers {
+            println!("  - {} -> {}.tex", sticker.filename, sticker.name);
+        }
+        for planned in output.plan(workspace.dist_dir(), &package)? {
+            println!(
+                "{}",
+                t!(
+                    "{} (预计 {})：",
+                    planned.path.display(),
+                    util::format_size(planned.size)
+                )
+            );
+            for entry in planned.entries {
+                println!("  - {} ({})", entry.path, util::format_size(entry.size));
+            }
+        }
+        note!("{}", t!("预览完成，没有写入任何文件"));
+
+        Ok(())
+    }
+
+    /// 将贴纸转换为 tex 文件，`composed` 为合成图块后的图集
+    ///
+    /// 每个贴纸的编码可能需要数秒，转换时显示进度条和当前的贴纸。
+    fn convert_stickers(
+        workspace: &Workspace,
+        stickers: &[PackagedSticker],
+    ) -> anyhow::Result<Vec<PackagedFile>> {
+        let root_path = Path::new(workspace.root_path());
+        let info = workspace.info();
+        // 转换失败提前返回时同样清除进度条
+        let progress = ProgressBar::new(stickers.len() as u64)
+            .with_style(ProgressStyle::with_template(
+                "{spinner} {bar:30} {pos}/{len} [{elapsed}] {wide_msg}",
+            )?)
+            .with_finish(ProgressFinish::AndClear);
+        progress.enable_steady_tick(Duration::from_millis(100));
+        let mut files = vec![];
+        for (sticker, composed) in stickers {
+            progress.set_message(t!("正在转换：{}", sticker.name));
             let input_path = root_path.join(&sticker.filename);
-            let tex_data = match Path::new(&sticker.filename)
+            let extension = Path::new(&sticker.filename)
                 .extension()
                 .unwrap()
                 .to_str()
-                .unwrap()
-            {
-                "dds" => Self::convert_dds_sticker_to_tex(&input_path)?,
-                "png" => Self::convert_png_sticker_to_tex(&input_path)?,
-                _ => anyhow::bail!("不支持的文件后缀：{}", sticker.filename),
+                .unwrap();
+            let tex_data = match (composed, extension) {
+                (Some(image), _) => Self::convert_sticker_image_to_tex(image, sticker, info)?,
+                (None, "dds") => convert::dds_to_tex(std::fs::read(&input_path)?, &sticker.name)?,
+                (None, "png") => Self::convert_image_sticker_to_tex(
+                    &input_path,
+                    ImageFormat::Png,
+                    sticker,
+                    info,
+                )?,
+                (None, "tga") => Self::convert_image_sticker_to_tex(
+                    &input_path,
+                    ImageFormat::Tga,
+                    sticker,
+                    info,
+                )?,
+                _ => anyhow::bail!(
+                    ErrorCode::BadInput.with(t!("不支持的文件后缀：{}", sticker.filename))
+                ),
             };
+            progress.suspend(|| convert::validate_tex(&sticker.filename, &tex_data))?;
+            progress.suspend(|| info!("{}", t!("已转换：{}", sticker.name)));
+            progress.inc(1);
+            files.push(PackagedFile {
+                file_name: format!("{}.tex", sticker.name),
+                data: tex_data,
+            });
+        }
+        progress.finish_and_clear();
+
+        Ok(files)
+    }
 
-            let file_name = format!("{}.tex", sticker.name);
-            // 导出独立文件
-            let output_path = output_dir.join(&file_name);
-            let mut file = OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(&output_path)?;
-            file.write_all(&tex_data)?;
-            println!("导出文件：{}", output_path.display());
-            // 写入zip文件
-            zip_writer.start_file(
-                format!("nativePC/ui/chat/tex/stamp/{}", file_name),
-                SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated),
-            )?;
-            zip_writer.write_all(&tex_data)?;
+    /// 生成 Nexus Mods 发布包，第一次使用时先填写 MOD 信息
+    fn show_nexus_release(workspace: &mut Workspace) -> anyhow::Result<()> {
+        if workspace.info().mod_info().is_none() {
+            Self::show_edit_mod_info(workspace)?;
+        }
+        let stickers = Self::collect_packaged_stickers(workspace)?;
+        if stickers.is_empty() {
+            warn!("{}", t!("没有发现需要打包的贴纸"));
+            return Ok(());
         }
 
+        let config = workspace.config();
+        let mod_info = Self::mod_info(workspace);
+        let package_name = Self::package_name(workspace, &mod_info);
+        let readme = Self::readme(&mod_info, &stickers);
+        let files = Self::convert_stickers(workspace, &stickers)?;
+        let package = Package {
+            name: &package_name,
+            stamp_dir: workspace
+                .info()
+                .packaging()
+                .archive_path(workspace.info().target_game()),
+            files: &files,
+            readme: Some(&readme),
+            mod_info: &mod_info,
+            compression: config.compression().into(),
+            compression_level: config.compression_level(),
+        };
+        let dist_dir = workspace.dist_dir();
+        let mut trash = Trash::new(dist_dir.join(TRASH_DIR))?;
+        let outputs = release::write_release(&dist_dir, workspace, &package, &stickers, &mut trash);
+        if trash.len() > 0 {
+            info!("{}", t!("已将 {} 个被覆盖的文件移入回收站", trash.len()));
+        }
+        for path in outputs? {
+            info!("{}", t!("已输出：{}", path.display()));
+        }
+        info!(
+            "{}",
+            t!("发布包已生成，上传预览图后请替换 description.txt 中的图片链接")
+        );
+
         Ok(())
     }
 
-    fn convert_png_sticker_to_tex<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<u8>> {
-        let img = image::open(&path)?;
-        if img.width() != 128 && img.height() != 512 {
-            anyhow::bail!(
-                "贴纸尺寸错误：应为 128x512，实际为 {}x{}",
-                img.width(),
-                img.height()
+    /// 确认后从游戏目录卸载工作区安装的文件
+    fn show_uninstall(workspace: &Workspace) -> anyhow::Result<()> {
+        let Some(manifest) = InstallManifest::load(workspace.root_path())? else {
+            println!("{}", t!("工作区没有安装到游戏目录"));
+            return Ok(());
+        };
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "确定要从 {} 卸载 {} 个文件吗？被覆盖的文件将从备份恢复",
+                manifest.game_dir.display(),
+                manifest.files.len()
+            ))
+            .default(false)
+            .interact()?;
+        if confirmed {
+            Self::uninstall(workspace)?;
+        }
+
+        Ok(())
+    }
+
+    fn uninstall(workspace: &Workspace) -> anyhow::Result<()> {
+        let Some(result) = install::uninstall(workspace.root_path())? else {
+            note!("{}", t!("工作区没有安装到游戏目录"));
+            return Ok(());
+        };
+        for path in &result.skipped {
+            warn!("{}", t!("安装后已被修改，保持不变：{}", path.display()));
+        }
+        for path in &result.kept_backups {
+            warn!("{}", t!("安装前的文件已保留在备份中：{}", path.display()));
+        }
+        info!(
+            "{}",
+            t!(
+                "已卸载：删除 {} 个文件，恢复 {} 个备份",
+                result.removed,
+                result.restored
+            )
+        );
+
+        Ok(())
+    }
+
+    /// 找到游戏目录时询问是否安装，返回确认安装的游戏目录
+    fn confirm_install(
+        config: &config::Config,
+        game: TargetGame,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let Some(game_dir) = config.game_dir(game) else {
+            return Ok(None);
+        };
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "是否安装到游戏目录 {}？被覆盖的文件将先备份",
+                game_dir.display()
+            ))
+            .default(false)
+            .interact()?;
+        // 确认使用通过 Steam 找到的目录后才保存
+        if confirmed && config.game_dir.is_none() {
+            match config::save_game_dir(&game_dir) {
+                Ok(path) => info!("{}", t!("已保存游戏目录到 {}", path.display())),
+                Err(e) => warn!("{}", t!("无法保存游戏目录：{:#}", e)),
+            }
+        }
+
+        Ok(confirmed.then_some(game_dir))
+    }
+
+    /// 从回收站恢复被打包覆盖的文件
+    fn show_restore_output(workspace: &Workspace) -> anyhow::Result<()> {
+        let trash_root = workspace.dist_dir().join(TRASH_DIR);
+        let batches = trash::list_batches(&trash_root)?;
+        if batches.is_empty() {
+            println!("{}", t!("回收站中没有文件"));
+            return Ok(());
+        }
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请选择要恢复的打包批次： (按↑↓选择，Enter确认)"))
+            .items(&batches)
+            .default(0)
+            .interact()?;
+        let batch = batches.into_iter().nth(selection).unwrap();
+        for entry in &batch.entries {
+            println!("  - {}", entry.original.display());
+        }
+
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("确定要恢复以上文件吗？当前的同名文件将移入回收站"))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Ok(());
+        }
+        let count = batch.restore()?;
+        info!("{}", t!("已恢复 {} 个文件", count));
+
+        Ok(())
+    }
+
+    /// 导入外部的 tex 文件，覆盖已有文件前先确认
+    fn show_import_tex(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let path: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请输入要导入的 tex 文件路径： (可将文件拖入窗口)"))
+            .interact_text()?;
+        // 拖入的路径可能带有引号
+        let path = path.trim().trim_matches('"');
+        let conflicts = match workspace.import_conflicts(path) {
+            Ok(conflicts) => conflicts,
+            Err(e) => {
+                error!("{}", t!("导入失败：{}", e));
+                return Ok(());
+            }
+        };
+        if !conflicts.is_empty() {
+            println!("{}", t!("以下文件将被覆盖："));
+            for conflict in &conflicts {
+                println!("  - {}", conflict.display());
+            }
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("确定要覆盖以上文件吗？"))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+        match workspace.import_tex(path, true) {
+            Ok(name) => info!("{}", t!("导入成功：{}", name)),
+            Err(e) => error!("{}", t!("导入失败：{}", e)),
+        }
+
+        Ok(())
+    }
+
+    /// 列出最近一次打包生成的文件，确认后撤销
+    fn show_undo_package(workspace: &Workspace) -> anyhow::Result<()> {
+        let Some(record) = PackageRecord::load(workspace.root_path())? else {
+            println!("{}", t!("没有可以撤销的打包"));
+            return Ok(());
+        };
+        for path in &record.files {
+            println!("  - {}", path.display());
+        }
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("确定要删除以上文件吗？被覆盖的文件将从回收站恢复"))
+            .default(false)
+            .interact()?;
+        if confirmed {
+            Self::undo_package(workspace)?;
+        }
+
+        Ok(())
+    }
+
+    fn undo_package(workspace: &Workspace) -> anyhow::Result<()> {
+        let Some(undone) = PackageRecord::undo(workspace.root_path())? else {
+            note!("{}", t!("没有可以撤销的打包"));
+            return Ok(());
+        };
+        info!(
+            "{}",
+            t!(
+                "已撤销打包：删除 {} 个文件，恢复 {} 个文件",
+                undone.removed,
+                undone.restored
+            )
+        );
+        if InstallManifest::load(workspace.root_path())?.is_some() {
+            info!("{}", t!("安装到游戏目录的文件不受影响，可通过卸载移除"));
+        }
+
+        Ok(())
+    }
+
+    /// 选择打包时的输出方式
+    fn show_output_settings(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let current = workspace.info().output().clone();
+        let items = [
+            t!("独立 tex 文件 (dist/<工作区>/)"),
+            t!("nativePC zip (dist/<工作区>.zip)"),
+            t!("Fluffy Mod Manager 压缩包 (dist/<工作区>_fluffy.zip)"),
+            t!("直接安装到游戏目录"),
+        ];
+        let defaults = [
+            current.loose_files,
+            current.nativepc_zip,
+            current.fluffy_zip,
+            current.install_dir.is_some(),
+        ];
+        let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!("请选择输出方式： (按空格切换，Enter确认)"))
+            .items(&items)
+            .defaults(&defaults)
+            .interact()?;
+
+        let exe_name = workspace.info().target_game().exe_name();
+        let install_dir = if selections.contains(&3) {
+            let initial = current
+                .install_dir
+                .or_else(|| workspace.config().game_dir(workspace.info().target_game()))
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            let dir: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(t!("请输入游戏目录： ({} 所在目录)", exe_name))
+                .with_initial_text(initial)
+                .interact_text()?;
+            let dir = PathBuf::from(dir.trim().trim_matches('"'));
+            if !dir.join(exe_name).exists() {
+                warn!("{}", t!("目录中没有 {}：{}", exe_name, dir.display()));
+            }
+            Some(dir)
+        } else {
+            None
+        };
+        let output = OutputProfile {
+            loose_files: selections.contains(&0),
+            nativepc_zip: selections.contains(&1),
+            fluffy_zip: selections.contains(&2),
+            install_dir,
+        };
+        info!("{}", t!("输出方式：{}", output));
+        workspace.set_output(output)?;
+
+        let convert_to_srgb = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "打包时是否将嵌入了颜色配置 (ICC/gAMA) 的 PNG 转换为 sRGB？"
+            ))
+            .default(workspace.info().convert_to_srgb())
+            .interact()?;
+        workspace.set_convert_to_srgb(convert_to_srgb)?;
+
+        Self::show_compression_settings(workspace)
+    }
+
+    /// 编辑输出目录、文件名模板和包内路径，留空使用默认值
+    fn show_packaging_settings(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let current = workspace.info().packaging().clone();
+        let input = |prompt: &str, initial: String| -> anyhow::Result<Option<String>> {
+            let value: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .with_initial_text(initial)
+                .allow_empty(true)
+                .interact_text()?;
+            let value = value.trim();
+            Ok((!value.is_empty()).then(|| value.to_string()))
+        };
+
+        let output_dir = input(
+            &t!(
+                "输出目录： (相对于工作区所在的目录，留空使用 {})",
+                workspace
+                    .config()
+                    .output_dir
+                    .unwrap_or("dist".into())
+                    .display()
+            ),
+            current
+                .output_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+        )?;
+        let name_template = input(
+            &t!(
+                "输出文件名： (可使用 {} 和 {}，留空使用工作区名称)",
+                "{name}",
+                "{version}"
+            ),
+            current.name_template.clone().unwrap_or_default(),
+        )?;
+        let game = workspace.info().target_game();
+        let archive_path = loop {
+            let Some(path) = input(
+                &t!("包内路径： (留空使用 {})", game.stamp_dir()),
+                current.archive_path.clone().unwrap_or_default(),
+            )?
+            else {
+                break None;
+            };
+            match PackagingSettings::normalize_archive_path(&path) {
+                Ok(path) => break Some(path),
+                Err(e) => error!("{:#}", e),
+            }
+        };
+        if archive_path
+            .as_deref()
+            .is_some_and(|path| path != game.stamp_dir())
+        {
+            warn!(
+                "{}",
+                t!("包内路径与贴纸目录不同，只有替换其他贴图时才需要修改")
             );
         }
 
-        let DynamicImage::ImageRgba8(img) = img else {
-            anyhow::bail!("贴纸格式错误：应为 RGBA8 (png)，实际为 {:?}", img.color());
+        workspace.set_packaging(PackagingSettings {
+            output_dir: output_dir.map(PathBuf::from),
+            name_template,
+            archive_path,
+        })?;
+        info!("{}", t!("打包设置已保存"));
+
+        Ok(())
+    }
+
+    /// 选择压缩包的压缩方式和级别，保存到工作区的配置文件
+    fn show_compression_settings(workspace: &Workspace) -> anyhow::Result<()> {
+        let config = workspace.config();
+        let current = config.compression();
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "请选择压缩包的压缩方式： (按↑↓选择，Enter确认)\n部分 MOD 管理器不支持 zstd"
+            ))
+            .items(&Compression::ALL)
+            .default(Compression::ALL.iter().position(|c| *c == current).unwrap())
+            .interact()?;
+        let compression = Compression::ALL[selection];
+
+        let level = match compression.level_range() {
+            Some(range) => {
+                let initial = config
+                    .compression_level
+                    .filter(|level| range.contains(level))
+                    .map(|level| level.to_string())
+                    .unwrap_or_default();
+                let level: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt(t!(
+                        "压缩级别： ({}-{}，留空使用默认级别)",
+                        range.start(),
+                        range.end()
+                    ))
+                    .with_initial_text(initial)
+                    .allow_empty(true)
+                    .validate_with(|input: &String| -> Result<(), String> {
+                        match input.trim() {
+                            "" => Ok(()),
+                            level => match level.parse::<i64>() {
+                                Ok(level) if range.contains(&level) => Ok(()),
+                                _ => Err(t!("请输入 {}-{} 之间的整数", range.start(), range.end())),
+                            },
+                        }
+                    })
+                    .interact_text()?;
+                level.trim().parse().ok()
+            }
+            None => None,
         };
+        workspace.save_compression(compression, level)?;
+
+        Ok(())
+    }
+
+    /// 工作区目录名，也是输出文件的名称
+    fn workspace_name(workspace: &Workspace) -> String {
+        Path::new(workspace.root_path())
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// 输出文件的名称，见 [PackagingSettings::output_name]
+    fn package_name(workspace: &Workspace, mod_info: &ModInfo) -> String {
+        workspace
+            .info()
+            .packaging()
+            .output_name(&Self::workspace_name(workspace), mod_info)
+    }
+
+    /// 工作区的 MOD 信息，未填写时只有名称
+    fn mod_info(workspace: &Workspace) -> ModInfo {
+        workspace
+            .info()
+            .mod_info()
+            .cloned()
+            .unwrap_or_else(|| ModInfo::new(&Self::workspace_name(workspace)))
+    }
+
+    /// MOD 信息和贴纸的作者、标签、备注组成的说明文件
+    fn readme(mod_info: &ModInfo, stickers: &[PackagedSticker]) -> String {
+        let mut readme = mod_info.readme_header();
+        readme.push('\n');
+        for (sticker, _) in stickers.iter().filter(|(s, _)| !s.metadata.is_empty()) {
+            let metadata = &sticker.metadata;
+            readme.push_str(&format!("[{}]\n", sticker.name));
+            if let Some(author) = &metadata.author {
+                readme.push_str(&t!("作者：{}\n", author));
+            }
+            if !metadata.tags.is_empty() {
+                readme.push_str(&t!("标签：{}\n", metadata.tags.join(", ")));
+            }
+            if let Some(notes) = &metadata.notes {
+                readme.push_str(&t!("备注：{}\n", notes));
+            }
+            readme.push('\n');
+        }
+
+        readme
+    }
+
+    /// 各贴纸是否已更改，与工作区中的贴纸顺序一致
+    fn modified_flags(workspace: &Workspace) -> anyhow::Result<Vec<bool>> {
+        let modified_stickers = workspace.get_modified_stickers()?;
+        Ok(workspace
+            .info()
+            .sticker_packs()
+            .iter()
+            .map(|sticker| modified_stickers.iter().any(|s| s.name == sticker.name))
+            .collect())
+    }
+
+    /// 各贴纸的标签，用于在选择列表中按标签筛选
+    fn sticker_tags(stickers: &[StickerPack]) -> Vec<Vec<String>> {
+        stickers
+            .iter()
+            .map(|sticker| sticker.metadata.tags.clone())
+            .collect()
+    }
+
+    /// 选择快照并回滚
+    fn show_rollback(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let snapshots = workspace.list_snapshots()?;
+        if snapshots.is_empty() {
+            println!("{}", t!("没有可用的快照"));
+            return Ok(());
+        }
+        let selection =
+            Picker::new(t!("请选择要回滚的快照： (按↑↓选择，Enter确认)"), &snapshots).interact()?;
+        let snapshot = &snapshots[selection];
+
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(t!(
+                "确定要回滚到 {} 吗？之后的修改将丢失，可先创建快照",
+                snapshot.label
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        workspace.rollback(snapshot)?;
+        info!("{}", t!("已回滚到快照：{}", snapshot));
+
+        Ok(())
+    }
+
+    /// 选择忽略的贴纸，忽略的贴纸不参与更改检测和打包
+    fn show_ignore_list(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let stickers = workspace.info().sticker_packs();
+        let labels = workspace.names();
+        let names = stickers
+            .iter()
+            .map(|sticker| labels.display(&sticker.name))
+            .collect::<Vec<_>>();
+        let checked = stickers
+            .iter()
+            .map(|sticker| sticker.ignored)
+            .collect::<Vec<_>>();
+        let selections = Picker::new(
+            t!("请选择要忽略的贴纸，忽略的贴纸不会被打包： (空格选择，Enter确认)"),
+            &names,
+        )
+        .modified(Self::modified_flags(workspace)?)
+        .tags(Self::sticker_tags(stickers))
+        .interact_multi(&checked)?;
+
+        let ignored = selections
+            .into_iter()
+            .map(|index| stickers[index].name.clone())
+            .collect::<Vec<_>>();
+        workspace.set_ignored(&ignored.iter().map(String::as_str).collect::<Vec<_>>())?;
+        info!("{}", t!("已忽略 {} 个贴纸", ignored.len()));
+
+        Ok(())
+    }
+
+    /// 编辑贴纸的作者、标签和备注，留空表示清除
+    fn show_edit_metadata(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let stickers = workspace.info().sticker_packs().to_vec();
+        if stickers.is_empty() {
+            println!("{}", t!("工作区中没有贴纸"));
+            return Ok(());
+        }
+        let labels = workspace.names();
+        let names = stickers
+            .iter()
+            .map(|sticker| labels.display(&sticker.name))
+            .collect::<Vec<_>>();
+        let selection = Picker::new(t!("请选择要编辑的贴纸： (按↑↓选择，Enter确认)"), &names)
+            .modified(Self::modified_flags(workspace)?)
+            .tags(Self::sticker_tags(&stickers))
+            .interact()?;
+        let metadata = &stickers[selection].metadata;
+
+        let input = |prompt: &str, initial: String| -> anyhow::Result<Option<String>> {
+            let value: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .with_initial_text(initial)
+                .allow_empty(true)
+                .interact_text()?;
+            let value = value.trim();
+            Ok((!value.is_empty()).then(|| value.to_string()))
+        };
+        let author = input(t!("作者："), metadata.author.clone().unwrap_or_default())?;
+        let tags = input(t!("标签： (以逗号分隔)"), metadata.tags.join(", "))?
+            .map(|tags| {
+                tags.split([',', '，'])
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let notes = input(t!("备注："), metadata.notes.clone().unwrap_or_default())?;
+
+        workspace.set_metadata(
+            &stickers[selection].name,
+            StickerMetadata {
+                author,
+                tags,
+                notes,
+            },
+        )?;
+        info!("{}", t!("已保存贴纸信息：{}", names[selection]));
+
+        Ok(())
+    }
+
+    /// 编辑打包时写入的 MOD 名称、作者、版本和说明
+    fn show_edit_mod_info(workspace: &mut Workspace) -> anyhow::Result<()> {
+        let current = Self::mod_info(workspace);
+
+        let input = |prompt: &str, initial: String| -> anyhow::Result<Option<String>> {
+            let value: String = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .with_initial_text(initial)
+                .allow_empty(true)
+                .interact_text()?;
+            let value = value.trim();
+            Ok((!value.is_empty()).then(|| value.to_string()))
+        };
+        let name = input(t!("MOD 名称："), current.name.clone())?.unwrap_or(current.name);
+        let author = input(t!("作者："), current.author.unwrap_or_default())?;
+        let version = input(t!("版本："), current.version.unwrap_or_default())?;
+        let description = input(t!("说明："), current.description.unwrap_or_default())?;
+
+        workspace.set_mod_info(ModInfo {
+            name,
+            author,
+            version,
+            description,
+        })?;
+        info!("{}", t!("已保存 MOD 信息"));
+
+        Ok(())
+    }
+
+    /// 转换单个文件，tex 转为 png，png 和 dds 转为 tex，输出到同一目录，已存在时加上编号
+    fn quick_convert(path: &Path) -> anyhow::Result<PathBuf> {
+        let kind = FileKind::from_path(path).ok_or_else(|| convert::unsupported(path))?;
+        let output = util::unique_path(path.with_extension(kind.default_target().extension()));
+        convert::convert_file(path, &output)?;
+
+        Ok(output)
+    }
+
+    /// 转换单个文件或批量转换，输出为目录时按 `to` 或默认方向决定格式
+    ///
+    /// 批量转换时输出文件已存在则在文件名后加上编号，见 [util::unique_path]。
+    fn convert_files(input: &Path, output: &Path, to: Option<FileKind>) -> anyhow::Result<()> {
+        let target = |input: &Path| -> (PathBuf, FileKind) {
+            let kind = FileKind::from_path(input).unwrap_or(FileKind::Png);
+            let to = to.unwrap_or(kind.default_target());
+            let name = input.file_stem().unwrap_or_default();
+            (output.join(name).with_extension(to.extension()), to)
+        };
+
+        if !convert::is_batch(input) {
+            let output = match FileKind::from_path(output) {
+                Some(_) if !output.is_dir() => output.to_path_buf(),
+                _ => target(input).0,
+            };
+            convert::convert_file(input, &output)?;
+            info!("{}", t!("已转换：{}", output.display()));
+            return Ok(());
+        }
+
+        let inputs = convert::batch_inputs(input)?;
+        if inputs.is_empty() {
+            warn!("{}", t!("没有找到可以转换的文件：{}", input.display()));
+            return Ok(());
+        }
+        let mut failed = 0;
+        for input in &inputs {
+            let (output, to) = target(input);
+            if FileKind::from_path(input) == Some(to) {
+                continue;
+            }
+            // 批量转换不覆盖已有的文件，其中可能有同一批的输入
+            let output = util::unique_path(output);
+            match convert::convert_file(input, &output) {
+                Ok(()) => info!("{}", t!("已转换：{}", output.display())),
+                Err(e) => {
+                    error!("{}", t!("{} 转换失败：{:#}", input.display(), e));
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!(ErrorCode::PartialFailure.with(t!("{} 个文件转换失败", failed)));
+        }
+
+        Ok(())
+    }
+
+    /// 显示 tex 文件头中的信息和校验结果，文件无效时返回错误
+    fn inspect_tex(path: &Path) -> anyhow::Result<()> {
+        let data = std::fs::read(path)?;
+        let summary = tex_convert::inspect(&mut Cursor::new(&data)).map_err(|e| {
+            ErrorCode::BadInput.with(t!("无法读取 tex 文件头：{}：{}", path.display(), e))
+        })?;
+        let size = |size: u64| format!("{} ({})", size, util::format_size(size));
+
+        println!("{}", path.display());
+        println!("{}", t!("尺寸：{}x{}", summary.width, summary.height));
+        println!(
+            "{}",
+            t!(
+                "格式：{} ({})",
+                format!("{:?}", summary.format),
+                summary.format as i32
+            )
+        );
+        println!(
+            "{}",
+            t!("颜色空间：{}", format!("{:?}", summary.color_space))
+        );
+        println!("{}", t!("Mipmap 数量：{}", summary.mip_map_count));
+        if summary.image_count > 1 || summary.depth > 1 {
+            println!(
+                "{}",
+                t!("图片数量：{}，深度：{}", summary.image_count, summary.depth)
+            );
+        }
+        println!(
+            "{}",
+            t!(
+                "数据偏移：{} ({})",
+                format!("0x{:X}", summary.data_offset),
+                summary.data_offset
+            )
+        );
+        println!("{}", t!("计算大小：{}", size(summary.vram_size)));
+        println!("{}", t!("实际大小：{}", size(summary.data_size)));
+
+        let report = tex_convert::validate(&mut Cursor::new(&data))?;
+        for warning in report.warnings() {
+            println!("{}", t!("  警告：{}", warning));
+        }
+        for error in report.errors() {
+            println!("{}", t!("  错误：{}", error));
+        }
+        if !report.is_valid() {
+            anyhow::bail!(ErrorCode::BadInput.with(t!("文件校验失败：{}", path.display())));
+        }
+        note!("{}", t!("文件校验通过"));
+
+        Ok(())
+    }
+
+    /// 显示诊断结果，返回失败的项数
+    fn doctor(root_path: Option<&Path>) -> usize {
+        let checks = doctor::diagnose(root_path);
+        for check in &checks {
+            match &check.result {
+                Ok(detail) => note!("{}", t!("[通过] {}：{}", check.name, detail)),
+                Err(failure) => {
+                    println!("{}", t!("[失败] {}：{}", check.name, failure.problem));
+                    println!("{}", t!("       建议：{}", failure.suggestion));
+                }
+            }
+        }
+        let failed = checks.iter().filter(|check| check.result.is_err()).count();
+        if failed == 0 {
+            note!("{}", t!("没有发现问题"));
+        }
+
+        failed
+    }
+
+    /// 等待按下 Enter 键，输入或输出被重定向时（脚本调用）不等待
+    fn pause() {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return;
+        }
+        println!("{}", t!("按 Enter 键退出"));
+        let _ = std::io::stdin().read_line(&mut String::new());
+    }
+
+    fn convert_image_sticker_to_tex<P: AsRef<Path>>(
+        path: P,
+        format: ImageFormat,
+        sticker: &StickerPack,
+        info: &WorkspaceInfo,
+    ) -> anyhow::Result<Vec<u8>> {
+        let img = color::load_image(path, format, info.convert_to_srgb())?;
+
+        Self::convert_sticker_image_to_tex(&img, sticker, info)
+    }
+
+    fn convert_sticker_image_to_tex(
+        img: &RgbaImage,
+        sticker: &StickerPack,
+        info: &WorkspaceInfo,
+    ) -> anyhow::Result<Vec<u8>> {
+        let layout = sticker.layout;
+        if img.width() != layout.sheet_w || img.height() != layout.sheet_h {
+            anyhow::bail!(ErrorCode::BadInput.with(t!(
+                "贴纸尺寸错误：应为 {}x{}，实际为 {}x{}",
+                layout.sheet_w,
+                layout.sheet_h,
+                img.width(),
+                img.height()
+            )));
+        }
         // Tex文件数据
-        let tex_data = tex_convert::convert_image_to_tex(&img)?;
+        let original = convert::original_header(&sticker.name)?;
+        let options = Self::convert_options_for(sticker, original.as_ref(), info);
+        debug!("转换 {}：{:?}", sticker.name, options);
+        let tex_data = match &original {
+            Some(reference) => tex_convert::convert_image_to_tex_like(img, &options, reference)?,
+            None => tex_convert::convert_image_to_tex(img, &options)?,
+        };
 
         Ok(tex_data)
     }
 
-    fn convert_dds_sticker_to_tex<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<u8>> {
-        let dds_data = std::fs::read(&path)?;
-        let tex_data = tex_convert::dds2tex::convert_to_tex(&mut Cursor::new(dds_data))?;
+    /// 沿用原始贴纸的格式和颜色空间，避免重新编码后体积改变或颜色变亮变暗
+    ///
+    /// 旧版工作区没有记录原始格式，从内置的原始贴纸读取。
+    fn convert_options_for(
+        sticker: &StickerPack,
+        original: Option<&TexHeader>,
+        info: &WorkspaceInfo,
+    ) -> ConvertOptions {
+        let options = ConvertOptions::default().preset(info.target_game().preset());
+        let Some(original_format) = sticker
+            .original_format()
+            .or(original.map(|header| header.format))
+        else {
+            return options;
+        };
 
-        Ok(tex_data)
+        if info.keep_original_format() {
+            options.same_format_as(original_format)
+        } else {
+            options.color_space(original_format.color_space())
+        }
     }
 }
 
@@ -235,15 +2046,21 @@ impl App {
 enum MainSelection {
     NewWorkspace,
     OpenWorkspace,
+    ImportWorkspace,
+    PackageAll,
+    ValidateAll,
     Exit,
 }
 
 impl Display for MainSelection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            MainSelection::NewWorkspace => write!(f, "新建工作区"),
-            MainSelection::OpenWorkspace => write!(f, "打开工作区"),
-            MainSelection::Exit => write!(f, "退出"),
+            MainSelection::NewWorkspace => write!(f, "{}", t!("新建工作区")),
+            MainSelection::OpenWorkspace => write!(f, "{}", t!("打开工作区")),
+            MainSelection::ImportWorkspace => write!(f, "{}", t!("导入单文件工作区 (.mhwsws)")),
+            MainSelection::PackageAll => write!(f, "{}", t!("打包所有工作区")),
+            MainSelection::ValidateAll => write!(f, "{}", t!("检查所有工作区")),
+            MainSelection::Exit => write!(f, "{}", t!("退出")),
         }
     }
 }
@@ -253,7 +2070,10 @@ impl From<usize> for MainSelection {
         match index {
             0 => MainSelection::NewWorkspace,
             1 => MainSelection::OpenWorkspace,
-            2 => MainSelection::Exit,
+            2 => MainSelection::ImportWorkspace,
+            3 => MainSelection::PackageAll,
+            4 => MainSelection::ValidateAll,
+            5 => MainSelection::Exit,
             _ => unreachable!(),
         }
     }
@@ -264,10 +2084,13 @@ impl MainSelection {
         let selections = &[
             MainSelection::NewWorkspace,
             MainSelection::OpenWorkspace,
+            MainSelection::ImportWorkspace,
+            MainSelection::PackageAll,
+            MainSelection::ValidateAll,
             MainSelection::Exit,
         ];
         let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("请选择操作： (按↑↓选择，Enter确认)")
+            .with_prompt(t!("请选择操作： (按↑↓选择，Enter确认)"))
             .items(selections)
             .default(0)
             .interact()?;
@@ -279,16 +2102,68 @@ impl MainSelection {
 #[derive(Debug)]
 enum WorkspaceSelection {
     Info,
+    GitStatus,
     Package,
+    PackagePreview,
+    NexusRelease,
+    Validate,
+    Output,
+    Packaging,
+    RestoreOutput,
+    UndoPackage,
+    Uninstall,
+    Compare,
+    Preview,
+    Templates,
+    Watch,
+    Metadata,
+    ModInfo,
+    Ignore,
+    Snapshot,
+    Rollback,
+    Import,
+    Register,
+    Restore,
+    Repair,
+    Doctor,
+    Clone,
+    Export,
+    Delete,
     Back,
 }
 
 impl Display for WorkspaceSelection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WorkspaceSelection::Info => write!(f, "查看信息"),
-            WorkspaceSelection::Package => write!(f, "打包为 MHW MOD (.zip)"),
-            WorkspaceSelection::Back => write!(f, "返回"),
+            WorkspaceSelection::Info => write!(f, "{}", t!("查看信息")),
+            WorkspaceSelection::GitStatus => write!(f, "{}", t!("查看 git 状态")),
+            WorkspaceSelection::Package => write!(f, "{}", t!("打包为 MHW MOD (.zip)")),
+            WorkspaceSelection::PackagePreview => write!(f, "{}", t!("预览打包内容")),
+            WorkspaceSelection::NexusRelease => write!(f, "{}", t!("生成 Nexus Mods 发布包")),
+            WorkspaceSelection::Validate => write!(f, "{}", t!("检查已更改的贴纸")),
+            WorkspaceSelection::Output => write!(f, "{}", t!("输出设置")),
+            WorkspaceSelection::Packaging => write!(f, "{}", t!("打包设置")),
+            WorkspaceSelection::RestoreOutput => write!(f, "{}", t!("恢复被覆盖的输出文件")),
+            WorkspaceSelection::UndoPackage => write!(f, "{}", t!("撤销上次打包")),
+            WorkspaceSelection::Uninstall => write!(f, "{}", t!("从游戏目录卸载")),
+            WorkspaceSelection::Compare => write!(f, "{}", t!("与原版比较")),
+            WorkspaceSelection::Preview => write!(f, "{}", t!("生成预览页面 (preview.html)")),
+            WorkspaceSelection::Templates => write!(f, "{}", t!("导出编辑模板")),
+            WorkspaceSelection::Watch => write!(f, "{}", t!("监视更改并自动打包")),
+            WorkspaceSelection::Metadata => write!(f, "{}", t!("编辑贴纸信息")),
+            WorkspaceSelection::ModInfo => write!(f, "{}", t!("编辑 MOD 信息")),
+            WorkspaceSelection::Ignore => write!(f, "{}", t!("设置忽略的贴纸")),
+            WorkspaceSelection::Snapshot => write!(f, "{}", t!("创建快照")),
+            WorkspaceSelection::Rollback => write!(f, "{}", t!("回滚到快照")),
+            WorkspaceSelection::Import => write!(f, "{}", t!("导入 tex 文件")),
+            WorkspaceSelection::Register => write!(f, "{}", t!("登记新图片")),
+            WorkspaceSelection::Restore => write!(f, "{}", t!("还原贴纸")),
+            WorkspaceSelection::Repair => write!(f, "{}", t!("修复缺失文件")),
+            WorkspaceSelection::Doctor => write!(f, "{}", t!("诊断工作区")),
+            WorkspaceSelection::Clone => write!(f, "{}", t!("复制工作区")),
+            WorkspaceSelection::Export => write!(f, "{}", t!("导出为单文件工作区 (.mhwsws)")),
+            WorkspaceSelection::Delete => write!(f, "{}", t!("删除工作区")),
+            WorkspaceSelection::Back => write!(f, "{}", t!("返回")),
         }
     }
 }
@@ -297,8 +2172,34 @@ impl From<usize> for WorkspaceSelection {
     fn from(index: usize) -> Self {
         match index {
             0 => WorkspaceSelection::Info,
-            1 => WorkspaceSelection::Package,
-            2 => WorkspaceSelection::Back,
+            1 => WorkspaceSelection::GitStatus,
+            2 => WorkspaceSelection::Package,
+            3 => WorkspaceSelection::PackagePreview,
+            4 => WorkspaceSelection::NexusRelease,
+            5 => WorkspaceSelection::Validate,
+            6 => WorkspaceSelection::Output,
+            7 => WorkspaceSelection::Packaging,
+            8 => WorkspaceSelection::RestoreOutput,
+            9 => WorkspaceSelection::UndoPackage,
+            10 => WorkspaceSelection::Uninstall,
+            11 => WorkspaceSelection::Compare,
+            12 => WorkspaceSelection::Preview,
+            13 => WorkspaceSelection::Templates,
+            14 => WorkspaceSelection::Watch,
+            15 => WorkspaceSelection::Metadata,
+            16 => WorkspaceSelection::ModInfo,
+            17 => WorkspaceSelection::Ignore,
+            18 => WorkspaceSelection::Snapshot,
+            19 => WorkspaceSelection::Rollback,
+            20 => WorkspaceSelection::Import,
+            21 => WorkspaceSelection::Register,
+            22 => WorkspaceSelection::Restore,
+            23 => WorkspaceSelection::Repair,
+            24 => WorkspaceSelection::Doctor,
+            25 => WorkspaceSelection::Clone,
+            26 => WorkspaceSelection::Export,
+            27 => WorkspaceSelection::Delete,
+            28 => WorkspaceSelection::Back,
             _ => unreachable!(),
         }
     }
@@ -308,13 +2209,40 @@ impl WorkspaceSelection {
     pub fn show_interact() -> anyhow::Result<Self> {
         let selections = &[
             WorkspaceSelection::Info,
+            WorkspaceSelection::GitStatus,
             WorkspaceSelection::Package,
+            WorkspaceSelection::PackagePreview,
+            WorkspaceSelection::NexusRelease,
+            WorkspaceSelection::Validate,
+            WorkspaceSelection::Output,
+            WorkspaceSelection::Packaging,
+            WorkspaceSelection::RestoreOutput,
+            WorkspaceSelection::UndoPackage,
+            WorkspaceSelection::Uninstall,
+            WorkspaceSelection::Compare,
+            WorkspaceSelection::Preview,
+            WorkspaceSelection::Templates,
+            WorkspaceSelection::Watch,
+            WorkspaceSelection::Metadata,
+            WorkspaceSelection::ModInfo,
+            WorkspaceSelection::Ignore,
+            WorkspaceSelection::Snapshot,
+            WorkspaceSelection::Rollback,
+            WorkspaceSelection::Import,
+            WorkspaceSelection::Register,
+            WorkspaceSelection::Restore,
+            WorkspaceSelection::Repair,
+            WorkspaceSelection::Doctor,
+            WorkspaceSelection::Clone,
+            WorkspaceSelection::Export,
+            WorkspaceSelection::Delete,
             WorkspaceSelection::Back,
         ];
         let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("请选择工作区操作： (按↑↓选择，Enter确认)")
+            .with_prompt(t!("请选择工作区操作： (按↑↓选择，←→翻页，Enter确认)"))
             .items(selections)
             .default(0)
+            .max_length(picker::PAGE_SIZE)
             .interact()?;
 
         Ok(selection.into())
@@ -356,12 +2284,16 @@ impl From<WorkspaceModeSelection> for StickerPackType {
 }
 
 impl WorkspaceModeSelection {
-    pub fn show_interact() -> anyhow::Result<Self> {
+    pub fn show_interact(default: StickerPackType) -> anyhow::Result<Self> {
         let selections = &[WorkspaceModeSelection::Dds, WorkspaceModeSelection::Png];
+        let default = match default {
+            StickerPackType::Dds => 0,
+            StickerPackType::Png => 1,
+        };
         let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("请选择导出文件格式： (按↑↓选择，Enter确认)\n如果有PS插件，优先选择.dds格式，否则选择.png")
+            .with_prompt(t!("请选择导出文件格式： (按↑↓选择，Enter确认)\n如果有PS插件，优先选择.dds格式，否则选择.png"))
             .items(selections)
-            .default(0)
+            .default(default)
             .interact()?;
 
         Ok(selection.into())
@@ -370,46 +2302,68 @@ impl WorkspaceModeSelection {
 
 #[cfg(test)]
 mod tests {
-    use image::{DynamicImage, Rgba, RgbaImage};
+    use tex_convert::diff::{diff_images, DiffOptions};
 
-    #[test]
-    fn read_png() -> anyhow::Result<()> {
-        // let orig_img = image::open("../../测试项目/chat_stamp00_ID_0.png")?;
-        // let ps_img = image::open("../../测试项目/chat_stamp00_ID_0_ps.png")?;
-        let orig_img = image::open("../../debug_orig.png")?;
-        let ps_img = image::open("../../debug_ps.png")?;
+    use crate::util::TempDir;
 
-        let DynamicImage::ImageRgba8(orig_buf) = orig_img else {
-            return Err(anyhow::anyhow!("not rgba8"));
-        };
-        let DynamicImage::ImageRgba8(ps_buf) = ps_img else {
-            return Err(anyhow::anyhow!("not rgba8"));
+    use super::*;
+
+    #[test]
+    fn test_restore_discards_tiles() {
+        let root = TempDir::new("restore-test");
+        let path = root.join("workspace");
+        let mut workspace =
+            Workspace::create_new(&path, StickerPackType::Png, TargetGame::default()).unwrap();
+        let sticker = workspace.info().sticker_packs()[0].clone();
+        let layout = sticker.layout;
+        let tile =
+            RgbaImage::from_pixel(layout.tile_w, layout.tile_h, image::Rgba([255, 0, 0, 255]));
+        tile.save(path.join(format!("{}_0.png", sticker.name)))
+            .unwrap();
+        std::fs::create_dir_all(path.join(workspace::OVERLAY_DIR)).unwrap();
+        tile.save(
+            path.join(workspace::OVERLAY_DIR)
+                .join(format!("{}_1.png", sticker.name)),
+        )
+        .unwrap();
+        let packaged = |workspace: &Workspace| {
+            App::collect_packaged_stickers(workspace)
+                .unwrap()
+                .iter()
+                .any(|(packaged, _)| packaged.name == sticker.name)
         };
+        assert!(packaged(&workspace));
 
-        let (width, height) = orig_buf.dimensions();
+        workspace.restore_sticker(&sticker.name).unwrap();
+        assert!(!packaged(&workspace));
+        assert!(!path.join(format!("{}_0.png", sticker.name)).exists());
+    }
 
-        // 创建一个新的图像缓冲区，初始化为原始图像的副本
-        let mut output_buf: RgbaImage = ps_buf.clone();
+    #[test]
+    fn read_png() -> anyhow::Result<()> {
+        // let orig_img = image::open("../../测试项目/chat_stamp00_ID_0.png")?;
+        // let ps_img = image::open("../../测试项目/chat_stamp00_ID_0_ps.png")?;
+        let orig_img = image::open("../../debug_orig.png")?.to_rgba8();
+        let ps_img = image::open("../../debug_ps.png")?.to_rgba8();
 
-        for y in 0..height {
-            for x in 0..width {
-                let orig_pixel = orig_buf.get_pixel(x, y);
-                let ps_pixel = ps_buf.get_pixel(x, y);
+        let report = diff_images(
+            &orig_img,
+            &ps_img,
+            &DiffOptions {
+                annotate: true,
+                ..Default::default()
+            },
+        )?;
+        println!(
+            "Mismatch pixels: {}/{}, max delta: {:?}",
+            report.differing_pixels, report.total_pixels, report.max_delta
+        );
 
-                if orig_pixel != ps_pixel && orig_pixel.0[3] != 0 {
-                    println!(
-                        "Mismatch at ({}, {}): original = {:?}, compared = {:?}",
-                        x, y, orig_pixel, ps_pixel
-                    );
-                    // 将不匹配的像素标记为品红色
-                    output_buf.put_pixel(x, y, Rgba([255, 0, 255, 255])); // 品红色
-                }
-            }
+        // 保存输出图像，不匹配的像素标记为品红色
+        if let Some(diff_image) = report.diff_image {
+            diff_image.save("../../diff.png")?;
         }
 
-        // 保存输出图像
-        output_buf.save("../../diff.png")?;
-
         Ok(())
     }
 }
@@ -0,0 +1,158 @@
+//! workspace.json 的版本迁移
+//!
+//! 每个版本的迁移函数只负责升级到下一个版本，读取时依次执行，
+//! 新增字段时添加一个迁移函数并增加 [CURRENT_VERSION] 即可。
+
+use std::io::Cursor;
+
+use serde_json::{Map, Value};
+use tex_convert::{atlas::SheetLayout, spec::TexInfo};
+
+use crate::{asset, game::TargetGame, i18n::t};
+
+/// 当前程序写入的工作区版本
+pub const CURRENT_VERSION: i32 = 5;
+
+type Migration = fn(&mut Map<String, Value>) -> anyhow::Result<()>;
+
+/// `MIGRATIONS[i]` 将版本 `i + 1` 升级到 `i + 2`
+const MIGRATIONS: &[Migration] = &[v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5];
+
+/// 将工作区信息升级到 [CURRENT_VERSION]，返回是否进行了迁移
+pub fn migrate(value: &mut Value) -> anyhow::Result<bool> {
+    let info = value
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!(t!("工作区信息格式错误")))?;
+    let version = info.get("version").and_then(Value::as_i64).unwrap_or(1) as i32;
+    if version > CURRENT_VERSION {
+        anyhow::bail!(t!(
+            "工作区版本 {} 高于程序支持的版本 {}，请更新程序",
+            version,
+            CURRENT_VERSION
+        ));
+    }
+    if version < 1 {
+        anyhow::bail!(t!("无效的工作区版本：{}", version));
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(info)?;
+    }
+    info.insert("version".to_string(), CURRENT_VERSION.into());
+
+    Ok(version != CURRENT_VERSION)
+}
+
+/// v2：显式记录 `keep_original_format` 和每个贴纸的 `original_format`
+fn v1_to_v2(info: &mut Map<String, Value>) -> anyhow::Result<()> {
+    info.entry("keep_original_format").or_insert(true.into());
+
+    let Some(sticker_packs) = info.get_mut("sticker_packs").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for sticker in sticker_packs.iter_mut().filter_map(Value::as_object_mut) {
+        if sticker.contains_key("original_format") {
+            continue;
+        }
+        let Some(name) = sticker.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some(file) = asset::Asset::get(&format!("{}.tex", name)) else {
+            continue;
+        };
+        let format = TexInfo::from_reader(&mut Cursor::new(file.data))?.format;
+        sticker.insert("original_format".to_string(), (format as i32).into());
+    }
+
+    Ok(())
+}
+
+/// v3：每个贴纸记录图集布局，此前的工作区只有游戏内的聊天贴纸
+fn v2_to_v3(info: &mut Map<String, Value>) -> anyhow::Result<()> {
+    let Some(sticker_packs) = info.get_mut("sticker_packs").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    let layout = serde_json::to_value(SheetLayout::MHW_STICKER)?;
+    for sticker in sticker_packs.iter_mut().filter_map(Value::as_object_mut) {
+        sticker.entry("layout").or_insert_with(|| layout.clone());
+    }
+
+    Ok(())
+}
+
+/// v4：记录目标游戏，此前的工作区都来自本体的贴纸
+fn v3_to_v4(info: &mut Map<String, Value>) -> anyhow::Result<()> {
+    info.entry("target_game")
+        .or_insert(serde_json::to_value(TargetGame::Mhw)?);
+
+    Ok(())
+}
+
+/// v5：校验值记录算法，新解压的贴纸改用 BLAKE3，已有的 SHA-256 校验值保留
+fn v4_to_v5(info: &mut Map<String, Value>) -> anyhow::Result<()> {
+    let Some(sticker_packs) = info.get_mut("sticker_packs").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for sticker in sticker_packs.iter_mut().filter_map(Value::as_object_mut) {
+        if let Some(sha256) = sticker.remove("checksum_sha256") {
+            sticker.insert(
+                "checksum".to_string(),
+                serde_json::json!({ "sha256": sha256 }),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v1() {
+        let mut value = serde_json::json!({
+            "version": 1,
+            "sticker_packs": [],
+        });
+        assert!(migrate(&mut value).unwrap());
+        assert_eq!(value["version"], CURRENT_VERSION);
+        assert_eq!(value["keep_original_format"], true);
+
+        // 已是最新版本
+        assert!(!migrate(&mut value).unwrap());
+
+        let mut value = serde_json::json!({ "version": CURRENT_VERSION + 1 });
+        assert!(migrate(&mut value).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v2_layout() {
+        let mut value = serde_json::json!({
+            "version": 2,
+            "sticker_packs": [{ "name": "custom" }],
+        });
+        assert!(migrate(&mut value).unwrap());
+        let layout: SheetLayout =
+            serde_json::from_value(value["sticker_packs"][0]["layout"].clone()).unwrap();
+        assert_eq!(layout, SheetLayout::MHW_STICKER);
+        assert_eq!(value["target_game"], "mhw");
+
+        // 无效的布局在读取时报错，而不是在切分时越界
+        let mut layout = value["sticker_packs"][0]["layout"].clone();
+        layout["cols"] = 0.into();
+        assert!(serde_json::from_value::<SheetLayout>(layout).is_err());
+    }
+
+    #[test]
+    fn test_migrate_v4_checksum() {
+        let mut value = serde_json::json!({
+            "version": 4,
+            "sticker_packs": [{ "name": "custom", "checksum_sha256": "00ff" }],
+        });
+        assert!(migrate(&mut value).unwrap());
+        let sticker = &value["sticker_packs"][0];
+        assert!(sticker.get("checksum_sha256").is_none());
+        assert_eq!(sticker["checksum"]["sha256"], "00ff");
+    }
+}
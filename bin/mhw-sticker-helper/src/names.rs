@@ -0,0 +1,81 @@
+//! 贴纸名称映射
+//!
+//! 工作区目录下的 `names.toml` 为内部名称指定便于识别的标签，例如：
+//!
+//! ```toml
+//! chat_stamp00_ID = "艾露猫"
+//! chat_stamp00_ID_3 = "艾露猫点赞"
+//! ```
+//!
+//! 键可以是贴纸图集名称，也可以是 `{name}_{index}` 形式的单张贴纸。
+
+use std::{collections::HashMap, path::Path};
+
+use tracing::warn;
+
+use crate::{i18n::t, workspace::Workspace};
+
+const NAMES_FILE: &str = "names.toml";
+
+#[derive(Debug, Clone, Default)]
+pub struct Names(HashMap<String, String>);
+
+impl Names {
+    /// 读取名称映射，文件不存在时为空
+    pub fn load<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Self> {
+        let path = root_path.as_ref().join(NAMES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let names = toml::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!(t!("{} 格式错误：{}", path.display(), e)))?;
+
+        Ok(Self(names))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    /// 有标签时显示为 `标签 (内部名称)`，否则只显示内部名称
+    pub fn display(&self, name: &str) -> String {
+        match self.get(name) {
+            Some(label) => format!("{} ({})", label, name),
+            None => name.to_string(),
+        }
+    }
+
+    /// 图集中第 `index` 张贴纸的名称，没有单独的标签时使用图集的标签加编号
+    pub fn display_tile(&self, name: &str, index: u32) -> String {
+        let tile_name = format!("{}_{}", name, index);
+        match self.get(&tile_name) {
+            Some(label) => format!("{} ({})", label, tile_name),
+            None => format!("{} #{}", self.display(name), index),
+        }
+    }
+}
+
+impl Workspace {
+    /// 读取工作区的名称映射，格式错误时给出警告并忽略
+    pub fn names(&self) -> Names {
+        Names::load(self.root_path()).unwrap_or_else(|e| {
+            warn!("{:#}", e);
+            Names::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let names = Names(toml::from_str("a = \"猫\"\na_3 = \"点赞\"").unwrap());
+        assert_eq!(names.display("a"), "猫 (a)");
+        assert_eq!(names.display("b"), "b");
+        assert_eq!(names.display_tile("a", 3), "点赞 (a_3)");
+        assert_eq!(names.display_tile("a", 1), "猫 (a) #1");
+        assert_eq!(names.display_tile("b", 0), "b #0");
+    }
+}
@@ -0,0 +1,698 @@
+//! 打包输出方式
+//!
+//! 转换后的 tex 文件可以同时输出为多种形式，由工作区的 [OutputProfile] 决定。
+
+use std::{
+    fs::File,
+    io::{Cursor, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    game::TargetGame,
+    i18n::t,
+    trash::{self, Trash, TRASH_DIR},
+    util::sanitize_file_name,
+};
+
+/// 最近一次打包的记录，位于工作区目录下
+///
+/// 多个工作区可能共用同一个输出目录，记录放在工作区中以免撤销其他工作区的打包。
+pub const LAST_PACKAGE: &str = "last_package.json";
+
+/// 工作区的输出配置，可任意组合
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputProfile {
+    /// `dist/<工作区>/` 下的独立 tex 文件
+    #[serde(default)]
+    pub loose_files: bool,
+    /// `dist/<工作区>.zip`，解压到游戏目录即可使用
+    #[serde(default)]
+    pub nativepc_zip: bool,
+    /// `dist/<工作区>_fluffy.zip`，供 Fluffy Mod Manager 导入
+    #[serde(default)]
+    pub fluffy_zip: bool,
+    /// 游戏目录，设置后直接安装到其中的 nativePC，见 [install](crate::install)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_dir: Option<PathBuf>,
+}
+
+impl Default for OutputProfile {
+    fn default() -> Self {
+        Self {
+            loose_files: true,
+            nativepc_zip: true,
+            fluffy_zip: false,
+            install_dir: None,
+        }
+    }
+}
+
+/// 工作区的打包设置，未设置的项使用默认值
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackagingSettings {
+    /// 输出目录，相对路径相对于工作区所在的目录，优先于配置文件中的 `output_dir`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<PathBuf>,
+    /// 输出文件名的模板，可使用 `{name}`（工作区名称）和 `{version}`（MOD 版本），默认为 `{name}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_template: Option<String>,
+    /// tex 文件在压缩包和游戏目录中的路径，默认为目标游戏的贴纸目录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_path: Option<String>,
+}
+
+impl PackagingSettings {
+    /// 按模板生成的输出名称，用于独立文件的目录和压缩包的文件名
+    pub fn output_name(&self, workspace_name: &str, mod_info: &ModInfo) -> String {
+        let Some(template) = &self.name_template else {
+            return workspace_name.to_string();
+        };
+        let template = match &mod_info.version {
+            Some(version) => template.replace("{version}", version),
+            // 没有版本时连同前面的分隔符一起去掉，例如 `{name}-v{version}`
+            None => {
+                let parts = template.split("{version}").collect::<Vec<_>>();
+                let (last, before) = parts.split_last().unwrap();
+                before
+                    .iter()
+                    .map(|part| {
+                        ["-v", "_v", " v", "-", "_", " "]
+                            .iter()
+                            .find_map(|separator| part.strip_suffix(separator))
+                            .unwrap_or(part)
+                    })
+                    .chain([*last])
+                    .collect()
+            }
+        };
+        let name = sanitize_file_name(template.replace("{name}", workspace_name).trim());
+        if name.is_empty() {
+            workspace_name.to_string()
+        } else {
+            name
+        }
+    }
+
+    /// tex 文件在压缩包和游戏目录中的路径
+    pub fn archive_path(&self, game: TargetGame) -> &str {
+        self.archive_path
+            .as_deref()
+            .unwrap_or_else(|| game.stamp_dir())
+    }
+
+    /// 检查并规范化包内路径：统一使用 `/` 分隔，去掉首尾的 `/`，不允许绝对路径和 `..`
+    pub fn normalize_archive_path(path: &str) -> anyhow::Result<String> {
+        let path = path.trim().replace('\\', "/");
+        let path = path.trim_matches('/');
+        if path.is_empty() || path.contains(':') || path.split('/').any(|part| part == "..") {
+            anyhow::bail!(t!("包内路径无效：{}", path));
+        }
+
+        Ok(path
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect::<Vec<_>>()
+            .join("/"))
+    }
+}
+
+/// MOD 的名称、作者、版本和说明，打包时写入 `modinfo.json` 和说明文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl ModInfo {
+    /// 只有名称的信息，工作区未填写 MOD 信息时使用
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 说明文件开头的 MOD 信息
+    pub fn readme_header(&self) -> String {
+        let mut header = self.name.clone();
+        if let Some(version) = &self.version {
+            header.push_str(&format!(" v{}", version));
+        }
+        header.push('\n');
+        if let Some(author) = &self.author {
+            header.push_str(&t!("作者：{}\n", author));
+        }
+        if let Some(description) = &self.description {
+            header.push_str(&format!("{}\n", description));
+        }
+
+        header
+    }
+}
+
+/// 写入压缩包的 modinfo.json
+#[derive(Serialize)]
+struct ModInfoFile<'a> {
+    #[serde(flatten)]
+    info: &'a ModInfo,
+    /// 生成该 MOD 的程序版本
+    generator: String,
+    files: Vec<&'a str>,
+}
+
+/// 最近一次打包写入 dist 目录的文件，用于撤销打包
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageRecord {
+    /// 打包时的输出目录，回收站位于其中
+    pub dist_dir: PathBuf,
+    pub files: Vec<PathBuf>,
+    /// 被覆盖的文件所在的回收站批次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_batch: Option<PathBuf>,
+}
+
+/// 撤销打包的结果
+#[derive(Debug)]
+pub struct Undone {
+    pub removed: usize,
+    pub restored: usize,
+}
+
+impl PackageRecord {
+    pub fn load<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Option<Self>> {
+        let path = root_path.as_ref().join(LAST_PACKAGE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    fn save(&self, root_path: &Path) -> anyhow::Result<()> {
+        std::fs::write(
+            root_path.join(LAST_PACKAGE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+
+        Ok(())
+    }
+
+    /// 删除工作区最近一次打包生成的文件，并从回收站恢复被其覆盖的文件，没有打包记录时返回 None
+    ///
+    /// 安装到游戏目录的文件不受影响，见 [install::uninstall](crate::install::uninstall)。
+    pub fn undo<P: AsRef<Path>>(root_path: P) -> anyhow::Result<Option<Undone>> {
+        let root_path = root_path.as_ref();
+        let Some(record) = Self::load(root_path)? else {
+            return Ok(None);
+        };
+        let dist_dir = record.dist_dir.as_path();
+
+        let mut removed = 0;
+        for path in &record.files {
+            if path.is_file() {
+                std::fs::remove_file(path)?;
+                removed += 1;
+            }
+            // 独立文件的目录清空后一并删除，目录中还有其他文件时保留
+            if let Some(parent) = path.parent().filter(|parent| *parent != dist_dir) {
+                let _ = std::fs::remove_dir(parent);
+            }
+        }
+        let mut restored = 0;
+        if let Some(batch_path) = &record.trash_batch {
+            let batch = trash::list_batches(dist_dir.join(TRASH_DIR))?
+                .into_iter()
+                .find(|batch| batch.path().file_name() == batch_path.file_name());
+            if let Some(batch) = batch {
+                restored = batch.restore()?;
+            }
+        }
+        std::fs::remove_file(root_path.join(LAST_PACKAGE))?;
+
+        Ok(Some(Undone { removed, restored }))
+    }
+}
+
+/// 转换完成的 tex 文件
+pub struct PackagedFile {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+/// 一次打包的内容
+pub struct Package<'a> {
+    pub name: &'a str,
+    /// 贴纸在游戏目录中的位置，见 [TargetGame::stamp_dir](crate::game::TargetGame::stamp_dir)
+    pub stamp_dir: &'a str,
+    pub files: &'a [PackagedFile],
+    pub readme: Option<&'a str>,
+    pub mod_info: &'a ModInfo,
+    /// 压缩包中 tex 文件的压缩方式
+    pub compression: zip::CompressionMethod,
+    /// 压缩级别，None 为默认级别
+    pub compression_level: Option<i64>,
+}
+
+/// 打包将生成的一个输出，见 [OutputProfile::plan]
+pub struct PlannedOutput {
+    /// 输出的文件或目录
+    pub path: PathBuf,
+    /// 输出的总大小，压缩包为压缩后的大小
+    pub size: u64,
+    /// 输出中的文件，压缩包为包内路径，目录为相对该目录的路径
+    pub entries: Vec<PlannedEntry>,
+}
+
+pub struct PlannedEntry {
+    pub path: String,
+    /// 未压缩的大小
+    pub size: u64,
+}
+
+impl OutputProfile {
+    pub fn is_empty(&self) -> bool {
+        !self.loose_files && !self.nativepc_zip && !self.fluffy_zip && self.install_dir.is_none()
+    }
+
+    /// 按配置写入 dist 目录中的输出，返回生成的文件或目录
+    ///
+    /// 已存在的同名文件先移入回收站，写入的文件记录在工作区的 [PackageRecord] 中，
+    /// 没有写入任何文件（只安装到游戏目录）时保留上一次的记录。
+    /// 安装到游戏目录由 [install](crate::install) 负责。
+    pub fn write<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        root_path: P,
+        dist_dir: Q,
+        package: &Package,
+        trash: &mut Trash,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let dist_dir = dist_dir.as_ref();
+        let modinfo_json = modinfo_json(package)?;
+        let mut outputs = vec![];
+        let mut files = vec![];
+        if self.loose_files {
+            let output_dir = dist_dir.join(package.name);
+            files.extend(write_loose(
+                &output_dir,
+                package,
+                &[("modinfo.json", &modinfo_json)],
+                trash,
+            )?);
+            outputs.push(output_dir);
+        }
+        if self.nativepc_zip {
+            let zip_path = dist_dir.join(format!("{}.zip", package.name));
+            trash.protect(&zip_path)?;
+            write_zip(&zip_path, "", package, &[("modinfo.json", &modinfo_json)])?;
+            files.push(zip_path.clone());
+            outputs.push(zip_path);
+        }
+        if self.fluffy_zip {
+            // Fluffy Mod Manager 要求压缩包内有一层以 MOD 命名的目录，modinfo.ini 提供显示名称
+            let zip_path = dist_dir.join(format!("{}_fluffy.zip", package.name));
+            let modinfo_ini = fluffy_modinfo(package);
+            trash.protect(&zip_path)?;
+            write_zip(
+                &zip_path,
+                &format!("{}/", package.name),
+                package,
+                &[
+                    ("modinfo.ini", &modinfo_ini),
+                    ("modinfo.json", &modinfo_json),
+                ],
+            )?;
+            files.push(zip_path.clone());
+            outputs.push(zip_path);
+        }
+        if !files.is_empty() {
+            PackageRecord {
+                dist_dir: std::path::absolute(dist_dir)?,
+                files: files
+                    .into_iter()
+                    .map(std::path::absolute)
+                    .collect::<std::io::Result<_>>()?,
+                trash_batch: trash.batch_path().map(Path::to_path_buf),
+            }
+            .save(root_path.as_ref())?;
+        }
+
+        Ok(outputs)
+    }
+
+    /// 列出 [write](Self::write) 和安装将生成的文件，不写入任何内容
+    ///
+    /// 压缩包在内存中生成以得到压缩后的大小。
+    pub fn plan<P: AsRef<Path>>(
+        &self,
+        dist_dir: P,
+        package: &Package,
+    ) -> anyhow::Result<Vec<PlannedOutput>> {
+        let dist_dir = dist_dir.as_ref();
+        let modinfo_json = modinfo_json(package)?;
+        let mut outputs = vec![];
+        if self.loose_files {
+            let entries = planned_entries("", "", package, &[("modinfo.json", &modinfo_json)]);
+            outputs.push(PlannedOutput {
+                path: dist_dir.join(package.name),
+                size: entries.iter().map(|entry| entry.size).sum(),
+                entries,
+            });
+        }
+        if self.nativepc_zip {
+            let extra = [("modinfo.json", modinfo_json.as_str())];
+            outputs.push(PlannedOutput {
+                path: dist_dir.join(format!("{}.zip", package.name)),
+                size: zip_size("", package, &extra)?,
+                entries: planned_entries("", &format!("{}/", package.stamp_dir), package, &extra),
+            });
+        }
+        if self.fluffy_zip {
+            let prefix = format!("{}/", package.name);
+            let modinfo_ini = fluffy_modinfo(package);
+            let extra = [
+                ("modinfo.ini", modinfo_ini.as_str()),
+                ("modinfo.json", modinfo_json.as_str()),
+            ];
+            outputs.push(PlannedOutput {
+                path: dist_dir.join(format!("{}_fluffy.zip", package.name)),
+                size: zip_size(&prefix, package, &extra)?,
+                entries: planned_entries(
+                    &prefix,
+                    &format!("{}{}/", prefix, package.stamp_dir),
+                    package,
+                    &extra,
+                ),
+            });
+        }
+        if let Some(install_dir) = &self.install_dir {
+            let entries = package
+                .files
+                .iter()
+                .map(|file| PlannedEntry {
+                    path: format!("{}/{}", package.stamp_dir, file.file_name),
+                    size: file.data.len() as u64,
+                })
+                .collect::<Vec<_>>();
+            outputs.push(PlannedOutput {
+                path: install_dir.clone(),
+                size: entries.iter().map(|entry| entry.size).sum(),
+                entries,
+            });
+        }
+        Ok(outputs)
+    }
+}
+
+/// 文本文件放在 `prefix` 下，tex 文件放在 `file_prefix` 下，与 [write_loose] 和 [write_zip] 的布局一致
+fn planned_entries(
+    prefix: &str,
+    file_prefix: &str,
+    package: &Package,
+    extra: &[(&str, &str)],
+) -> Vec<PlannedEntry> {
+    let readme = package.readme.map(|readme| ("README.txt", readme));
+    let texts = extra
+        .iter()
+        .copied()
+        .chain(readme)
+        .map(|(name, content)| PlannedEntry {
+            path: format!("{}{}", prefix, name),
+            size: content.len() as u64,
+        });
+    let files = package.files.iter().map(|file| PlannedEntry {
+        path: format!("{}{}", file_prefix, file.file_name),
+        size: file.data.len() as u64,
+    });
+
+    texts.chain(files).collect()
+}
+
+/// 在内存中生成压缩包，返回压缩后的大小
+fn zip_size(prefix: &str, package: &Package, extra: &[(&str, &str)]) -> anyhow::Result<u64> {
+    let cursor = write_zip_to(Cursor::new(vec![]), prefix, package, extra)?;
+    Ok(cursor.into_inner().len() as u64)
+}
+
+impl std::fmt::Display for OutputProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names = vec![];
+        if self.loose_files {
+            names.push(t!("独立文件").to_string());
+        }
+        if self.nativepc_zip {
+            names.push("nativePC zip".to_string());
+        }
+        if self.fluffy_zip {
+            names.push(t!("Fluffy 压缩包").to_string());
+        }
+        if let Some(install_dir) = &self.install_dir {
+            names.push(t!("安装到 {}", install_dir.display()));
+        }
+        if names.is_empty() {
+            write!(f, "{}", t!("无"))
+        } else {
+            write!(f, "{}", names.join(t!("、")))
+        }
+    }
+}
+
+/// 写入输出的 modinfo.json
+pub fn modinfo_json(package: &Package) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(&ModInfoFile {
+        info: package.mod_info,
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        files: package.files.iter().map(|f| f.file_name.as_str()).collect(),
+    })?)
+}
+
+/// Fluffy Mod Manager 的 modinfo.ini
+fn fluffy_modinfo(package: &Package) -> String {
+    let mod_info = package.mod_info;
+    let description = mod_info
+        .description
+        .clone()
+        .unwrap_or_else(|| t!("MHW 贴纸 MOD，共 {} 个文件", package.files.len()));
+    let mut modinfo = format!("name={}\ndescription={}\n", mod_info.name, description);
+    if let Some(author) = &mod_info.author {
+        modinfo.push_str(&format!("author={}\n", author));
+    }
+    if let Some(version) = &mod_info.version {
+        modinfo.push_str(&format!("version={}\n", version));
+    }
+
+    modinfo
+}
+
+/// `extra` 为额外写入的文本文件，返回写入的文件
+fn write_loose(
+    output_dir: &Path,
+    package: &Package,
+    extra: &[(&str, &str)],
+    trash: &mut Trash,
+) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output_dir)?;
+    let mut written = vec![];
+    let readme = package.readme.map(|readme| ("README.txt", readme));
+    for (name, content) in extra.iter().copied().chain(readme) {
+        let path = output_dir.join(name);
+        trash.protect(&path)?;
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    for file in package.files {
+        let path = output_dir.join(&file.file_name);
+        trash.protect(&path)?;
+        std::fs::write(&path, &file.data)?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// `prefix` 为压缩包内所有文件的上级目录，`extra` 为额外写入的文本文件
+pub fn write_zip(
+    zip_path: &Path,
+    prefix: &str,
+    package: &Package,
+    extra: &[(&str, &str)],
+) -> anyhow::Result<()> {
+    if let Some(parent) = zip_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_zip_to(File::create(zip_path)?, prefix, package, extra)?;
+
+    Ok(())
+}
+
+fn write_zip_to<W: Write + Seek>(
+    writer: W,
+    prefix: &str,
+    package: &Package,
+    extra: &[(&str, &str)],
+) -> anyhow::Result<W> {
+    let mut zip_writer = ZipWriter::new(writer);
+    let readme = package.readme.map(|readme| ("README.txt", readme));
+    for (name, content) in extra.iter().copied().chain(readme) {
+        zip_writer.start_file(format!("{}{}", prefix, name), SimpleFileOptions::default())?;
+        zip_writer.write_all(content.as_bytes())?;
+    }
+    for file in package.files {
+        zip_writer.start_file(
+            format!("{}{}/{}", prefix, package.stamp_dir, file.file_name),
+            SimpleFileOptions::default()
+                .compression_method(package.compression)
+                .compression_level(package.compression_level),
+        )?;
+        zip_writer.write_all(&file.data)?;
+    }
+
+    Ok(zip_writer.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_packaging_settings() {
+        let mut mod_info = ModInfo::new("example");
+        let mut settings = PackagingSettings::default();
+        assert_eq!(settings.output_name("example", &mod_info), "example");
+        assert_eq!(
+            settings.archive_path(TargetGame::Mhw),
+            TargetGame::Mhw.stamp_dir()
+        );
+
+        settings.name_template = Some("{name}-v{version}".to_string());
+        assert_eq!(settings.output_name("example", &mod_info), "example");
+        settings.name_template = Some("{name}_dev".to_string());
+        assert_eq!(settings.output_name("example", &mod_info), "example_dev");
+        settings.name_template = Some("{name}-v{version}".to_string());
+        mod_info.version = Some("1.2/beta".to_string());
+        assert_eq!(
+            settings.output_name("example", &mod_info),
+            "example-v1.2_beta"
+        );
+
+        assert_eq!(
+            PackagingSettings::normalize_archive_path("\\nativePC\\ui//tex/./").unwrap(),
+            "nativePC/ui/tex"
+        );
+        assert!(PackagingSettings::normalize_archive_path("nativePC/../..").is_err());
+        assert!(PackagingSettings::normalize_archive_path("C:/nativePC").is_err());
+    }
+
+    #[test]
+    fn test_write_outputs() {
+        let base_dir = TempDir::new("output-test");
+        let dist_dir = base_dir.join("dist");
+        let root_path = base_dir.join("workspace");
+        let other_root = base_dir.join("other");
+        std::fs::create_dir_all(&root_path).unwrap();
+        std::fs::create_dir_all(&other_root).unwrap();
+        let files = [PackagedFile {
+            file_name: "chat_stamp00_ID.tex".to_string(),
+            data: vec![1, 2, 3],
+        }];
+        let package = Package {
+            name: "example",
+            stamp_dir: TargetGame::Mhw.stamp_dir(),
+            files: &files,
+            readme: Some("readme"),
+            mod_info: &ModInfo::new("example"),
+            compression: zip::CompressionMethod::Deflated,
+            compression_level: Some(9),
+        };
+        let profile = OutputProfile {
+            fluffy_zip: true,
+            ..Default::default()
+        };
+
+        let planned = profile.plan(&dist_dir, &package).unwrap();
+        assert_eq!(planned.len(), 3);
+        assert!(!dist_dir.exists());
+
+        let mut trash = Trash::new(dist_dir.join(TRASH_DIR)).unwrap();
+        let outputs = profile
+            .write(&root_path, &dist_dir, &package, &mut trash)
+            .unwrap();
+        assert_eq!(outputs.len(), 3);
+        for (planned, output) in planned.iter().zip(&outputs) {
+            assert_eq!(planned.path, *output);
+        }
+        assert_eq!(
+            planned[2].size,
+            std::fs::metadata(dist_dir.join("example_fluffy.zip"))
+                .unwrap()
+                .len()
+        );
+        assert_eq!(trash.len(), 0);
+
+        // 再次打包时覆盖的文件移入回收站
+        profile
+            .write(&root_path, &dist_dir, &package, &mut trash)
+            .unwrap();
+        assert_eq!(trash.len(), 5);
+        assert!(dist_dir.join("example/chat_stamp00_ID.tex").exists());
+
+        let zip =
+            zip::ZipArchive::new(File::open(dist_dir.join("example_fluffy.zip")).unwrap()).unwrap();
+        let mut names = zip.file_names().collect::<Vec<_>>();
+        names.sort();
+        let mut planned_names = planned[2]
+            .entries
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect::<Vec<_>>();
+        planned_names.sort();
+        assert_eq!(names, planned_names);
+
+        // 只安装到游戏目录时不覆盖打包记录
+        let install_only = OutputProfile {
+            loose_files: false,
+            nativepc_zip: false,
+            fluffy_zip: false,
+            install_dir: Some(base_dir.join("game")),
+        };
+        install_only
+            .write(&root_path, &dist_dir, &package, &mut trash)
+            .unwrap();
+        assert_eq!(
+            PackageRecord::load(&root_path)
+                .unwrap()
+                .unwrap()
+                .files
+                .len(),
+            5
+        );
+
+        // 共用输出目录的其他工作区没有可撤销的打包
+        assert!(PackageRecord::undo(&other_root).unwrap().is_none());
+
+        // 撤销第二次打包，恢复第一次打包的文件
+        let undone = PackageRecord::undo(&root_path).unwrap().unwrap();
+        assert_eq!((undone.removed, undone.restored), (5, 5));
+        assert!(dist_dir.join("example/chat_stamp00_ID.tex").exists());
+        assert!(PackageRecord::undo(&root_path).unwrap().is_none());
+        assert_eq!(
+            names,
+            [
+                "example/README.txt",
+                "example/modinfo.ini",
+                "example/modinfo.json",
+                "example/nativePC/ui/chat/tex/stamp/chat_stamp00_ID.tex",
+            ]
+        );
+    }
+}
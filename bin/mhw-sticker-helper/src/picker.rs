@@ -0,0 +1,315 @@
+//! 长列表选择
+//!
+//! 工作区和贴纸较多时直接使用 Select 难以找到目标，超过 [FILTER_THRESHOLD] 项时先输入关键字，
+//! 按模糊匹配的得分排序后再选择。已更改的项以 `*` 标出，以 `*` 开头的关键字只显示已更改的项，
+//! 以 `#` 开头的关键字只显示带有匹配标签的项，
+//! 以 `^` 开头时显示全部并跳到以其后内容开头的第一项。多选时同样先筛选，未显示的项保持原有的勾选状态。
+//!
+//! 超过 [PAGE_SIZE] 项的列表分页显示，←→ 翻页，避免提示被滚出终端。
+
+use std::fmt::Display;
+
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect, Select};
+
+use crate::i18n::t;
+
+/// 超过该数量时先筛选
+const FILTER_THRESHOLD: usize = 10;
+const MODIFIED_MARK: char = '*';
+const TAG_MARK: char = '#';
+const JUMP_MARK: char = '^';
+/// 每页显示的项数，用于所有较长的 Select 和 MultiSelect
+pub const PAGE_SIZE: usize = 15;
+
+/// 可筛选的选择列表
+pub struct Picker<'a, T> {
+    prompt: String,
+    items: &'a [T],
+    default: usize,
+    modified: Vec<bool>,
+    tags: Vec<Vec<String>>,
+}
+
+impl<'a, T: Display> Picker<'a, T> {
+    pub fn new(prompt: &str, items: &'a [T]) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            items,
+            default: 0,
+            modified: vec![],
+            tags: vec![],
+        }
+    }
+
+    /// 默认选中的项
+    pub fn default(mut self, default: usize) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// 各项是否已更改，与 `items` 一一对应
+    pub fn modified(mut self, modified: Vec<bool>) -> Self {
+        self.modified = modified;
+        self
+    }
+
+    /// 各项的标签，与 `items` 一一对应，例如贴纸的标签
+    pub fn tags(mut self, tags: Vec<Vec<String>>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// 返回选中项在 `items` 中的下标，列表为空时返回错误
+    pub fn interact(self) -> anyhow::Result<usize> {
+        anyhow::ensure!(!self.items.is_empty(), t!("没有可选择的项"));
+        let labels = self.labels();
+
+        loop {
+            let mut matches = (0..labels.len()).collect::<Vec<_>>();
+            let mut default = self.default;
+            if labels.len() > FILTER_THRESHOLD {
+                let query = self.query(labels.len(), true)?;
+                let query = query.trim();
+                match query.strip_prefix(JUMP_MARK) {
+                    Some(prefix) => match jump_target(&labels, prefix.trim()) {
+                        Some(target) => default = target,
+                        None => matches.clear(),
+                    },
+                    None => matches = self.filter(&labels, query),
+                }
+            }
+            if matches.is_empty() {
+                println!("{}", t!("没有匹配的项，请重新输入"));
+                continue;
+            }
+
+            let items = matches
+                .iter()
+                .map(|&index| &labels[index])
+                .collect::<Vec<_>>();
+            let selection = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.page_prompt(items.len()))
+                .items(&items)
+                .default(
+                    matches
+                        .iter()
+                        .position(|&index| index == default)
+                        .unwrap_or_default(),
+                )
+                .max_length(PAGE_SIZE)
+                .interact()?;
+            return Ok(matches[selection]);
+        }
+    }
+
+    /// 勾选多项，`checked` 为各项原有的勾选状态，返回勾选的项在 `items` 中的下标
+    ///
+    /// 项数较多时先筛选，只显示匹配的项，未显示的项保持原有的勾选状态。
+    pub fn interact_multi(self, checked: &[bool]) -> anyhow::Result<Vec<usize>> {
+        let mut checked = checked.to_vec();
+        checked.resize(self.items.len(), false);
+        if self.items.is_empty() {
+            return Ok(vec![]);
+        }
+        let labels = self.labels();
+
+        let matches = loop {
+            if labels.len() <= FILTER_THRESHOLD {
+                break (0..labels.len()).collect::<Vec<_>>();
+            }
+            let query = self.query(labels.len(), false)?;
+            let matches = self.filter(&labels, query.trim());
+            if !matches.is_empty() {
+                break matches;
+            }
+            println!("{}", t!("没有匹配的项，请重新输入"));
+        };
+        let items = matches
+            .iter()
+            .map(|&index| &labels[index])
+            .collect::<Vec<_>>();
+        let defaults = matches
+            .iter()
+            .map(|&index| checked[index])
+            .collect::<Vec<_>>();
+        let selections = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(self.page_prompt(items.len()))
+            .items(&items)
+            .defaults(&defaults)
+            .max_length(PAGE_SIZE)
+            .interact()?;
+        for (position, &index) in matches.iter().enumerate() {
+            checked[index] = selections.contains(&position);
+        }
+
+        Ok((0..checked.len()).filter(|&index| checked[index]).collect())
+    }
+
+    /// 显示的文本，已更改的项加上标记
+    fn labels(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if self.is_modified(index) {
+                    format!("{} {}", item, MODIFIED_MARK)
+                } else {
+                    item.to_string()
+                }
+            })
+            .collect()
+    }
+
+    /// 输入筛选关键字，提示中只列出当前列表支持的前缀
+    fn query(&self, count: usize, jump: bool) -> anyhow::Result<String> {
+        let mut prompt = t!("共 {} 项，输入关键字筛选", count);
+        if !self.modified.is_empty() {
+            prompt.push_str(t!("，以 * 开头只显示已更改的项"));
+        }
+        if !self.tags.is_empty() {
+            prompt.push_str(t!("，以 # 开头按标签筛选"));
+        }
+        if jump {
+            prompt.push_str(t!("，以 ^ 开头跳到该字母"));
+        }
+        prompt.push_str(t!(" (留空显示全部)"));
+
+        Ok(Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .allow_empty(true)
+            .interact_text()?)
+    }
+
+    fn page_prompt(&self, count: usize) -> String {
+        if count > PAGE_SIZE {
+            t!("{} (←→翻页)", self.prompt)
+        } else {
+            self.prompt.clone()
+        }
+    }
+
+    fn is_modified(&self, index: usize) -> bool {
+        self.modified.get(index).copied().unwrap_or_default()
+    }
+
+    /// 是否有包含 `keyword` 的标签，忽略大小写
+    fn has_tag(&self, index: usize, keyword: &str) -> bool {
+        let keyword = keyword.to_lowercase();
+        self.tags
+            .get(index)
+            .is_some_and(|tags| tags.iter().any(|tag| tag.to_lowercase().contains(&keyword)))
+    }
+
+    /// 匹配关键字的下标，得分高的在前，关键字为空时保持原有顺序
+    ///
+    /// `*` 和 `#` 只在提供了更改状态和标签时作为前缀，`#标签 关键字` 在带有该标签的项中筛选。
+    fn filter(&self, labels: &[String], query: &str) -> Vec<usize> {
+        let (modified_only, query) = match query.strip_prefix(MODIFIED_MARK) {
+            Some(rest) if !self.modified.is_empty() => (true, rest.trim()),
+            _ => (false, query),
+        };
+        let (tag, pattern) = match query.strip_prefix(TAG_MARK) {
+            Some(rest) if !self.tags.is_empty() => {
+                let (tag, pattern) = rest.split_once(' ').unwrap_or((rest, ""));
+                (Some(tag), pattern.trim())
+            }
+            _ => (None, query),
+        };
+        let mut matches = labels
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !modified_only || self.is_modified(*index))
+            .filter(|(index, _)| tag.is_none_or(|tag| self.has_tag(*index, tag)))
+            .filter_map(|(index, label)| Some((index, fuzzy_score(pattern, label)?)))
+            .collect::<Vec<_>>();
+        // 稳定排序，得分相同时保持原有顺序
+        matches.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+        matches.into_iter().map(|(index, _)| index).collect()
+    }
+}
+
+/// 第一项以 `prefix` 开头的下标，忽略大小写
+fn jump_target(labels: &[String], prefix: &str) -> Option<usize> {
+    let prefix = prefix.to_lowercase();
+    labels
+        .iter()
+        .position(|label| label.to_lowercase().starts_with(&prefix))
+}
+
+/// 关键字中的字符按顺序出现在文本中时返回得分，忽略大小写和空白
+///
+/// 连续匹配和在单词开头的匹配得分更高，`pattern` 为空时得分为 0。
+fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text = text
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let found = position + text[position..].iter().position(|&t| t == c)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(fuzzy_score("", "chat_stamp00_ID"), Some(0));
+        assert!(fuzzy_score("stamp03", "chat_stamp00_ID").is_none());
+        assert!(fuzzy_score("S00", "chat_stamp00_ID").is_some());
+        assert!(fuzzy_score("st00", "chat_stamp00_ID") > fuzzy_score("ht00", "chat_stamp00_ID"));
+
+        let items = ["chat_stamp00_ID", "chat_stamp01_ID", "猫 chat_stamp10_ID"];
+        let picker = Picker::new("", &items).modified(vec![false, true, true]);
+        let labels = items.map(String::from);
+        assert_eq!(picker.filter(&labels, ""), [0, 1, 2]);
+        assert_eq!(picker.filter(&labels, "10"), [2]);
+        assert_eq!(picker.filter(&labels, "*"), [1, 2]);
+        assert_eq!(picker.filter(&labels, "* 猫"), [2]);
+        assert_eq!(jump_target(&labels, "C"), Some(0));
+        assert_eq!(jump_target(&labels, "猫"), Some(2));
+        assert_eq!(jump_target(&labels, "x"), None);
+
+        // 没有提供标签时 # 作为普通字符
+        assert!(picker.filter(&labels, "#猫").is_empty());
+        let picker = picker.tags(vec![
+            vec![],
+            vec!["Cats".to_string()],
+            vec!["cats".to_string()],
+        ]);
+        assert_eq!(picker.filter(&labels, "#cat"), [1, 2]);
+        assert_eq!(picker.filter(&labels, "#cat 10"), [2]);
+        assert_eq!(picker.filter(&labels, "* #dog"), [] as [usize; 0]);
+
+        // 没有提供更改状态时 * 作为普通字符
+        let picker = Picker::new("", &items);
+        assert!(picker.filter(&labels, "*").is_empty());
+
+        let empty: [&str; 0] = [];
+        assert!(Picker::new("", &empty).interact().is_err());
+        assert!(Picker::new("", &empty)
+            .interact_multi(&[])
+            .unwrap()
+            .is_empty());
+    }
+}
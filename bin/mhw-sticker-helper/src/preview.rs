@@ -0,0 +1,149 @@
+//! 工作区预览页面
+//!
+//! 将每个贴纸图集切分为单张贴纸写入 `preview/` 目录，并生成引用这些图片的 `preview.html`，
+//! 与原版不同的贴纸会高亮显示。
+
+use std::{fmt::Write, path::PathBuf};
+
+use tex_convert::diff::{diff_images, DiffOptions};
+use tracing::warn;
+
+use crate::{
+    i18n::t,
+    workspace::{StickerPack, Workspace},
+};
+
+const PREVIEW_DIR: &str = "preview";
+
+/// 单张贴纸的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileState {
+    Unchanged,
+    Modified,
+    /// 导入的贴纸没有原版可比较
+    Imported,
+}
+
+impl TileState {
+    fn class(&self) -> &'static str {
+        match self {
+            TileState::Unchanged => "unchanged",
+            TileState::Modified => "modified",
+            TileState::Imported => "imported",
+        }
+    }
+}
+
+/// 生成预览页面，返回 `preview.html` 的路径
+pub fn write_preview(workspace: &Workspace) -> anyhow::Result<PathBuf> {
+    let root_path = PathBuf::from(workspace.root_path());
+    let preview_dir = root_path.join(PREVIEW_DIR);
+    std::fs::create_dir_all(&preview_dir)?;
+
+    let names = workspace.names();
+    let mut sections = String::new();
+    let mut modified_count = 0;
+    for sticker in workspace.info().sticker_packs() {
+        // 同名的 tga 文件优先，与打包时一致
+        let tga_name = format!("{}.tga", sticker.name);
+        let sticker = if root_path.join(&tga_name).exists() {
+            StickerPack {
+                filename: tga_name,
+                ..sticker.clone()
+            }
+        } else {
+            sticker.clone()
+        };
+        let image = match workspace.load_sticker_image(&sticker) {
+            Ok(image) => image,
+            Err(e) => {
+                warn!("{}", t!("无法读取贴纸：{}：{}, 跳过", sticker.filename, e));
+                continue;
+            }
+        };
+        let layout = sticker.layout;
+        let Ok(tiles) = layout.slice(&image) else {
+            warn!(
+                "{}",
+                t!(
+                    "贴纸尺寸错误：{}：{}x{}, 跳过",
+                    sticker.filename,
+                    image.width(),
+                    image.height()
+                )
+            );
+            continue;
+        };
+        let original_tiles = workspace
+            .load_original_image(&sticker)?
+            .and_then(|original| Some(layout.slice(&original).ok()?.collect::<Vec<_>>()));
+
+        writeln!(
+            sections,
+            "<h2>{} <small>{}</small></h2>",
+            escape(&names.display(&sticker.name)),
+            escape(&sticker.filename)
+        )?;
+        writeln!(sections, "<div class=\"sheet\">")?;
+        for (index, tile) in tiles.enumerate() {
+            let state = match &original_tiles {
+                None => TileState::Imported,
+                Some(tiles) => match diff_images(&tiles[index], &tile, &DiffOptions::default()) {
+                    Ok(report) if report.differing_pixels == 0 => TileState::Unchanged,
+                    _ => TileState::Modified,
+                },
+            };
+            if state == TileState::Modified {
+                modified_count += 1;
+            }
+
+            let tile_name = format!("{}_{}.png", sticker.name, index);
+            tile.save(preview_dir.join(&tile_name))?;
+            writeln!(
+                sections,
+                "<figure class=\"{}\"><img src=\"{}/{}\"><figcaption>{}</figcaption></figure>",
+                state.class(),
+                PREVIEW_DIR,
+                escape(&tile_name),
+                escape(&names.display_tile(&sticker.name, index as u32)),
+            )?;
+        }
+        writeln!(sections, "</div>")?;
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>{title} - 贴纸预览</title>
+<style>
+body {{ font-family: sans-serif; background: #2b2b2b; color: #ddd; }}
+.sheet {{ display: flex; flex-wrap: wrap; gap: 8px; }}
+figure {{ margin: 0; padding: 4px; border: 3px solid transparent; text-align: center; }}
+figure img {{ display: block; background: repeating-conic-gradient(#555 0 25%, #444 0 50%) 0 0 / 16px 16px; }}
+figure.modified {{ border-color: #e0b000; }}
+figure.imported {{ border-color: #3a8ee6; }}
+figcaption {{ font-size: 12px; margin-top: 4px; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>已更改的贴纸：{modified_count}（黄色边框），导入的贴纸以蓝色边框标出</p>
+{sections}</body>
+</html>
+"#,
+        title = escape(&root_path.file_name().unwrap_or_default().to_string_lossy()),
+    );
+    let html_path = root_path.join("preview.html");
+    std::fs::write(&html_path, html)?;
+
+    Ok(html_path)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,209 @@
+//! Nexus Mods 发布包
+//!
+//! 在 `dist/<工作区>_nexus/` 下生成可直接上传的压缩包、已更改贴纸的预览拼图，
+//! 以及 BBCode 格式的说明模板（文件列表与安装方法），作者只需补充预览图的链接。
+
+use std::path::{Path, PathBuf};
+
+use image::{imageops, Rgba, RgbaImage};
+use tex_convert::diff::{diff_images, DiffOptions};
+use tracing::warn;
+
+use crate::{
+    game::TargetGame,
+    i18n::t,
+    output::{self, Package},
+    trash::Trash,
+    util::sanitize_file_name,
+    workspace::{StickerPack, Workspace},
+};
+
+/// 拼图每行的贴纸数量
+const COLUMNS: u32 = 6;
+/// 贴纸之间的间距
+const SPACING: u32 = 8;
+const BACKGROUND: Rgba<u8> = Rgba([48, 48, 48, 255]);
+
+/// 生成发布包，返回生成的文件
+///
+/// `stickers` 为打包的贴纸及合成图块后的图集，与 `package` 中的文件对应。
+pub fn write_release<P: AsRef<Path>>(
+    dist_dir: P,
+    workspace: &Workspace,
+    package: &Package,
+    stickers: &[(StickerPack, Option<RgbaImage>)],
+    trash: &mut Trash,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let release_dir = dist_dir.as_ref().join(format!("{}_nexus", package.name));
+    std::fs::create_dir_all(&release_dir)?;
+    let mut outputs = vec![];
+
+    let archive_name = match &package.mod_info.version {
+        Some(version) => format!("{}-{}.zip", package.name, sanitize_file_name(version)),
+        None => format!("{}.zip", package.name),
+    };
+    let archive_path = release_dir.join(archive_name);
+    trash.protect(&archive_path)?;
+    output::write_zip(
+        &archive_path,
+        "",
+        package,
+        &[("modinfo.json", &output::modinfo_json(package)?)],
+    )?;
+    outputs.push(archive_path);
+
+    let tiles = modified_tiles(workspace, stickers);
+    let has_preview = !tiles.is_empty();
+    if has_preview {
+        let preview_path = release_dir.join("preview.png");
+        trash.protect(&preview_path)?;
+        collage(&tiles).save(&preview_path)?;
+        outputs.push(preview_path);
+    }
+
+    let description_path = release_dir.join("description.txt");
+    trash.protect(&description_path)?;
+    let game = workspace.info().target_game();
+    std::fs::write(&description_path, description(package, game, has_preview))?;
+    outputs.push(description_path);
+
+    Ok(outputs)
+}
+
+/// 与原版不同的贴纸，导入的贴纸没有原版，全部视为已更改
+fn modified_tiles(
+    workspace: &Workspace,
+    stickers: &[(StickerPack, Option<RgbaImage>)],
+) -> Vec<RgbaImage> {
+    let mut tiles = vec![];
+    for (sticker, composed) in stickers {
+        let image = match composed {
+            Some(image) => image.clone(),
+            None => match workspace.load_sticker_image(sticker) {
+                Ok(image) => image,
+                Err(e) => {
+                    warn!("{}", t!("无法读取贴纸：{}：{}, 跳过", sticker.filename, e));
+                    continue;
+                }
+            },
+        };
+        let layout = sticker.layout;
+        let Ok(sticker_tiles) = layout.slice(&image) else {
+            continue;
+        };
+        let original = workspace
+            .load_original_image(sticker)
+            .ok()
+            .flatten()
+            .and_then(|original| Some(layout.slice(&original).ok()?.collect::<Vec<_>>()));
+        for (index, tile) in sticker_tiles.enumerate() {
+            let unchanged = original.as_ref().is_some_and(|original| {
+                diff_images(&original[index], &tile, &DiffOptions::default())
+                    .is_ok_and(|report| report.differing_pixels == 0)
+            });
+            if !unchanged {
+                tiles.push(tile);
+            }
+        }
+    }
+
+    tiles
+}
+
+/// 将贴纸按网格排列在深色背景上
+fn collage(tiles: &[RgbaImage]) -> RgbaImage {
+    let tile_w = tiles.iter().map(|tile| tile.width()).max().unwrap_or(0);
+    let tile_h = tiles.iter().map(|tile| tile.height()).max().unwrap_or(0);
+    let columns = COLUMNS.min(tiles.len() as u32).max(1);
+    let rows = (tiles.len() as u32).div_ceil(columns);
+    let mut image = RgbaImage::from_pixel(
+        columns * (tile_w + SPACING) + SPACING,
+        rows * (tile_h + SPACING) + SPACING,
+        BACKGROUND,
+    );
+    for (index, tile) in tiles.iter().enumerate() {
+        let (row, col) = (index as u32 / columns, index as u32 % columns);
+        let x = SPACING + col * (tile_w + SPACING);
+        let y = SPACING + row * (tile_h + SPACING);
+        imageops::overlay(&mut image, tile, x as i64, y as i64);
+    }
+
+    image
+}
+
+/// BBCode 格式的说明模板，安装方法按工作区的目标游戏和包内路径填写
+fn description(package: &Package, game: TargetGame, has_preview: bool) -> String {
+    let mod_info = package.mod_info;
+    let mut text = format!("[size=5][b]{}[/b][/size]", mod_info.name);
+    if let Some(version) = &mod_info.version {
+        text.push_str(&format!(" v{}", version));
+    }
+    text.push('\n');
+    if let Some(author) = &mod_info.author {
+        text.push_str(&t!("作者：{}\n", author));
+    }
+    if let Some(description) = &mod_info.description {
+        text.push_str(&format!("\n{}\n", description));
+    }
+    if has_preview {
+        text.push_str(t!(
+            "\n[b]预览[/b]\n[img]<上传 preview.png 后替换为图片链接>[/img]\n"
+        ));
+    }
+    text.push_str(&t!(
+        "\n[b]包含的文件[/b] ({})\n[list]\n",
+        package.files.len()
+    ));
+    for file in package.files {
+        text.push_str(&format!("[*]{}/{}\n", package.stamp_dir, file.file_name));
+    }
+    text.push_str("[/list]\n");
+    // 压缩包中的顶层文件夹，默认的包内路径为 nativePC
+    let top_dir = package.stamp_dir.split('/').next().unwrap_or_default();
+    text.push_str(&t!(
+        "\n[b]安装方法[/b]\n[list=1]\n[*]备份游戏目录中已有的同名文件（如果有）\n[*]将压缩包中的 {} 文件夹解压到游戏目录，即 {} 所在的目录\n[/list]\n",
+        top_dir,
+        game.exe_name()
+    ));
+    text.push_str(t!("\n[b]卸载方法[/b]\n删除上述文件，并恢复备份的文件。\n"));
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{ModInfo, PackagedFile};
+
+    use super::*;
+
+    #[test]
+    fn test_collage() {
+        let tiles = vec![RgbaImage::from_pixel(4, 3, Rgba([255, 0, 0, 255])); 7];
+        let image = collage(&tiles);
+        assert_eq!(image.dimensions(), (6 * 12 + 8, 2 * 11 + 8));
+        assert_eq!(*image.get_pixel(8, 8), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(0, 0), BACKGROUND);
+    }
+
+    #[test]
+    fn test_description() {
+        let files = [PackagedFile {
+            file_name: "chat_stamp00_ID.tex".to_string(),
+            data: vec![],
+        }];
+        let package = Package {
+            name: "example",
+            stamp_dir: "mods/stamp",
+            files: &files,
+            readme: None,
+            mod_info: &ModInfo::new("example"),
+            compression: zip::CompressionMethod::Stored,
+            compression_level: None,
+        };
+        let text = description(&package, TargetGame::Iceborne, false);
+        assert!(text.contains("[*]mods/stamp/chat_stamp00_ID.tex"));
+        assert!(text.contains(" mods "));
+        assert!(text.contains(TargetGame::Iceborne.exe_name()));
+        assert!(!text.contains("nativePC"));
+    }
+}
@@ -0,0 +1,130 @@
+//! 工作区快照
+//!
+//! 快照保存在工作区的 `.snapshots/<时间戳>/` 目录下，只包含创建时已更改的贴纸文件，
+//! 未更改的贴纸可随时从内置资源还原。
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::t, workspace::Workspace};
+
+const SNAPSHOT_DIR: &str = ".snapshots";
+const SNAPSHOT_INFO: &str = "snapshot.json";
+
+/// 快照信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub label: String,
+    /// 创建时间，Unix 时间戳（秒）
+    pub created: u64,
+    /// 快照中的贴纸文件名
+    pub files: Vec<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl std::fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            t!(
+                "{} ({}, {} 个文件)",
+                self.label,
+                self.created,
+                self.files.len()
+            )
+        )
+    }
+}
+
+impl Workspace {
+    fn snapshot_root(&self) -> PathBuf {
+        Path::new(self.root_path()).join(SNAPSHOT_DIR)
+    }
+
+    /// 将已更改的贴纸复制到新的快照目录
+    pub fn snapshot(&self, label: &str) -> anyhow::Result<Snapshot> {
+        let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut path = self.snapshot_root().join(created.to_string());
+        // 同一秒内多次创建
+        let mut suffix = 1;
+        while path.exists() {
+            path = self.snapshot_root().join(format!("{}_{}", created, suffix));
+            suffix += 1;
+        }
+        std::fs::create_dir_all(&path)?;
+
+        let files = self
+            .get_modified_stickers()?
+            .into_iter()
+            .map(|sticker| sticker.filename)
+            .collect::<Vec<_>>();
+        for filename in &files {
+            std::fs::copy(
+                Path::new(self.root_path()).join(filename),
+                path.join(filename),
+            )?;
+        }
+
+        let snapshot = Snapshot {
+            label: label.to_string(),
+            created,
+            files,
+            path,
+        };
+        std::fs::write(
+            snapshot.path.join(SNAPSHOT_INFO),
+            serde_json::to_string_pretty(&snapshot)?,
+        )?;
+
+        Ok(snapshot)
+    }
+
+    /// 列出所有快照，按创建时间从新到旧排列
+    pub fn list_snapshots(&self) -> anyhow::Result<Vec<Snapshot>> {
+        let snapshot_root = self.snapshot_root();
+        if !snapshot_root.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut snapshots = vec![];
+        for entry in snapshot_root.read_dir()? {
+            let path = entry?.path();
+            let Ok(info_str) = std::fs::read_to_string(path.join(SNAPSHOT_INFO)) else {
+                continue;
+            };
+            let snapshot = Snapshot {
+                path,
+                ..serde_json::from_str(&info_str)?
+            };
+            snapshots.push(snapshot);
+        }
+        snapshots.sort_by(|a, b| b.created.cmp(&a.created).then(b.path.cmp(&a.path)));
+
+        Ok(snapshots)
+    }
+
+    /// 回滚到快照创建时的状态
+    ///
+    /// 快照中的文件覆盖工作区中的同名文件，快照之后才更改的贴纸还原为原版。
+    pub fn rollback(&mut self, snapshot: &Snapshot) -> anyhow::Result<()> {
+        for sticker in self.get_modified_stickers()? {
+            if !sticker.imported && !snapshot.files.contains(&sticker.filename) {
+                self.restore_sticker(&sticker.name)?;
+            }
+        }
+        for filename in &snapshot.files {
+            std::fs::copy(
+                snapshot.path.join(filename),
+                Path::new(self.root_path()).join(filename),
+            )?;
+        }
+
+        Ok(())
+    }
+}
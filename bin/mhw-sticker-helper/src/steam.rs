@@ -0,0 +1,282 @@
+//! 通过 Steam 查找游戏目录
+//!
+//! 读取 Steam 的 `steamapps/libraryfolders.vdf` 得到所有游戏库，
+//! 再按 `appmanifest_<AppID>.acf` 中的安装目录找到游戏主程序。
+
+use std::path::{Path, PathBuf};
+
+use crate::game::TargetGame;
+
+/// Monster Hunter: World 的 Steam AppID，Iceborne 为其 DLC
+const MHW_APP_ID: &str = "582010";
+/// appmanifest 缺失时使用的默认安装目录名
+const DEFAULT_INSTALL_DIR: &str = "Monster Hunter World";
+
+/// VDF (KeyValues) 中的值
+#[derive(Debug, PartialEq)]
+enum Value {
+    String(String),
+    Table(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(entries) => entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Value::String(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            Value::Table(_) => None,
+        }
+    }
+}
+
+/// 解析 VDF 文本，格式错误的部分忽略
+fn parse_vdf(text: &str) -> Value {
+    let mut tokens = tokenize(text).into_iter();
+    parse_table(&mut tokens)
+}
+
+#[derive(Debug)]
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => {
+                let mut s = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => break,
+                        },
+                        c => s.push(c),
+                    }
+                }
+                tokens.push(Token::String(s));
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                // 不带引号的值
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::String(s));
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_table(tokens: &mut impl Iterator<Item = Token>) -> Value {
+    let mut entries = vec![];
+    while let Some(token) = tokens.next() {
+        let Token::String(key) = token else {
+            break;
+        };
+        match tokens.next() {
+            Some(Token::String(value)) => entries.push((key, Value::String(value))),
+            Some(Token::Open) => entries.push((key, parse_table(tokens))),
+            _ => break,
+        }
+    }
+
+    Value::Table(entries)
+}
+
+/// libraryfolders.vdf 中的所有游戏库目录
+fn library_folders(text: &str) -> Vec<PathBuf> {
+    let vdf = parse_vdf(text);
+    let Some(Value::Table(entries)) = vdf.get("libraryfolders") else {
+        return vec![];
+    };
+    entries
+        .iter()
+        // 旧版格式中库目录直接作为值，新版为包含 path 的表
+        .filter(|(key, _)| key.parse::<u32>().is_ok())
+        .filter_map(|(_, value)| value.as_str().or_else(|| value.get("path")?.as_str()))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// appmanifest 中的安装目录名
+fn install_dir(text: &str) -> Option<String> {
+    parse_vdf(text)
+        .get("AppState")?
+        .get("installdir")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// 在一个 Steam 安装中查找游戏目录
+fn find_in_steam(steam_root: &Path, exe_name: &str) -> Option<PathBuf> {
+    let libraries = std::fs::read_to_string(steam_root.join("steamapps/libraryfolders.vdf"))
+        .map(|text| library_folders(&text))
+        .unwrap_or_default();
+    // Steam 目录本身也是一个游戏库，旧版的 libraryfolders.vdf 中不会列出
+    libraries
+        .into_iter()
+        .chain([steam_root.to_path_buf()])
+        .find_map(|library| {
+            let steamapps = library.join("steamapps");
+            let manifest = steamapps.join(format!("appmanifest_{}.acf", MHW_APP_ID));
+            let install_dir = std::fs::read_to_string(manifest)
+                .ok()
+                .and_then(|text| install_dir(&text))
+                .unwrap_or_else(|| DEFAULT_INSTALL_DIR.to_string());
+            let game_dir = steamapps.join("common").join(install_dir);
+            game_dir.join(exe_name).is_file().then_some(game_dir)
+        })
+}
+
+/// 可能的 Steam 安装目录
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+    #[cfg(windows)]
+    {
+        use winreg::{
+            enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE},
+            RegKey,
+        };
+
+        let keys = [
+            (HKEY_CURRENT_USER, r"Software\Valve\Steam", "SteamPath"),
+            (
+                HKEY_LOCAL_MACHINE,
+                r"SOFTWARE\WOW6432Node\Valve\Steam",
+                "InstallPath",
+            ),
+        ];
+        for (hkey, path, name) in keys {
+            if let Ok(value) = RegKey::predef(hkey)
+                .open_subkey(path)
+                .and_then(|key| key.get_value::<String, _>(name))
+            {
+                roots.push(PathBuf::from(value));
+            }
+        }
+        roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+        roots.push(PathBuf::from(r"C:\Program Files\Steam"));
+    }
+    #[cfg(not(windows))]
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".steam/steam"));
+        roots.push(home.join(".local/share/Steam"));
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        roots.push(home.join("Library/Application Support/Steam"));
+    }
+
+    roots
+}
+
+/// 在本机的 Steam 游戏库中查找游戏目录
+pub fn find_game_dir(game: TargetGame) -> Option<PathBuf> {
+    steam_roots()
+        .iter()
+        .find_map(|root| find_in_steam(root, game.exe_name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_library_folders() {
+        let text = r#"
+"libraryfolders"
+{
+    "0"
+    {
+        "path"      "C:\\Program Files (x86)\\Steam"
+        "apps"
+        {
+            "228980"    "123"
+        }
+    }
+    "1"
+    {
+        "path"      "D:\\SteamLibrary"
+        "apps"
+        {
+            "582010"    "456"
+        }
+    }
+}
+"#;
+        assert_eq!(
+            library_folders(text),
+            [
+                PathBuf::from(r"C:\Program Files (x86)\Steam"),
+                PathBuf::from(r"D:\SteamLibrary")
+            ]
+        );
+
+        // 旧版格式
+        let text = "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"E:\\\\Games\"\n}\n";
+        assert_eq!(library_folders(text), [PathBuf::from(r"E:\Games")]);
+    }
+
+    #[test]
+    fn test_find_in_steam() {
+        let root = TempDir::new("steam-test");
+        let library = root.join("library");
+        let steamapps = library.join("steamapps");
+        let game_dir = steamapps.join("common/MHW");
+        std::fs::create_dir_all(&game_dir).unwrap();
+        std::fs::create_dir_all(root.join("steam/steamapps")).unwrap();
+        std::fs::write(game_dir.join("MonsterHunterWorld.exe"), b"").unwrap();
+        std::fs::write(
+            steamapps.join("appmanifest_582010.acf"),
+            "\"AppState\"\n{\n\t\"appid\"\t\"582010\"\n\t\"installdir\"\t\"MHW\"\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("steam/steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\" {{ \"0\" {{ \"path\" \"{}\" }} }}",
+                library.display().to_string().replace('\\', "\\\\")
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_in_steam(&root.join("steam"), "MonsterHunterWorld.exe"),
+            Some(game_dir)
+        );
+        assert_eq!(find_in_steam(&root.join("steam"), "missing.exe"), None);
+    }
+}
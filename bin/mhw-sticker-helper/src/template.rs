@@ -0,0 +1,159 @@
+//! 编辑模板
+//!
+//! 为每个贴纸图集导出 `templates/<name>/` 目录，包含：
+//! - `tile.png`：单张贴纸尺寸的透明画布，标出安全区域
+//! - `mask.png`：原版贴纸的不透明区域，即游戏内聊天气泡中实际显示的范围
+//! - `sheet.png`：完整图集的网格，标出每张贴纸的位置和编号
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use tex_convert::atlas::SheetLayout;
+
+use crate::workspace::Workspace;
+
+const TEMPLATE_DIR: &str = "templates";
+/// 安全区域到贴纸边缘的距离，靠近边缘的内容在游戏内容易被裁切
+const SAFE_MARGIN: u32 = 4;
+const GUIDE_COLOR: Rgba<u8> = Rgba([0, 200, 255, 160]);
+const GRID_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+const UNUSED_COLOR: Rgba<u8> = Rgba([128, 128, 128, 96]);
+const MASK_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// 3x5 点阵数字，每行低 3 位从左到右
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// 导出所有贴纸图集的编辑模板，返回模板目录
+pub fn write_templates(workspace: &Workspace) -> anyhow::Result<PathBuf> {
+    let template_dir = Path::new(workspace.root_path()).join(TEMPLATE_DIR);
+    for sticker in workspace.info().sticker_packs() {
+        let layout = sticker.layout;
+        let output_dir = template_dir.join(&sticker.name);
+        std::fs::create_dir_all(&output_dir)?;
+
+        tile_template(&layout).save(output_dir.join("tile.png"))?;
+        sheet_template(&layout).save(output_dir.join("sheet.png"))?;
+        // 导入的贴纸没有原版可以参考
+        if let Some(original) = workspace.load_original_image(sticker)? {
+            mask_template(&layout, &original).save(output_dir.join("mask.png"))?;
+        }
+    }
+
+    Ok(template_dir)
+}
+
+/// 单张贴纸大小的透明画布，虚线标出安全区域
+fn tile_template(layout: &SheetLayout) -> RgbaImage {
+    let mut image = RgbaImage::new(layout.tile_w, layout.tile_h);
+    if layout.tile_w > SAFE_MARGIN * 2 && layout.tile_h > SAFE_MARGIN * 2 {
+        let (right, bottom) = (
+            layout.tile_w - SAFE_MARGIN - 1,
+            layout.tile_h - SAFE_MARGIN - 1,
+        );
+        for x in (SAFE_MARGIN..=right).filter(|x| x % 4 < 2) {
+            image.put_pixel(x, SAFE_MARGIN, GUIDE_COLOR);
+            image.put_pixel(x, bottom, GUIDE_COLOR);
+        }
+        for y in (SAFE_MARGIN..=bottom).filter(|y| y % 4 < 2) {
+            image.put_pixel(SAFE_MARGIN, y, GUIDE_COLOR);
+            image.put_pixel(right, y, GUIDE_COLOR);
+        }
+    }
+
+    image
+}
+
+/// 完整图集，标出每张贴纸的边框和编号，未使用的区域以灰色填充
+fn sheet_template(layout: &SheetLayout) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(layout.sheet_w, layout.sheet_h, UNUSED_COLOR);
+    for index in 0..layout.tile_count() {
+        let (x0, y0) = layout.tile_origin(index);
+        let (x1, y1) = (
+            u32::min(x0 + layout.tile_w, layout.sheet_w),
+            u32::min(y0 + layout.tile_h, layout.sheet_h),
+        );
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let border = x == x0 || y == y0 || x == x1 - 1 || y == y1 - 1;
+                let color = if border {
+                    GRID_COLOR
+                } else {
+                    Rgba([0, 0, 0, 0])
+                };
+                image.put_pixel(x, y, color);
+            }
+        }
+        draw_number(&mut image, index, x0 + 3, y0 + 3, 2);
+    }
+
+    image
+}
+
+/// 原版各贴纸不透明区域的并集，原版尺寸与布局不符时为空白
+fn mask_template(layout: &SheetLayout, original: &RgbaImage) -> RgbaImage {
+    let mut mask = RgbaImage::new(layout.tile_w, layout.tile_h);
+    for tile in layout.slice(original).into_iter().flatten() {
+        for (x, y, pixel) in tile.enumerate_pixels() {
+            if pixel[3] > 0 {
+                mask.put_pixel(x, y, MASK_COLOR);
+            }
+        }
+    }
+
+    mask
+}
+
+/// 以 `scale` 倍大小绘制数字，超出图片的部分忽略
+fn draw_number(image: &mut RgbaImage, number: u32, x: u32, y: u32, scale: u32) {
+    for (i, digit) in number.to_string().bytes().enumerate() {
+        let glyph = &DIGITS[(digit - b'0') as usize];
+        let left = x + i as u32 * 4 * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let (px, py) = (left + col * scale + dx, y + row as u32 * scale + dy);
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, GRID_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates() {
+        let layout = SheetLayout::MHW_STICKER;
+
+        let tile = tile_template(&layout);
+        assert_eq!(tile.dimensions(), (120, 86));
+        assert_eq!(*tile.get_pixel(SAFE_MARGIN, SAFE_MARGIN), GUIDE_COLOR);
+        assert_eq!(tile.get_pixel(60, 43)[3], 0);
+
+        let sheet = sheet_template(&layout);
+        assert_eq!(sheet.dimensions(), (128, 512));
+        assert_eq!(*sheet.get_pixel(0, 86), GRID_COLOR);
+        assert_eq!(*sheet.get_pixel(124, 0), UNUSED_COLOR);
+        assert_eq!(sheet.get_pixel(60, 43)[3], 0);
+    }
+}
@@ -0,0 +1,204 @@
+//! 回收站
+//!
+//! 打包时被覆盖的输出文件移入 `dist/.trash/<时间戳>/`，
+//! 同一次打包的文件为一批，可以整批恢复到原来的位置。
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::t;
+
+pub const TRASH_DIR: &str = ".trash";
+const TRASH_INFO: &str = "trash.json";
+
+/// 回收站中的一个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    /// 原来的路径
+    pub original: PathBuf,
+    /// 批次目录中的文件名
+    pub stored: String,
+}
+
+/// 一次打包移入回收站的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashBatch {
+    /// 创建时间，Unix 时间戳（秒）
+    pub created: u64,
+    pub entries: Vec<TrashEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl std::fmt::Display for TrashBatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            t!("{} ({} 个文件)", self.created, self.entries.len())
+        )
+    }
+}
+
+/// 收集一次打包中被覆盖的文件，第一次用到时才创建批次目录
+pub struct Trash {
+    batch: TrashBatch,
+}
+
+impl Trash {
+    pub fn new<P: AsRef<Path>>(trash_root: P) -> anyhow::Result<Self> {
+        let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut path = trash_root.as_ref().join(created.to_string());
+        // 同一秒内多次打包
+        let mut suffix = 1;
+        while path.exists() {
+            path = trash_root.as_ref().join(format!("{}_{}", created, suffix));
+            suffix += 1;
+        }
+
+        Ok(Self {
+            batch: TrashBatch {
+                created,
+                entries: vec![],
+                path,
+            },
+        })
+    }
+
+    /// 将即将被覆盖的文件移入回收站，文件不存在时不做任何事
+    pub fn protect<P: AsRef<Path>>(&mut self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.batch.path)?;
+        let stored = format!(
+            "{}_{}",
+            self.batch.entries.len(),
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        move_file(path, &self.batch.path.join(&stored))?;
+        self.batch.entries.push(TrashEntry {
+            original: std::path::absolute(path)?,
+            stored,
+        });
+        // 每次移入后立即记录，中途失败也能恢复已移入的文件
+        std::fs::write(
+            self.batch.path.join(TRASH_INFO),
+            serde_json::to_string_pretty(&self.batch)?,
+        )?;
+
+        Ok(())
+    }
+
+    /// 移入回收站的文件数量
+    pub fn len(&self) -> usize {
+        self.batch.entries.len()
+    }
+
+    /// 批次目录，没有移入任何文件时为 None
+    pub fn batch_path(&self) -> Option<&Path> {
+        (self.len() > 0).then_some(self.batch.path.as_path())
+    }
+}
+
+/// 列出回收站中的所有批次，按时间从新到旧排列
+pub fn list_batches<P: AsRef<Path>>(trash_root: P) -> anyhow::Result<Vec<TrashBatch>> {
+    let trash_root = trash_root.as_ref();
+    if !trash_root.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut batches = vec![];
+    for entry in trash_root.read_dir()? {
+        let path = entry?.path();
+        let Ok(info_str) = std::fs::read_to_string(path.join(TRASH_INFO)) else {
+            continue;
+        };
+        batches.push(TrashBatch {
+            path,
+            ..serde_json::from_str(&info_str)?
+        });
+    }
+    batches.sort_by(|a, b| b.created.cmp(&a.created).then(b.path.cmp(&a.path)));
+
+    Ok(batches)
+}
+
+impl TrashBatch {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 将文件移回原来的位置并删除该批次，返回恢复的文件数量
+    ///
+    /// 原来位置上的当前文件先移入回收站的一个新批次，恢复后仍可找回。
+    pub fn restore(self) -> anyhow::Result<usize> {
+        let mut trash = Trash::new(self.path.parent().unwrap_or(Path::new("")))?;
+        for entry in &self.entries {
+            trash.protect(&entry.original)?;
+        }
+        for entry in &self.entries {
+            if let Some(parent) = entry.original.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            move_file(&self.path.join(&entry.stored), &entry.original)?;
+        }
+        std::fs::remove_dir_all(&self.path)?;
+
+        Ok(self.entries.len())
+    }
+}
+
+/// 无法重命名时（例如跨磁盘）复制后删除
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_protect_and_restore() {
+        let root = TempDir::new("trash-test");
+        let file = root.join("example.zip");
+        std::fs::write(&file, b"old").unwrap();
+
+        let mut trash = Trash::new(root.join(TRASH_DIR)).unwrap();
+        trash.protect(&file).unwrap();
+        trash.protect(root.join("missing.zip")).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert!(!file.exists());
+
+        std::fs::write(&file, b"new").unwrap();
+        let batches = list_batches(root.join(TRASH_DIR)).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches.into_iter().next().unwrap().restore().unwrap(), 1);
+        assert_eq!(std::fs::read(&file).unwrap(), b"old");
+
+        // 恢复前的文件移入新的批次
+        let batches = list_batches(root.join(TRASH_DIR)).unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(
+            batch.entries[0].original,
+            std::path::absolute(&file).unwrap()
+        );
+        assert_eq!(
+            std::fs::read(batch.path().join(&batch.entries[0].stored)).unwrap(),
+            b"new"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! 检查更新
+//!
+//! 在全局配置中设置 `check_updates = true` 后，启动时查询 GitHub 上的最新版本。
+//! 请求通过系统中的 curl 发出，未安装 curl 或网络不可用时跳过，不影响其他操作。
+//! 设置 `offline = true` 后不会访问网络。
+
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::i18n::t;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/eigeen/mhw-stickers-helper/releases/latest";
+/// 请求超时（秒），避免网络不好时长时间卡在启动阶段
+const TIMEOUT_SECS: &str = "5";
+
+/// GitHub 上发布的版本
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+}
+
+/// 查询最新版本，比当前版本新时返回
+pub fn check() -> anyhow::Result<Option<Release>> {
+    let release = latest_release()?;
+    let newer = is_newer(&release.tag_name, env!("CARGO_PKG_VERSION"));
+
+    Ok(newer.then_some(release))
+}
+
+fn latest_release() -> anyhow::Result<Release> {
+    tracing::debug!("GET {}", LATEST_RELEASE_URL);
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            TIMEOUT_SECS,
+            "--header",
+            "Accept: application/vnd.github+json",
+            "--user-agent",
+            concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+            LATEST_RELEASE_URL,
+        ])
+        .output()
+        .map_err(|e| anyhow::anyhow!(t!("无法运行 curl：{}", e)))?;
+    if !output.status.success() {
+        anyhow::bail!(t!("查询最新版本失败：{}", output.status));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// 比较 `v1.2.3` 形式的版本号，无法识别的部分视为 0
+fn is_newer(latest: &str, current: &str) -> bool {
+    parse_version(latest) > parse_version(current)
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    // 忽略预发布和构建信息，例如 1.2.0-beta.1
+    let version = version.split(['-', '+']).next().unwrap_or_default();
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse().unwrap_or_default())
+        .collect::<Vec<_>>();
+    while parts.last() == Some(&0) {
+        parts.pop();
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v1.0.1", "1.0.0"));
+        assert!(is_newer("1.10.0", "1.9.3"));
+        assert!(!is_newer("v1.0", "1.0.0"));
+        assert!(!is_newer("v1.0.0-beta.1", "1.0.0"));
+        assert!(!is_newer("0.9.0", "1.0.0"));
+    }
+}
@@ -1,4 +1,9 @@
-use std::io::Read;
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
 use ring::digest::{Context, Digest, SHA256};
 
@@ -19,3 +24,154 @@ where
 
     Ok(ctx.finish())
 }
+
+/// 在文件名后追加后缀，例如 `workspace.json` -> `workspace.json.bak`
+pub fn append_extension<P: AsRef<Path>>(path: P, extension: &str) -> PathBuf {
+    let mut file_name = path
+        .as_ref()
+        .file_name()
+        .map(OsString::from)
+        .unwrap_or_default();
+    file_name.push(".");
+    file_name.push(extension);
+    path.as_ref().with_file_name(file_name)
+}
+
+/// 先写入临时文件再重命名替换，写入中途崩溃不会留下不完整的文件
+///
+/// 替换前将原文件复制为 `.bak`，原文件在任何时刻都存在。
+pub fn write_with_backup<P: AsRef<Path>>(path: P, data: &[u8]) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let tmp_path = append_extension(path, "tmp");
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+
+        if path.exists() {
+            std::fs::copy(path, append_extension(path, "bak"))?;
+        }
+        std::fs::rename(&tmp_path, path)
+    })();
+    // 写入失败时不留下临时文件
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// 去掉文件名中不能使用的字符
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// 文件已存在时在文件名后加上编号，例如 `a.png` -> `a (1).png`
+pub fn unique_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate = path.to_path_buf();
+    let mut number = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{} ({}){}", stem, number, extension));
+        number += 1;
+    }
+
+    candidate
+}
+
+/// 以 B、KiB、MiB 显示文件大小
+pub fn format_size(size: u64) -> String {
+    match size {
+        0..1024 => format!("{} B", size),
+        1024..1_048_576 => format!("{:.1} KiB", size as f64 / 1024.0),
+        _ => format!("{:.1} MiB", size as f64 / 1_048_576.0),
+    }
+}
+
+/// 测试用的临时目录，离开作用域时删除，测试中途失败也不会留下文件
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// 创建空目录，`name` 用于区分并行运行的测试
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mhw-{}-{}", name, std::process::id()));
+        // 上次运行被强制结束时可能留下同名目录
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_with_backup() {
+        let dir = TempDir::new("util-test");
+        let path = dir.join("workspace.json");
+
+        write_with_backup(&path, b"first").unwrap();
+        write_with_backup(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(
+            std::fs::read(dir.join("workspace.json.bak")).unwrap(),
+            b"first"
+        );
+        assert!(!dir.join("workspace.json.tmp").exists());
+        assert_eq!(unique_path(dir.join("a.png")), dir.join("a.png"));
+        assert_eq!(unique_path(&path), dir.join("workspace (1).json"));
+
+        // 目标是目录时写入失败，临时文件被删除
+        let blocked = dir.join("blocked");
+        std::fs::create_dir(&blocked).unwrap();
+        assert!(write_with_backup(&blocked, b"data").is_err());
+        assert!(!dir.join("blocked.tmp").exists());
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("1.0/beta"), "1.0_beta");
+        assert_eq!(
+            sanitize_file_name(r#"a:b*c?"d<e>f|g\h"#),
+            "a_b_c__d_e_f_g_h"
+        );
+        assert_eq!(sanitize_file_name("猫贴纸 v2"), "猫贴纸 v2");
+    }
+}
@@ -0,0 +1,223 @@
+//! 打包前检查
+//!
+//! 一次检查所有已更改的贴纸和图块，汇总全部问题，避免打包时逐个报错。
+
+use std::{fmt::Display, fs::File, io::BufReader, path::Path};
+
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader};
+
+use crate::{
+    color,
+    i18n::t,
+    workspace::{Workspace, OVERLAY_DIR},
+};
+
+/// 图片存在的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Unreadable(String),
+    DimensionMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// 灰度、浮点等非 RGBA 颜色类型
+    ColorType(ColorType),
+    SixteenBit,
+    NoAlpha,
+    /// 有透明通道但所有像素都不透明，通常是导出时合并了背景
+    Opaque,
+    /// PNG 嵌入了非 sRGB 的颜色配置，见 [ColorProfile](color::ColorProfile)
+    ColorProfile(String),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Unreadable(e) => write!(f, "{}", t!("无法读取：{}", e)),
+            Problem::DimensionMismatch { expected, actual } => write!(
+                f,
+                "{}",
+                t!(
+                    "尺寸错误：应为 {}x{}，实际为 {}x{}",
+                    expected.0,
+                    expected.1,
+                    actual.0,
+                    actual.1
+                )
+            ),
+            Problem::ColorType(color_type) => {
+                write!(
+                    f,
+                    "{}",
+                    t!("颜色类型为 {}，应为 RGBA", format!("{:?}", color_type))
+                )
+            }
+            Problem::SixteenBit => write!(f, "{}", t!("16 位色深，转换时会降为 8 位")),
+            Problem::NoAlpha => write!(f, "{}", t!("没有透明通道")),
+            Problem::Opaque => write!(f, "{}", t!("所有像素都不透明，背景可能未删除")),
+            Problem::ColorProfile(profile) => {
+                write!(
+                    f,
+                    "{}",
+                    t!("嵌入了{}，游戏内颜色可能与编辑器中不同", profile)
+                )
+            }
+        }
+    }
+}
+
+/// 检查结果，`file` 为工作区中的文件名
+pub struct FileProblems {
+    pub file: String,
+    pub problems: Vec<Problem>,
+}
+
+/// 检查所有已更改的贴纸和单独编辑的图块，只返回存在问题的文件
+pub fn validate_workspace(workspace: &Workspace) -> anyhow::Result<Vec<FileProblems>> {
+    let root_path = Path::new(workspace.root_path());
+    let to_srgb = workspace.info().convert_to_srgb();
+    let mut files = vec![];
+    for sticker in workspace.get_modified_stickers()? {
+        let layout = sticker.layout;
+        let path = root_path.join(&sticker.filename);
+        let extension = Path::new(&sticker.filename)
+            .extension()
+            .and_then(|ext| ext.to_str());
+        let format = match extension {
+            Some("png") => Some(ImageFormat::Png),
+            Some("tga") => Some(ImageFormat::Tga),
+            _ => None,
+        };
+        let image = match (extension, format) {
+            (_, Some(format)) => decode(&path, format),
+            (Some("dds"), _) => workspace
+                .load_sticker_image(&sticker)
+                .map(DynamicImage::ImageRgba8)
+                .map_err(|e| e.to_string()),
+            _ => Err(t!("不支持的文件后缀").to_string()),
+        };
+        let mut problems = check(image, (layout.sheet_w, layout.sheet_h));
+        problems.extend(format.and_then(|format| check_color_profile(&path, format, to_srgb)));
+        files.push(FileProblems {
+            file: sticker.filename.clone(),
+            problems,
+        });
+    }
+    for sticker in workspace
+        .info()
+        .sticker_packs()
+        .iter()
+        .filter(|s| !s.ignored)
+    {
+        let layout = sticker.layout;
+        for (_, (path, format)) in workspace.tile_files(sticker) {
+            let mut problems = check(decode(&path, format), (layout.tile_w, layout.tile_h));
+            problems.extend(check_color_profile(&path, format, to_srgb));
+            files.push(FileProblems {
+                file: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                problems,
+            });
+        }
+        for (_, path) in workspace.overlay_files(sticker) {
+            files.push(FileProblems {
+                file: format!(
+                    "{}/{}",
+                    OVERLAY_DIR,
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                problems: check(
+                    decode(&path, ImageFormat::Png),
+                    (layout.tile_w, layout.tile_h),
+                )
+                .into_iter()
+                .chain(check_color_profile(&path, ImageFormat::Png, to_srgb))
+                .collect(),
+            });
+        }
+    }
+    files.retain(|file| !file.problems.is_empty());
+
+    Ok(files)
+}
+
+/// 检查 PNG 嵌入的颜色配置，开启了转换到 sRGB 时不视为问题
+fn check_color_profile(path: &Path, format: ImageFormat, to_srgb: bool) -> Option<Problem> {
+    if format != ImageFormat::Png || to_srgb {
+        return None;
+    }
+    let data = std::fs::read(path).ok()?;
+    color::detect(&data)
+        .ok()
+        .flatten()
+        .map(|profile| Problem::ColorProfile(profile.to_string()))
+}
+
+fn decode(path: &Path, format: ImageFormat) -> Result<DynamicImage, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    ImageReader::with_format(BufReader::new(file), format)
+        .decode()
+        .map_err(|e| e.to_string())
+}
+
+fn check(image: Result<DynamicImage, String>, expected: (u32, u32)) -> Vec<Problem> {
+    let image = match image {
+        Ok(image) => image,
+        Err(e) => return vec![Problem::Unreadable(e)],
+    };
+
+    let mut problems = vec![];
+    let actual = (image.width(), image.height());
+    if actual != expected {
+        problems.push(Problem::DimensionMismatch { expected, actual });
+    }
+    match image.color() {
+        ColorType::Rgba8 | ColorType::Rgb8 => {}
+        ColorType::Rgba16 | ColorType::Rgb16 | ColorType::La16 | ColorType::L16 => {
+            problems.push(Problem::SixteenBit)
+        }
+        color_type => problems.push(Problem::ColorType(color_type)),
+    }
+    if !image.color().has_alpha() {
+        problems.push(Problem::NoAlpha);
+    } else if image.to_rgba8().pixels().all(|pixel| pixel[3] == u8::MAX) {
+        problems.push(Problem::Opaque);
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgb, Rgba, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let image = RgbaImage::from_fn(120, 86, |x, _| Rgba([0, 0, 0, (x % 2 * 255) as u8]));
+        assert!(check(Ok(DynamicImage::ImageRgba8(image)), (120, 86)).is_empty());
+
+        let image = ImageBuffer::<Rgb<u16>, _>::new(128, 256);
+        assert_eq!(
+            check(Ok(DynamicImage::ImageRgb16(image)), (128, 512)),
+            [
+                Problem::DimensionMismatch {
+                    expected: (128, 512),
+                    actual: (128, 256)
+                },
+                Problem::SixteenBit,
+                Problem::NoAlpha,
+            ]
+        );
+
+        let image = RgbaImage::from_pixel(120, 86, Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            check(Ok(DynamicImage::ImageRgba8(image)), (120, 86)),
+            [Problem::Opaque]
+        );
+    }
+}
@@ -1,13 +1,44 @@
-use image::ImageFormat;
-use ring::digest::Digest;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     fs::{File, OpenOptions},
     io::{BufReader, Cursor, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-use crate::{asset, util};
+use image::{imageops, ImageFormat, RgbaImage};
+use indicatif::{ProgressBar, ProgressStyle};
+use num_traits::FromPrimitive;
+use rayon::prelude::*;
+use tex_convert::{
+    atlas::SheetLayout,
+    spec::{TexFormat, TexInfo},
+    ImageOutputFormat,
+};
+
+use tracing::warn;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    asset,
+    checksum::{Checksum, HashCache, HASH_CACHE_FILE},
+    color, convert,
+    error_code::ErrorCode,
+    game::TargetGame,
+    i18n::t,
+    install::INSTALL_MANIFEST,
+    migration,
+    output::{ModInfo, OutputProfile, PackagingSettings, LAST_PACKAGE},
+    trash::{Trash, TRASH_DIR},
+    util,
+};
+
+/// 登记新图片时默认扫描的文件名，游戏内的贴纸文件名都含有 `stamp`
+pub const DEFAULT_IMAGE_PATTERN: &str = "*stamp*";
+/// 叠加层目录，其中的图片在打包时叠加到对应的图块上
+pub const OVERLAY_DIR: &str = "overlays";
+/// 原版贴纸 tex 的只读副本，还原、对比和合成图块时优先使用，不依赖程序内置的资源
+pub const ORIGINAL_DIR: &str = "originals";
 
 /// 工作区信息
 ///
@@ -16,18 +47,64 @@ use crate::{asset, util};
 pub struct WorkspaceInfo {
     version: i32,
     sticker_packs: Vec<StickerPack>,
+    /// 打包时沿用原始贴纸的 tex 格式，关闭后统一编码为 BC7
+    #[serde(default = "default_keep_original_format")]
+    keep_original_format: bool,
+    /// 打包时的输出方式
+    #[serde(default)]
+    output: OutputProfile,
+    #[serde(default)]
+    target_game: TargetGame,
+    /// 打包时将嵌入了颜色配置的 PNG 转换到 sRGB
+    #[serde(default)]
+    convert_to_srgb: bool,
+    /// 打包时写入的 MOD 信息，未填写时只使用工作区名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mod_info: Option<ModInfo>,
+    /// 输出目录、文件名和包内路径
+    #[serde(default)]
+    packaging: PackagingSettings,
+}
+
+fn default_keep_original_format() -> bool {
+    true
 }
 
 impl Default for WorkspaceInfo {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: migration::CURRENT_VERSION,
             sticker_packs: Default::default(),
+            keep_original_format: default_keep_original_format(),
+            output: OutputProfile::default(),
+            target_game: TargetGame::default(),
+            convert_to_srgb: false,
+            mod_info: None,
+            packaging: PackagingSettings::default(),
         }
     }
 }
 
 impl WorkspaceInfo {
+    /// 读取工作区信息，旧版本的文件会先迁移到当前版本
+    ///
+    /// 返回值的第二项表示是否进行了迁移，需要写回文件。
+    pub fn from_json(info_str: &str) -> anyhow::Result<(Self, bool)> {
+        // 先按对象解析，内容不是对象时同样作为格式错误，见 [is_corrupt](Self::is_corrupt)
+        let info: serde_json::Map<String, serde_json::Value> = serde_json::from_str(info_str)?;
+        let mut value = serde_json::Value::Object(info);
+        let migrated = migration::migrate(&mut value)?;
+
+        Ok((serde_json::from_value(value)?, migrated))
+    }
+
+    /// [from_json](Self::from_json) 的错误是否由文件内容损坏（JSON 格式或字段错误）引起
+    ///
+    /// 只有这类错误可以从备份恢复，版本高于程序支持的版本等错误应原样报告。
+    pub fn is_corrupt(error: &anyhow::Error) -> bool {
+        error.is::<serde_json::Error>()
+    }
+
     pub fn version(&self) -> i32 {
         self.version
     }
@@ -35,66 +112,143 @@ impl WorkspaceInfo {
     pub fn sticker_packs(&self) -> &[StickerPack] {
         &self.sticker_packs
     }
+
+    pub fn keep_original_format(&self) -> bool {
+        self.keep_original_format
+    }
+
+    pub fn output(&self) -> &OutputProfile {
+        &self.output
+    }
+
+    pub fn target_game(&self) -> TargetGame {
+        self.target_game
+    }
+
+    pub fn convert_to_srgb(&self) -> bool {
+        self.convert_to_srgb
+    }
+
+    pub fn mod_info(&self) -> Option<&ModInfo> {
+        self.mod_info.as_ref()
+    }
+
+    pub fn packaging(&self) -> &PackagingSettings {
+        &self.packaging
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StickerPack {
     pub name: String,
     pub filename: String,
-    pub checksum_sha256: HashString,
+    /// 解压时贴纸文件的校验值，用于判断是否已更改
+    pub checksum: Checksum,
+    /// 解压时原始 tex 的格式，即 [TexFormat] 的数值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_format: Option<i32>,
+    /// 从外部 tex 导入，没有对应的内置贴纸，总是参与打包
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub imported: bool,
+    /// 忽略该贴纸，即使文件已更改也不参与打包，用于保留不打算发布的草稿
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignored: bool,
+    #[serde(default, skip_serializing_if = "StickerMetadata::is_empty")]
+    pub metadata: StickerMetadata,
+    /// 图集中贴纸的排列方式，切分预览和合成图块时使用
+    #[serde(default = "default_layout")]
+    pub layout: SheetLayout,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct HashString(Vec<u8>);
-
-impl HashString {
-    pub fn to_hex(&self) -> String {
-        use std::fmt::Write;
+fn default_layout() -> SheetLayout {
+    SheetLayout::MHW_STICKER
+}
 
-        let hex_string = String::with_capacity(self.0.len() * 2);
-        self.0.iter().fold(hex_string, |mut hex_string, byte| {
-            write!(hex_string, "{:02x}", byte).unwrap();
-            hex_string
-        })
-    }
+/// 贴纸的作者、标签和备注，打包时写入 MOD 的说明文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StickerMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
 
-    pub fn from_hex(hex_str: &str) -> Result<HashString, hex::FromHexError> {
-        let bytes = hex::decode(hex_str)?;
-        Ok(HashString(bytes))
+impl StickerMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none() && self.tags.is_empty() && self.notes.is_none()
     }
 }
 
-impl Serialize for HashString {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&self.to_hex())
+impl StickerPack {
+    pub fn original_format(&self) -> Option<TexFormat> {
+        self.original_format.and_then(TexFormat::from_i32)
     }
 }
 
-impl<'de> Deserialize<'de> for HashString {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s: String = Deserialize::deserialize(deserializer)?;
-        HashString::from_hex(&s).map_err(serde::de::Error::custom)
-    }
+/// 单独编辑的 png 图块的路径，见 [Workspace::tile_files]
+fn tile_path(root_path: &Path, name: &str, index: u32) -> PathBuf {
+    root_path.join(format!("{}_{}.png", name, index))
 }
 
-impl PartialEq<Digest> for HashString {
-    fn eq(&self, other: &Digest) -> bool {
-        self.0 == other.as_ref()
-    }
+/// 程序生成的文件，复制工作区时跳过
+fn is_generated_file(name: &str) -> bool {
+    name == ".lock"
+        || name == "preview.html"
+        || name == HASH_CACHE_FILE
+        || name == INSTALL_MANIFEST
+        || name == LAST_PACKAGE
+        || [".bak", ".tmp", ".corrupt"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StickerPackType {
     Dds,
     Png,
 }
 
+impl StickerPackType {
+    /// 根据工作区中贴纸文件的后缀判断类型
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        match Path::new(filename).extension()?.to_str()? {
+            "dds" => Some(StickerPackType::Dds),
+            "png" => Some(StickerPackType::Png),
+            _ => None,
+        }
+    }
+}
+
+/// 登记图片时的目标位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSlot {
+    /// 替换整张贴纸图集
+    Sheet,
+    /// 替换图集中的单个图块
+    Tile(u32),
+}
+
+impl ImageSlot {
+    /// 从文件名末尾的 `_{编号}` 推测图块编号，例如 `mycustom_stamp_2.png` 对应图块 2
+    pub fn guess(filename: &str) -> Option<Self> {
+        let stem = Path::new(filename).file_stem()?.to_str()?;
+        let (_, index) = stem.rsplit_once('_')?;
+        index.parse().ok().map(ImageSlot::Tile)
+    }
+}
+
+impl std::fmt::Display for ImageSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageSlot::Sheet => write!(f, "{}", t!("整张图集")),
+            ImageSlot::Tile(index) => write!(f, "{}", t!("图块 {}", index)),
+        }
+    }
+}
+
 /// 工作区
 #[derive(Debug, Clone)]
 pub struct Workspace {
@@ -112,15 +266,19 @@ impl Workspace {
     pub fn create_new<P: AsRef<Path>>(
         path: P,
         sticker_type: StickerPackType,
+        target_game: TargetGame,
     ) -> anyhow::Result<Self> {
         if path.as_ref().exists() {
-            return Err(anyhow::anyhow!(
+            return Err(anyhow::anyhow!(t!(
                 "目录已存在: {}\n请删除该目录或指定其他目录作为工作区目录",
                 path.as_ref().display()
-            ));
+            )));
         }
 
-        let info = WorkspaceInfo::default();
+        let info = WorkspaceInfo {
+            target_game,
+            ..Default::default()
+        };
         let mut this = Workspace {
             info,
             root_path: path.as_ref().to_string_lossy().to_string(),
@@ -148,83 +306,798 @@ impl Workspace {
     pub fn write_info(&self) -> anyhow::Result<()> {
         let info_path = Path::new(&self.root_path).join("workspace.json");
         let info_str = serde_json::to_string_pretty(&self.info)?;
-        std::fs::write(info_path, info_str)?;
+        util::write_with_backup(info_path, info_str.as_bytes())?;
 
         Ok(())
     }
 
     /// 解压贴纸到工作区目录，转换为png格式，并更新工作区信息
+    ///
+    /// 各贴纸并行转换，贴纸信息仍按内置资源的顺序记录。
     pub fn extract_stickers(&mut self, sticker_type: StickerPackType) -> anyhow::Result<()> {
-        let output_dir = Path::new(&self.root_path);
+        let input_names = self.info.target_game.assets();
+        let progress = ProgressBar::new(input_names.len() as u64).with_style(
+            ProgressStyle::with_template("{bar:30} {pos}/{len} {wide_msg}")?,
+        );
 
-        for input_name in asset::Asset::iter() {
-            if !input_name.ends_with(".tex") {
-                continue;
+        let stickers = input_names
+            .par_iter()
+            .map(|input_name| {
+                let info = self.extract_sticker(input_name, sticker_type);
+                progress.set_message(t!("已解压：{}", input_name));
+                progress.inc(1);
+                info
+            })
+            .collect::<anyhow::Result<Vec<_>>>();
+        progress.finish_and_clear();
+        self.info.sticker_packs.extend(stickers?);
+
+        Ok(())
+    }
+
+    /// 从原版副本重新解压单个贴纸，覆盖工作区中的文件并重置校验值
+    ///
+    /// 同名的 tga 文件、单独编辑的图块和叠加层会优先于原文件打包，一并移入输出目录的回收站。
+    pub fn restore_sticker(&mut self, name: &str) -> anyhow::Result<()> {
+        let index = self
+            .info
+            .sticker_packs
+            .iter()
+            .position(|sticker| sticker.name == name)
+            .ok_or_else(|| anyhow::anyhow!(t!("工作区中没有该贴纸：{}", name)))?;
+        let filename = &self.info.sticker_packs[index].filename;
+        let sticker_type = StickerPackType::from_filename(filename)
+            .ok_or_else(|| anyhow::anyhow!(t!("不支持的文件后缀：{}", filename)))?;
+
+        let info = self.extract_sticker(&format!("{}.tex", name), sticker_type)?;
+        let sticker = &mut self.info.sticker_packs[index];
+        *sticker = StickerPack {
+            layout: sticker.layout,
+            metadata: std::mem::take(&mut sticker.metadata),
+            ..info
+        };
+        let sticker = &self.info.sticker_packs[index];
+        let root_path = Path::new(&self.root_path);
+        let mut trash = Trash::new(self.dist_dir().join(TRASH_DIR))?;
+        trash.protect(root_path.join(format!("{}.tga", name)))?;
+        for index in 0..sticker.layout.tile_count() {
+            for ext in ["png", "tga"] {
+                trash.protect(root_path.join(format!("{}_{}.{}", name, index, ext)))?;
             }
-            let file = asset::Asset::get(&input_name).unwrap();
-            let mut reader = Cursor::new(file.data);
+        }
+        for (_, path) in self.overlay_files(sticker) {
+            trash.protect(path)?;
+        }
+        self.write_info()?;
 
-            let input_name_owned = input_name.to_string();
-            let input_path = Path::new(&input_name_owned);
-            let filestem = input_path
-                .file_stem()
-                .unwrap_or_default()
-                .to_str()
-                .unwrap_or_default();
-            let file_output_path = match sticker_type {
-                StickerPackType::Dds => output_dir.join(format!("{}.dds", filestem)),
-                StickerPackType::Png => output_dir.join(format!("{}.png", filestem)),
-            };
+        Ok(())
+    }
+
+    /// 导入 `path` 时贴纸的名称、文件格式和图集布局，与已有贴纸同名时沿用其布局
+    fn import_target(&self, path: &Path) -> anyhow::Result<(String, StickerPackType, SheetLayout)> {
+        let name = path
+            .file_stem()
+            .ok_or_else(|| anyhow::anyhow!(t!("无效的文件路径：{}", path.display())))?
+            .to_string_lossy()
+            .to_string();
+        let sticker_type = self
+            .info
+            .sticker_packs
+            .iter()
+            .find_map(|sticker| StickerPackType::from_filename(&sticker.filename))
+            .unwrap_or(StickerPackType::Png);
+        let layout = self
+            .info
+            .sticker_packs
+            .iter()
+            .find(|sticker| sticker.name == name)
+            .map_or_else(default_layout, |sticker| sticker.layout);
+
+        Ok((name, sticker_type, layout))
+    }
+
+    /// 导入 `path` 时会被覆盖的贴纸文件和图块文件
+    pub fn import_conflicts<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<Vec<PathBuf>> {
+        let (name, sticker_type, layout) = self.import_target(path.as_ref())?;
+        let root_path = Path::new(&self.root_path);
+        let extension = match sticker_type {
+            StickerPackType::Dds => "dds",
+            StickerPackType::Png => "png",
+        };
+        let conflicts = std::iter::once(root_path.join(format!("{}.{}", name, extension)))
+            .chain((0..layout.tile_count()).map(|index| tile_path(root_path, &name, index)))
+            .filter(|path| path.exists())
+            .collect();
 
-            let mut data = vec![];
-            let mut writer = Cursor::new(&mut data);
-            match sticker_type {
-                StickerPackType::Dds => {
-                    let dds_data = tex_convert::tex2dds::convert_to_dds(&mut reader)?;
-                    data = dds_data;
+        Ok(conflicts)
+    }
+
+    /// 导入外部的 tex 文件，按工作区的文件格式解压并切分为图块，返回贴纸名称
+    ///
+    /// 图块写入 `{name}_{index}.png`，打包时优先于贴纸文件，之后按图块编辑即可。
+    /// 与原版贴纸同名时保留原版的校验值，打包时视为已更改；其他名称登记为导入的贴纸，总是参与打包。
+    /// 工作区中已有同名的贴纸文件或图块时，`overwrite` 为假则返回错误，见 [import_conflicts](Self::import_conflicts)。
+    pub fn import_tex<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        overwrite: bool,
+    ) -> anyhow::Result<String> {
+        let path = path.as_ref();
+        let (name, sticker_type, layout) = self.import_target(path)?;
+        if !overwrite {
+            if let Some(conflict) = self.import_conflicts(path)?.first() {
+                anyhow::bail!(
+                    ErrorCode::BadInput.with(t!("文件已存在，未覆盖：{}", conflict.display()))
+                );
+            }
+        }
+        let tex_data = std::fs::read(path)?;
+        let image = tex_convert::load_tex_image(&mut Cursor::new(&tex_data))?;
+        let tiles = layout
+            .slice(&image)
+            .map_err(|e| ErrorCode::BadInput.with(t!("贴纸尺寸错误：{}：{}", path.display(), e)))?
+            .collect::<Vec<_>>();
+
+        let info = self.write_sticker(&name, &tex_data, sticker_type)?;
+        let root_path = Path::new(&self.root_path);
+        for (index, tile) in tiles.iter().enumerate() {
+            tile.save_with_format(tile_path(root_path, &name, index as u32), ImageFormat::Png)?;
+        }
+        match self
+            .info
+            .sticker_packs
+            .iter_mut()
+            .find(|sticker| sticker.name == name)
+        {
+            // 保留原版的校验值
+            Some(sticker) if !sticker.imported => {}
+            Some(sticker) => {
+                *sticker = StickerPack {
+                    imported: true,
+                    metadata: std::mem::take(&mut sticker.metadata),
+                    layout,
+                    ..info
                 }
-                StickerPackType::Png => {
-                    let img = tex_convert::load_tex_image(&mut reader)?;
-                    img.write_to(&mut writer, ImageFormat::Png)?;
+            }
+            None => self.info.sticker_packs.push(StickerPack {
+                imported: true,
+                layout,
+                ..info
+            }),
+        }
+        self.write_info()?;
+
+        Ok(name)
+    }
+
+    /// 设置打包时的输出方式
+    pub fn set_output(&mut self, output: OutputProfile) -> anyhow::Result<()> {
+        self.info.output = output;
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    pub fn set_convert_to_srgb(&mut self, convert_to_srgb: bool) -> anyhow::Result<()> {
+        self.info.convert_to_srgb = convert_to_srgb;
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    pub fn set_mod_info(&mut self, mod_info: ModInfo) -> anyhow::Result<()> {
+        self.info.mod_info = Some(mod_info);
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    pub fn set_packaging(&mut self, packaging: PackagingSettings) -> anyhow::Result<()> {
+        self.info.packaging = packaging;
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    /// 设置贴纸的作者、标签和备注
+    pub fn set_metadata(&mut self, name: &str, metadata: StickerMetadata) -> anyhow::Result<()> {
+        let sticker = self
+            .info
+            .sticker_packs
+            .iter_mut()
+            .find(|sticker| sticker.name == name)
+            .ok_or_else(|| anyhow::anyhow!(t!("工作区中没有该贴纸：{}", name)))?;
+        sticker.metadata = metadata;
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    /// 设置忽略的贴纸，不在 `names` 中的贴纸取消忽略
+    pub fn set_ignored(&mut self, names: &[&str]) -> anyhow::Result<()> {
+        for sticker in &mut self.info.sticker_packs {
+            sticker.ignored = names.contains(&sticker.name.as_str());
+        }
+        self.write_info()?;
+
+        Ok(())
+    }
+
+    /// 重新解压 workspace.json 中记录但文件已丢失的贴纸，返回修复的贴纸名称和原版副本路径
+    ///
+    /// 只处理文件不存在的贴纸，已修改的文件不受影响；同时补齐 `originals/` 中缺失的原版副本。
+    pub fn repair(&mut self) -> anyhow::Result<Vec<String>> {
+        let mut repaired = vec![];
+        let mut info_changed = false;
+        for index in 0..self.info.sticker_packs.len() {
+            let sticker = &self.info.sticker_packs[index];
+            // 旧版工作区没有原版副本，从内置资源补齐
+            if !sticker.imported && !self.original_path(&sticker.name).exists() {
+                if let Some(tex_data) = self.original_tex(&sticker.name)? {
+                    self.write_original(&sticker.name, &tex_data)?;
+                    repaired.push(format!("{}/{}.tex", ORIGINAL_DIR, sticker.name));
                 }
             }
+            if Path::new(&self.root_path).join(&sticker.filename).exists() {
+                continue;
+            }
+            if sticker.imported {
+                warn!(
+                    "{}",
+                    t!(
+                        "导入的贴纸没有原始文件，无法修复：{}, 跳过",
+                        sticker.filename
+                    )
+                );
+                continue;
+            }
+            let Some(sticker_type) = StickerPackType::from_filename(&sticker.filename) else {
+                warn!("{}", t!("不支持的文件后缀：{}, 跳过", sticker.filename));
+                continue;
+            };
 
-            // 解析信息
-            let info = Self::parse_sticker_info(&mut Cursor::new(&data), &file_output_path)?;
-            self.info.sticker_packs.push(info);
-            // 写入文件
-            let mut file = OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(&file_output_path)?;
-            file.write_all(&data)?;
+            let name = sticker.name.clone();
+            let info = self.extract_sticker(&format!("{}.tex", name), sticker_type)?;
+            let sticker = &mut self.info.sticker_packs[index];
+            *sticker = StickerPack {
+                ignored: sticker.ignored,
+                layout: sticker.layout,
+                metadata: std::mem::take(&mut sticker.metadata),
+                ..info
+            };
+            repaired.push(name);
+            info_changed = true;
         }
+        if info_changed {
+            self.write_info()?;
+        }
+
+        Ok(repaired)
+    }
+
+    /// 解压单个内置贴纸并写入工作区目录，返回贴纸信息
+    fn extract_sticker(
+        &self,
+        input_name: &str,
+        sticker_type: StickerPackType,
+    ) -> anyhow::Result<StickerPack> {
+        let filestem = Path::new(input_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+        let tex_data = self
+            .original_tex(filestem)?
+            .ok_or_else(|| anyhow::anyhow!(t!("找不到内置贴纸：{}", input_name)))?;
+        self.write_original(filestem, &tex_data)?;
+
+        self.write_sticker(filestem, &tex_data, sticker_type)
+    }
+
+    /// 原版贴纸的 tex 数据，优先读取 `originals/` 中的副本，没有副本时取自内置资源
+    pub fn original_tex(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let path = self.original_path(name);
+        if path.exists() {
+            return Ok(Some(std::fs::read(path)?));
+        }
+
+        Ok(asset::Asset::get(&format!("{}.tex", name)).map(|file| file.data.into_owned()))
+    }
+
+    fn original_path(&self, name: &str) -> PathBuf {
+        Path::new(&self.root_path)
+            .join(ORIGINAL_DIR)
+            .join(format!("{}.tex", name))
+    }
+
+    /// 保存原版贴纸的只读副本，已有副本时不覆盖
+    fn write_original(&self, name: &str, tex_data: &[u8]) -> anyhow::Result<()> {
+        let path = self.original_path(name);
+        if path.exists() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, tex_data)?;
+        let mut permissions = std::fs::metadata(&path)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions)?;
 
         Ok(())
     }
 
-    /// 获取工作区中内容变更的贴纸
+    /// 将 tex 数据转换为贴纸文件写入工作区目录，返回贴纸信息
+    fn write_sticker(
+        &self,
+        filestem: &str,
+        tex_data: &[u8],
+        sticker_type: StickerPackType,
+    ) -> anyhow::Result<StickerPack> {
+        let output_dir = Path::new(&self.root_path);
+        let mut reader = Cursor::new(tex_data);
+        let original_format = TexInfo::from_reader(&mut reader)?.format;
+        reader.set_position(0);
+
+        let file_output_path = match sticker_type {
+            StickerPackType::Dds => output_dir.join(format!("{}.dds", filestem)),
+            StickerPackType::Png => output_dir.join(format!("{}.png", filestem)),
+        };
+
+        let mut data = vec![];
+        let mut writer = Cursor::new(&mut data);
+        match sticker_type {
+            StickerPackType::Dds => {
+                let dds_data = tex_convert::tex2dds::convert_to_dds(&mut reader)?;
+                data = dds_data;
+            }
+            StickerPackType::Png => {
+                tex_convert::export_image(&mut reader, &mut writer, ImageOutputFormat::Png)?;
+            }
+        }
+
+        // 解析信息
+        let mut info = Self::parse_sticker_info(&mut Cursor::new(&data), &file_output_path)?;
+        info.original_format = Some(original_format as i32);
+        // 写入文件
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&file_output_path)?;
+        file.write_all(&data)?;
+
+        Ok(info)
+    }
+
+    /// 获取工作区中内容变更的贴纸，忽略的贴纸除外
     pub fn get_modified_stickers(&self) -> anyhow::Result<Vec<StickerPack>> {
+        let root_path = Path::new(&self.root_path);
+        let mut cache = HashCache::load(root_path);
+        // 图块较多时逐个计算很慢，并行计算后统一写回缓存
+        let results = self
+            .info
+            .sticker_packs
+            .par_iter()
+            .filter(|sticker| !sticker.ignored)
+            .map(|sticker| {
+                // 同名的 tga 文件优先，Photoshop 等工具常导出 tga
+                let tga_name = format!("{}.tga", sticker.name);
+                if root_path.join(&tga_name).exists() {
+                    let sticker = StickerPack {
+                        filename: tga_name,
+                        ..sticker.clone()
+                    };
+                    let modified = self.tga_modified(&sticker).unwrap_or_else(|_| {
+                        warn!("{}", t!("无法读取文件: {}, 跳过", sticker.filename));
+                        false
+                    });
+                    return (modified.then_some(sticker), None);
+                }
+
+                let input_path = root_path.join(&sticker.filename);
+                if !input_path.exists() {
+                    return (None, None);
+                }
+                let Ok(checksum) = cache.checksum(root_path, &sticker.filename, &sticker.checksum)
+                else {
+                    warn!("{}", t!("无法读取文件: {}, 跳过", input_path.display()));
+                    return (None, None);
+                };
+                let modified = sticker.imported || sticker.checksum != checksum;
+                (
+                    modified.then(|| sticker.clone()),
+                    Some((sticker.filename.as_str(), checksum)),
+                )
+            })
+            .collect::<Vec<_>>();
+
         let mut modified_stickers = vec![];
+        for (modified, checksum) in results {
+            modified_stickers.extend(modified);
+            if let Some((filename, checksum)) = checksum {
+                cache.insert(root_path, filename, checksum);
+            }
+        }
+        // 缓存写入失败只影响下次的速度
+        let _ = cache.save(root_path);
+
+        Ok(modified_stickers)
+    }
+
+    /// 将已更改的贴纸和工作区信息打包为 zip 备份，返回备份的贴纸数量
+    pub fn backup_modified<P: AsRef<Path>>(&self, zip_path: P) -> anyhow::Result<usize> {
+        let modified_stickers = self.get_modified_stickers()?;
+        if let Some(parent) = zip_path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut zip_writer = ZipWriter::new(File::create(zip_path)?);
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        let root_path = Path::new(&self.root_path);
+        for filename in modified_stickers
+            .iter()
+            .map(|sticker| sticker.filename.as_str())
+            .chain(["workspace.json"])
+        {
+            zip_writer.start_file(filename, options)?;
+            zip_writer.write_all(&std::fs::read(root_path.join(filename))?)?;
+        }
+        zip_writer.finish()?;
+
+        Ok(modified_stickers.len())
+    }
+
+    /// 复制工作区到新目录，返回新的工作区
+    ///
+    /// 复制 workspace.json、贴纸文件、图块、叠加层和原版副本，快照、预览等可重新生成的内容不复制。
+    pub fn clone_to<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<Workspace> {
+        let path = path.as_ref();
+        if path.exists() {
+            anyhow::bail!(t!("目录已存在: {}", path.display()));
+        }
+        std::fs::create_dir_all(path)?;
+
+        let root_path = Path::new(&self.root_path);
+        for file in self.source_files()? {
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(path.join(parent))?;
+            }
+            std::fs::copy(root_path.join(&file), path.join(&file))?;
+        }
+
+        Workspace::open(path)
+    }
+
+    /// 工作区中需要保留的文件，即 workspace.json、贴纸文件、图块、叠加层和原版副本，返回相对路径
+    ///
+    /// 快照、预览等可重新生成的内容不包括在内。
+    pub fn source_files(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for entry in Path::new(&self.root_path).read_dir()? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() && (name == OVERLAY_DIR || name == ORIGINAL_DIR) {
+                for file in entry.path().read_dir()? {
+                    let file = file?;
+                    if file.file_type()?.is_file() {
+                        files.push(Path::new(&name).join(file.file_name()));
+                    }
+                }
+            } else if file_type.is_file() && !is_generated_file(&name.to_string_lossy()) {
+                files.push(PathBuf::from(name));
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// 删除工作区目录
+    ///
+    /// 目录中没有 workspace.json 时拒绝删除，避免误删其他目录。
+    pub fn delete(self) -> anyhow::Result<()> {
+        let root_path = Path::new(&self.root_path);
+        if !root_path.join("workspace.json").is_file() {
+            anyhow::bail!(ErrorCode::WorkspaceInvalid
+                .with(t!("不是有效的工作区目录：{}", root_path.display())));
+        }
+        // Windows 下无法直接删除只读文件
+        let original_dir = root_path.join(ORIGINAL_DIR);
+        if original_dir.is_dir() {
+            for entry in original_dir.read_dir()? {
+                let path = entry?.path();
+                let mut permissions = std::fs::metadata(&path)?.permissions();
+                #[allow(clippy::permissions_set_readonly_false)]
+                permissions.set_readonly(false);
+                std::fs::set_permissions(&path, permissions)?;
+            }
+        }
+        std::fs::remove_dir_all(root_path)?;
+
+        Ok(())
+    }
+
+    /// 读取工作区中的贴纸文件
+    pub fn load_sticker_image(&self, sticker: &StickerPack) -> anyhow::Result<RgbaImage> {
+        let path = Path::new(&self.root_path).join(&sticker.filename);
+        let to_srgb = self.info.convert_to_srgb;
+        let image = match Path::new(&sticker.filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some("dds") => tex_convert::load_dds_image(&mut BufReader::new(File::open(&path)?))?,
+            Some("png") => color::load_image(&path, ImageFormat::Png, to_srgb)?,
+            Some("tga") => color::load_image(&path, ImageFormat::Tga, to_srgb)?,
+            _ => {
+                anyhow::bail!(ErrorCode::BadInput.with(t!("不支持的文件后缀：{}", sticker.filename)))
+            }
+        };
+
+        Ok(image)
+    }
+
+    /// tga 没有记录校验值，与原版贴纸逐像素比较，导入的贴纸总是视为已更改
+    fn tga_modified(&self, sticker: &StickerPack) -> anyhow::Result<bool> {
+        let Some(original) = self.load_original_image(sticker)? else {
+            return Ok(true);
+        };
+
+        Ok(self.load_sticker_image(sticker)? != original)
+    }
+
+    /// 读取原版贴纸，导入的贴纸返回 `None`
+    pub fn load_original_image(&self, sticker: &StickerPack) -> anyhow::Result<Option<RgbaImage>> {
+        if sticker.imported {
+            return Ok(None);
+        }
+        let Some(tex_data) = self.original_tex(&sticker.name)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(tex_convert::load_tex_image(&mut Cursor::new(
+            tex_data,
+        ))?))
+    }
+
+    /// 单独编辑的图块文件，返回图块编号、路径和格式
+    ///
+    /// 图块文件名为 `{name}_{index}.png` 或 `.tga`，与 extract-stickers 的输出一致，同一位置 tga 优先。
+    pub fn tile_files(&self, sticker: &StickerPack) -> Vec<(u32, (PathBuf, ImageFormat))> {
+        let root_path = Path::new(&self.root_path);
+        (0..sticker.layout.tile_count())
+            .filter_map(|index| {
+                [("tga", ImageFormat::Tga), ("png", ImageFormat::Png)]
+                    .into_iter()
+                    .map(|(ext, format)| {
+                        (
+                            root_path.join(format!("{}_{}.{}", sticker.name, index, ext)),
+                            format,
+                        )
+                    })
+                    .find(|(path, _)| path.exists())
+                    .map(|tile| (index, tile))
+            })
+            .collect()
+    }
+
+    /// 工作区目录中文件名匹配 `pattern` 、既不是贴纸文件也不是图块的图片，即用户自行放入、尚未登记的图片，返回文件名
+    ///
+    /// `pattern` 中 `*` 匹配任意多个字符，`?` 匹配一个字符，见 [DEFAULT_IMAGE_PATTERN]。
+    pub fn unknown_images(&self, pattern: &str) -> anyhow::Result<Vec<String>> {
+        let mut known = HashSet::new();
         for sticker in &self.info.sticker_packs {
-            let input_path = Path::new(&self.root_path).join(&sticker.filename);
+            known.insert(sticker.filename.clone());
+            // 同名的 tga 文件代替贴纸文件参与打包，见 [get_modified_stickers](Self::get_modified_stickers)
+            known.insert(format!("{}.tga", sticker.name));
+            for index in 0..sticker.layout.tile_count() {
+                for ext in ["png", "tga"] {
+                    known.insert(format!("{}_{}.{}", sticker.name, index, ext));
+                }
+            }
+        }
 
-            if !input_path.exists() {
+        let mut images = vec![];
+        for entry in Path::new(&self.root_path).read_dir()? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
                 continue;
             }
-            let Ok(file) = File::open(&input_path) else {
-                eprintln!("无法打开文件: {}, 跳过", input_path.display());
-                continue;
-            };
-            let mut reader = BufReader::new(file);
-            let digest = util::sha256_digest(&mut reader)?;
-            if sticker.checksum_sha256 != digest {
-                modified_stickers.push(sticker.clone());
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_image = Path::new(&name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ["png", "tga", "dds"].contains(&ext));
+            if is_image && !known.contains(&name) && convert::wildcard_match(pattern, &name) {
+                images.push(name);
             }
         }
+        images.sort();
 
-        Ok(modified_stickers)
+        Ok(images)
+    }
+
+    /// 将未登记的图片重命名为贴纸文件或图块文件，返回新的路径
+    ///
+    /// 目标文件已存在时先移入输出目录的回收站，可以在“回收站”中恢复。
+    pub fn register_image(
+        &self,
+        filename: &str,
+        sticker_name: &str,
+        slot: ImageSlot,
+    ) -> anyhow::Result<PathBuf> {
+        let root_path = Path::new(&self.root_path);
+        let Some(sticker) = self
+            .info
+            .sticker_packs
+            .iter()
+            .find(|sticker| sticker.name == sticker_name)
+        else {
+            anyhow::bail!(t!("工作区中没有该贴纸：{}", sticker_name));
+        };
+        let ext = Path::new(filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+
+        let target = match slot {
+            ImageSlot::Sheet => {
+                if !sticker.filename.ends_with(&format!(".{}", ext)) {
+                    anyhow::bail!(t!(
+                        "图片格式与贴纸文件不同，请先转换为 {}：{}",
+                        sticker.filename,
+                        filename
+                    ));
+                }
+                root_path.join(&sticker.filename)
+            }
+            ImageSlot::Tile(index) => {
+                if index >= sticker.layout.tile_count() {
+                    anyhow::bail!(t!("图块编号超出范围：{}", index));
+                }
+                if ext == "dds" {
+                    anyhow::bail!(t!("图块仅支持 png 和 tga 格式：{}", filename));
+                }
+                root_path.join(format!("{}_{}.{}", sticker.name, index, ext))
+            }
+        };
+        let mut trash = Trash::new(self.dist_dir().join(TRASH_DIR))?;
+        trash.protect(&target)?;
+        std::fs::rename(root_path.join(filename), &target)?;
+
+        Ok(target)
+    }
+
+    /// 叠加层文件，返回图块编号和路径
+    ///
+    /// 叠加层位于 `overlays/` 目录，文件名与图块相同，为 `{name}_{index}.png`。
+    pub fn overlay_files(&self, sticker: &StickerPack) -> Vec<(u32, PathBuf)> {
+        let overlay_dir = Path::new(&self.root_path).join(OVERLAY_DIR);
+        (0..sticker.layout.tile_count())
+            .map(|index| {
+                (
+                    index,
+                    overlay_dir.join(format!("{}_{}.png", sticker.name, index)),
+                )
+            })
+            .filter(|(_, path)| path.exists())
+            .collect()
+    }
+
+    /// 将单独编辑的图块合成到贴纸图集上，再叠加 `overlays/` 中的叠加层，两者都没有时返回 `None`
+    ///
+    /// `sheet_modified` 为真时以工作区中的贴纸文件为底，否则未编辑的图块取自原版贴纸。
+    pub fn compose_tiles(
+        &self,
+        sticker: &StickerPack,
+        sheet_modified: bool,
+    ) -> anyhow::Result<Option<RgbaImage>> {
+        let layout = sticker.layout;
+        let tiles = self.tile_files(sticker);
+        let overlays = self.overlay_files(sticker);
+        if tiles.is_empty() && overlays.is_empty() {
+            return Ok(None);
+        }
+
+        let mut sheet = match self.load_original_image(sticker)? {
+            Some(original) if !sheet_modified => original,
+            _ => self.load_sticker_image(sticker)?,
+        };
+        for (index, (path, format)) in tiles {
+            let tile = color::load_image(&path, format, self.info.convert_to_srgb)?;
+            layout.put_tile(&mut sheet, index, &tile).map_err(|e| {
+                ErrorCode::BadInput.with(t!("无法合成图块：{}：{}", path.display(), e))
+            })?;
+        }
+        // 按透明度混合，叠加层只覆盖不透明的部分
+        for (index, path) in overlays {
+            let overlay = color::load_image(&path, ImageFormat::Png, self.info.convert_to_srgb)?;
+            if overlay.dimensions() != (layout.tile_w, layout.tile_h) {
+                anyhow::bail!(ErrorCode::BadInput.with(t!(
+                    "叠加层尺寸错误：{}：应为 {}x{}，实际为 {}x{}",
+                    path.display(),
+                    layout.tile_w,
+                    layout.tile_h,
+                    overlay.width(),
+                    overlay.height()
+                )));
+            }
+            let (x, y) = layout.tile_origin(index);
+            imageops::overlay(&mut sheet, &overlay, x as i64, y as i64);
+        }
+
+        Ok(Some(sheet))
+    }
+
+    /// 打开工作区目录，旧版本的工作区信息会迁移后写回
+    ///
+    /// workspace.json 损坏时从上次写入前的备份恢复，损坏的文件重命名为 `.corrupt` 保留。
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Workspace> {
+        let info_path = path.as_ref().join("workspace.json");
+        let info_str = std::fs::read_to_string(&info_path).map_err(|e| {
+            ErrorCode::WorkspaceInvalid.with(t!(
+                "无法读取工作区信息：{}：{}",
+                info_path.display(),
+                e
+            ))
+        })?;
+        let (info, rewrite) = match WorkspaceInfo::from_json(&info_str) {
+            Ok(result) => result,
+            Err(e) if !WorkspaceInfo::is_corrupt(&e) => {
+                return Err(e.context(
+                    ErrorCode::WorkspaceInvalid
+                        .with(t!("无法读取工作区信息：{}", info_path.display())),
+                ));
+            }
+            Err(e) => {
+                let backup = std::fs::read_to_string(util::append_extension(&info_path, "bak"))
+                    .ok()
+                    .and_then(|backup_str| WorkspaceInfo::from_json(&backup_str).ok());
+                let Some((info, _)) = backup else {
+                    return Err(e.context(
+                        ErrorCode::WorkspaceInvalid
+                            .with(t!("工作区信息损坏：{}", info_path.display())),
+                    ));
+                };
+                warn!(
+                    "{}",
+                    t!(
+                        "工作区信息损坏，已从备份恢复：{}：{:#}",
+                        info_path.display(),
+                        e
+                    )
+                );
+                // 避免写回时损坏的文件覆盖备份
+                std::fs::rename(&info_path, util::append_extension(&info_path, "corrupt"))?;
+                (info, true)
+            }
+        };
+        let workspace = Workspace {
+            info,
+            root_path: path.as_ref().to_string_lossy().to_string(),
+        };
+        if rewrite {
+            workspace.write_info()?;
+        }
+
+        Ok(workspace)
+    }
+
+    /// 只读打开工作区目录，不迁移写回，也不从备份恢复，用于诊断等不应修改工作区的场合
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> anyhow::Result<Workspace> {
+        let info_path = path.as_ref().join("workspace.json");
+        let (info, _) =
+            WorkspaceInfo::from_json(&std::fs::read_to_string(&info_path)?).map_err(|e| {
+                e.context(
+                    ErrorCode::WorkspaceInvalid
+                        .with(t!("无法读取工作区信息：{}", info_path.display())),
+                )
+            })?;
+
+        Ok(Workspace {
+            info,
+            root_path: path.as_ref().to_string_lossy().to_string(),
+        })
     }
 
     /// 列出当前目录下所有的工作区
@@ -235,16 +1108,8 @@ impl Workspace {
         for entry in current_dir.read_dir()? {
             let entry = entry?;
             let path = entry.path();
-            if path.is_dir() {
-                let info_path = path.join("workspace.json");
-                if info_path.exists() {
-                    let info_str = std::fs::read_to_string(&info_path)?;
-                    let info: WorkspaceInfo = serde_json::from_str(&info_str)?;
-                    workspaces.push(Workspace {
-                        info,
-                        root_path: path.to_string_lossy().to_string(),
-                    });
-                }
+            if path.is_dir() && path.join("workspace.json").exists() {
+                workspaces.push(Self::open(&path)?);
             }
         }
 
@@ -256,8 +1121,7 @@ impl Workspace {
         R: Read,
         P: AsRef<Path>,
     {
-        let digest = util::sha256_digest(reader)?;
-        let hash_string = HashString(digest.as_ref().to_vec());
+        let checksum = Checksum::blake3(reader)?;
 
         Ok(StickerPack {
             name: path
@@ -272,7 +1136,48 @@ impl Workspace {
                 .unwrap()
                 .to_string_lossy()
                 .to_string(),
-            checksum_sha256: hash_string,
+            checksum,
+            original_format: None,
+            imported: false,
+            ignored: false,
+            metadata: StickerMetadata::default(),
+            layout: default_layout(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_open_recovery() {
+        let root = TempDir::new("workspace-test");
+        let info_path = root.join("workspace.json");
+        let backup = serde_json::to_string(&WorkspaceInfo::default()).unwrap();
+        std::fs::write(util::append_extension(&info_path, "bak"), &backup).unwrap();
+
+        // 版本过高不是损坏，不从备份恢复，文件保持不变
+        let newer = format!(r#"{{"version":{}}}"#, migration::CURRENT_VERSION + 1);
+        std::fs::write(&info_path, &newer).unwrap();
+        assert!(Workspace::open(&root).is_err());
+        assert_eq!(std::fs::read_to_string(&info_path).unwrap(), newer);
+
+        // 内容损坏时从备份恢复
+        std::fs::write(&info_path, "[]").unwrap();
+        assert!(Workspace::open(&root).is_ok());
+        assert!(util::append_extension(&info_path, "corrupt").exists());
+
+        // 只列出匹配的图片
+        let workspace = Workspace::open(&root).unwrap();
+        std::fs::write(root.join("mycustom_stamp_2.png"), b"").unwrap();
+        std::fs::write(root.join("photo.png"), b"").unwrap();
+        assert_eq!(
+            workspace.unknown_images(DEFAULT_IMAGE_PATTERN).unwrap(),
+            ["mycustom_stamp_2.png"]
+        );
+        assert_eq!(workspace.unknown_images("*").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,40 @@
+#ifndef TEX_CONVERT_H
+#define TEX_CONVERT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define TEX_OK 0
+#define TEX_ERR_NULL_POINTER (-1)
+#define TEX_ERR_IO (-2)
+#define TEX_ERR_BAD_MAGIC (-3)
+#define TEX_ERR_UNKNOWN_FORMAT (-4)
+#define TEX_ERR_UNSUPPORTED_VERSION (-5)
+#define TEX_ERR_DDS (-6)
+#define TEX_ERR_IMAGE (-7)
+#define TEX_ERR_INVALID_FIELD (-8)
+#define TEX_ERR_OTHER (-99)
+#define TEX_ERR_PANIC (-100)
+
+/* Buffer allocated by the library, release with tex_buffer_free. */
+typedef struct TexBuffer {
+    uint8_t *data;
+    size_t len;
+} TexBuffer;
+
+int32_t tex_to_dds(const uint8_t *buf, size_t len, TexBuffer *out);
+int32_t dds_to_tex(const uint8_t *buf, size_t len, TexBuffer *out);
+void tex_buffer_free(TexBuffer *buf);
+
+/* Description of the last error on the calling thread, or NULL. */
+const char *tex_convert_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TEX_CONVERT_H */
@@ -0,0 +1,160 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use tex_convert::error::Error;
+
+/// 成功
+pub const TEX_OK: i32 = 0;
+/// 传入了空指针
+pub const TEX_ERR_NULL_POINTER: i32 = -1;
+pub const TEX_ERR_IO: i32 = -2;
+pub const TEX_ERR_BAD_MAGIC: i32 = -3;
+pub const TEX_ERR_UNKNOWN_FORMAT: i32 = -4;
+pub const TEX_ERR_UNSUPPORTED_VERSION: i32 = -5;
+pub const TEX_ERR_DDS: i32 = -6;
+pub const TEX_ERR_IMAGE: i32 = -7;
+pub const TEX_ERR_INVALID_FIELD: i32 = -8;
+/// 其他错误，详细信息见 [tex_convert_last_error]
+pub const TEX_ERR_OTHER: i32 = -99;
+/// 内部发生 panic
+pub const TEX_ERR_PANIC: i32 = -100;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 由本库分配的输出缓冲区，使用完毕后需调用 [tex_buffer_free] 释放
+#[repr(C)]
+pub struct TexBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// 将 tex 文件数据转换为 dds
+///
+/// # Safety
+///
+/// `buf` 必须指向长度至少为 `len` 的有效内存，`out` 必须指向可写的 [TexBuffer]。
+#[no_mangle]
+pub unsafe extern "C" fn tex_to_dds(buf: *const u8, len: usize, out: *mut TexBuffer) -> i32 {
+    convert(buf, len, out, tex_convert::tex2dds::convert_to_dds_bytes)
+}
+
+/// 将 dds 文件数据转换为 tex
+///
+/// # Safety
+///
+/// 同 [tex_to_dds]。
+#[no_mangle]
+pub unsafe extern "C" fn dds_to_tex(buf: *const u8, len: usize, out: *mut TexBuffer) -> i32 {
+    convert(buf, len, out, tex_convert::dds2tex::convert_to_tex_bytes)
+}
+
+/// 释放由本库分配的 [TexBuffer]，释放后 `data` 被置为空指针
+///
+/// # Safety
+///
+/// `buf` 必须为空指针，或指向由本库函数填充的 [TexBuffer]。
+#[no_mangle]
+pub unsafe extern "C" fn tex_buffer_free(buf: *mut TexBuffer) {
+    let Some(buf) = buf.as_mut() else {
+        return;
+    };
+    if !buf.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buf.data, buf.len,
+        )));
+    }
+    buf.data = ptr::null_mut();
+    buf.len = 0;
+}
+
+/// 当前线程最近一次错误的描述，没有错误时返回空指针
+///
+/// 返回的字符串在当前线程下一次调用本库函数前有效。
+#[no_mangle]
+pub extern "C" fn tex_convert_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+unsafe fn convert<F>(buf: *const u8, len: usize, out: *mut TexBuffer, f: F) -> i32
+where
+    F: FnOnce(&[u8]) -> tex_convert::error::Result<Vec<u8>>,
+{
+    set_last_error(None);
+    if buf.is_null() || out.is_null() {
+        set_last_error(Some("null pointer".to_string()));
+        return TEX_ERR_NULL_POINTER;
+    }
+
+    let input = std::slice::from_raw_parts(buf, len);
+    match catch_unwind(AssertUnwindSafe(|| f(input))) {
+        Ok(Ok(data)) => {
+            let data = data.into_boxed_slice();
+            let len = data.len();
+            *out = TexBuffer {
+                data: Box::into_raw(data) as *mut u8,
+                len,
+            };
+            TEX_OK
+        }
+        Ok(Err(e)) => {
+            set_last_error(Some(e.to_string()));
+            error_code(&e)
+        }
+        Err(_) => {
+            set_last_error(Some("panic during conversion".to_string()));
+            TEX_ERR_PANIC
+        }
+    }
+}
+
+fn error_code(error: &Error) -> i32 {
+    match error {
+        Error::IO(_) => TEX_ERR_IO,
+        Error::BadMagic(..) => TEX_ERR_BAD_MAGIC,
+        Error::UnknownTexFormat | Error::UnsupportedDxgiFormat(_) => TEX_ERR_UNKNOWN_FORMAT,
+        Error::UnsupportedTexVersion(_) => TEX_ERR_UNSUPPORTED_VERSION,
+        Error::Dds(_) | Error::CreateImageFromDds(_) | Error::CreateDdsFromImage(_) => TEX_ERR_DDS,
+        Error::Image(_) => TEX_ERR_IMAGE,
+        Error::InvalidField { .. } => TEX_ERR_INVALID_FIELD,
+        _ => TEX_ERR_OTHER,
+    }
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', " ")).unwrap());
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let data = b"not a tex file";
+        let mut out = TexBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let code = unsafe { tex_to_dds(data.as_ptr(), data.len(), &mut out) };
+        assert_eq!(code, TEX_ERR_BAD_MAGIC);
+        assert!(out.data.is_null());
+
+        let message = unsafe { CStr::from_ptr(tex_convert_last_error()) };
+        assert!(message.to_str().unwrap().contains("magic"));
+    }
+
+    #[test]
+    fn test_null_pointer() {
+        let code = unsafe { dds_to_tex(ptr::null(), 0, ptr::null_mut()) };
+        assert_eq!(code, TEX_ERR_NULL_POINTER);
+    }
+}
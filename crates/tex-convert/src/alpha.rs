@@ -0,0 +1,80 @@
+use image::RgbaImage;
+
+/// 半透明像素占可见像素的比例超过该值时，认为图片带有柔和边缘
+const SOFT_EDGE_RATIO: f32 = 0.01;
+
+/// 将 RGB 通道乘以透明度
+///
+/// 块压缩时透明像素的颜色会混入相邻像素，游戏中柔和边缘会出现暗边，预乘后可以避免。
+pub fn premultiply(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel.0[3] as u32;
+        for channel in &mut pixel.0[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// [premultiply] 的逆操作，完全透明的像素保持不变
+pub fn unpremultiply(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel.0[3] as u32;
+        if alpha == 0 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            *channel = u32::min((*channel as u32 * 255 + alpha / 2) / alpha, 255) as u8;
+        }
+    }
+}
+
+/// 判断图片是否需要预乘透明度
+///
+/// 只有完全透明和完全不透明像素的图片（如硬边遮罩）预乘后没有区别，
+/// 半透明像素足够多时才认为需要预乘。
+pub fn has_soft_edges(image: &RgbaImage) -> bool {
+    let mut visible = 0u64;
+    let mut translucent = 0u64;
+    for pixel in image.pixels() {
+        match pixel.0[3] {
+            0 => {}
+            255 => visible += 1,
+            _ => {
+                visible += 1;
+                translucent += 1;
+            }
+        }
+    }
+
+    visible > 0 && translucent as f32 / visible as f32 > SOFT_EDGE_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_premultiply_round_trip() {
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([200, 100, 0, 128]));
+        premultiply(&mut image);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([100, 50, 0, 128]));
+
+        unpremultiply(&mut image);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([199, 100, 0, 128]));
+    }
+
+    #[test]
+    fn test_has_soft_edges() {
+        let mut image = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        assert!(!has_soft_edges(&image));
+
+        image.put_pixel(0, 0, Rgba([255, 255, 255, 0]));
+        assert!(!has_soft_edges(&image));
+
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 100]));
+        image.put_pixel(2, 0, Rgba([255, 255, 255, 100]));
+        assert!(has_soft_edges(&image));
+    }
+}
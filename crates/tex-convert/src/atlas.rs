@@ -0,0 +1,321 @@
+use image::{GenericImage, GenericImageView, RgbaImage};
+
+use crate::error::{Error, Result};
+
+/// 贴纸图集的布局
+///
+/// 图集从左上角开始按行排列贴纸，剩余区域不使用。
+/// 纵向排列的图集 `cols` 为 1，横向排列的图集 `rows` 为 1。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedLayout"))]
+pub struct SheetLayout {
+    pub tile_w: u32,
+    pub tile_h: u32,
+    pub rows: u32,
+    pub cols: u32,
+    /// 相邻贴纸之间的间距
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub padding: u32,
+    /// 图集尺寸，游戏要求的纹理尺寸通常大于贴纸占用的区域
+    pub sheet_w: u32,
+    pub sheet_h: u32,
+}
+
+/// 反序列化时先读取到该结构，通过 [SheetLayout::validate] 后再转换
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedLayout {
+    tile_w: u32,
+    tile_h: u32,
+    rows: u32,
+    cols: u32,
+    #[serde(default)]
+    padding: u32,
+    sheet_w: u32,
+    sheet_h: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedLayout> for SheetLayout {
+    type Error = Error;
+
+    fn try_from(raw: UncheckedLayout) -> Result<Self> {
+        let layout = SheetLayout {
+            tile_w: raw.tile_w,
+            tile_h: raw.tile_h,
+            rows: raw.rows,
+            cols: raw.cols,
+            padding: raw.padding,
+            sheet_w: raw.sheet_w,
+            sheet_h: raw.sheet_h,
+        };
+        layout.validate()?;
+
+        Ok(layout)
+    }
+}
+
+impl SheetLayout {
+    /// 游戏内的聊天贴纸，128x512 的图集纵向排列 5 张 120x86 的贴纸
+    pub const MHW_STICKER: SheetLayout = SheetLayout {
+        tile_w: 120,
+        tile_h: 86,
+        rows: 5,
+        cols: 1,
+        padding: 0,
+        sheet_w: 128,
+        sheet_h: 512,
+    };
+
+    /// 检查布局是否可用：至少一行一列、贴纸尺寸不为 0，且贴纸占用的区域不超出图集
+    ///
+    /// 其余方法假定布局已通过检查，来自配置文件或命令行的布局应先调用该方法。
+    pub fn validate(&self) -> Result<()> {
+        if self.rows == 0 || self.cols == 0 {
+            return Err(Error::InvalidLayout("rows and cols must be at least 1"));
+        }
+        if self.tile_w == 0 || self.tile_h == 0 {
+            return Err(Error::InvalidLayout("tile size must not be 0"));
+        }
+        let used_len = |count: u32, tile: u32| {
+            count
+                .checked_mul(tile.checked_add(self.padding)?)?
+                .checked_sub(self.padding)
+        };
+        let (Some(used_w), Some(used_h)) = (
+            used_len(self.cols, self.tile_w),
+            used_len(self.rows, self.tile_h),
+        ) else {
+            return Err(Error::InvalidLayout("tiles exceed the sheet"));
+        };
+        if used_w > self.sheet_w || used_h > self.sheet_h {
+            return Err(Error::InvalidLayout("tiles exceed the sheet"));
+        }
+
+        Ok(())
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.rows * self.cols
+    }
+
+    /// 第 `index` 张贴纸左上角的坐标，按行优先编号
+    pub fn tile_origin(&self, index: u32) -> (u32, u32) {
+        let (row, col) = (index / self.cols, index % self.cols);
+        (
+            col * (self.tile_w + self.padding),
+            row * (self.tile_h + self.padding),
+        )
+    }
+
+    /// 贴纸占用区域的尺寸，不含最后一行、一列之后的间距
+    pub fn used_size(&self) -> (u32, u32) {
+        (
+            self.cols * (self.tile_w + self.padding) - self.padding,
+            self.rows * (self.tile_h + self.padding) - self.padding,
+        )
+    }
+
+    /// 按行优先顺序裁剪出所有贴纸
+    ///
+    /// 图集小于贴纸占用的区域时返回 [Error::DimensionMismatch]。
+    pub fn slice<'a>(&self, image: &'a RgbaImage) -> Result<impl Iterator<Item = RgbaImage> + 'a> {
+        let (used_w, used_h) = self.used_size();
+        if image.width() < used_w || image.height() < used_h {
+            return Err(Error::DimensionMismatch(
+                (used_w, used_h),
+                image.dimensions(),
+            ));
+        }
+
+        let layout = *self;
+        Ok((0..self.tile_count()).map(move |index| {
+            let (x, y) = layout.tile_origin(index);
+            image.view(x, y, layout.tile_w, layout.tile_h).to_image()
+        }))
+    }
+
+    /// 将贴纸按行优先顺序拼回完整图集，[SheetLayout::slice] 的逆操作
+    ///
+    /// 贴纸以外的区域（右侧的填充列与底部空白）保持透明，贴纸数量少于布局时其余位置同样留空。
+    pub fn compose(&self, tiles: &[RgbaImage]) -> Result<RgbaImage> {
+        let mut sheet = RgbaImage::new(self.sheet_w, self.sheet_h);
+        self.compose_onto(&mut sheet, tiles)?;
+
+        Ok(sheet)
+    }
+
+    /// 将贴纸写入已有图集，不覆盖贴纸以外的区域
+    ///
+    /// 只编辑了部分贴纸时，以原始图集为底可以保留填充区域的原始内容。
+    pub fn compose_onto(&self, sheet: &mut RgbaImage, tiles: &[RgbaImage]) -> Result<()> {
+        if sheet.dimensions() != (self.sheet_w, self.sheet_h) {
+            return Err(Error::DimensionMismatch(
+                (self.sheet_w, self.sheet_h),
+                sheet.dimensions(),
+            ));
+        }
+        if tiles.len() > self.tile_count() as usize {
+            return Err(Error::TileOutOfRange(
+                tiles.len() as u32 - 1,
+                self.tile_count(),
+            ));
+        }
+
+        for (index, tile) in tiles.iter().enumerate() {
+            self.put_tile(sheet, index as u32, tile)?;
+        }
+
+        Ok(())
+    }
+
+    /// 替换图集中第 `index` 张贴纸，其余贴纸保持不变
+    pub fn put_tile(&self, sheet: &mut RgbaImage, index: u32, tile: &RgbaImage) -> Result<()> {
+        if index >= self.tile_count() {
+            return Err(Error::TileOutOfRange(index, self.tile_count()));
+        }
+        if tile.dimensions() != (self.tile_w, self.tile_h) {
+            return Err(Error::DimensionMismatch(
+                (self.tile_w, self.tile_h),
+                tile.dimensions(),
+            ));
+        }
+        let (x, y) = self.tile_origin(index);
+        sheet.copy_from(tile, x, y)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_slice() {
+        let mut sheet = RgbaImage::new(128, 512);
+        sheet.put_pixel(0, 86 * 2, Rgba([255, 0, 0, 255]));
+        sheet.put_pixel(119, 86 * 3 - 1, Rgba([0, 255, 0, 255]));
+
+        let tiles = SheetLayout::MHW_STICKER
+            .slice(&sheet)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(tiles.len(), 5);
+        assert!(tiles.iter().all(|tile| tile.dimensions() == (120, 86)));
+        assert_eq!(*tiles[2].get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*tiles[2].get_pixel(119, 85), Rgba([0, 255, 0, 255]));
+
+        // 图集小于布局时不会越界
+        assert!(matches!(
+            SheetLayout::MHW_STICKER.slice(&RgbaImage::new(128, 256)),
+            Err(Error::DimensionMismatch((120, 430), (128, 256)))
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SheetLayout::MHW_STICKER.validate().is_ok());
+
+        let invalid = [
+            SheetLayout {
+                cols: 0,
+                ..SheetLayout::MHW_STICKER
+            },
+            SheetLayout {
+                tile_h: 0,
+                ..SheetLayout::MHW_STICKER
+            },
+            SheetLayout {
+                rows: 6,
+                ..SheetLayout::MHW_STICKER
+            },
+            SheetLayout {
+                rows: u32::MAX,
+                ..SheetLayout::MHW_STICKER
+            },
+        ];
+        for layout in invalid {
+            assert!(
+                matches!(layout.validate(), Err(Error::InvalidLayout(_))),
+                "{:?}",
+                layout
+            );
+        }
+    }
+
+    #[test]
+    fn test_tile_origin() {
+        let layout = SheetLayout {
+            tile_w: 10,
+            tile_h: 20,
+            rows: 2,
+            cols: 3,
+            padding: 0,
+            sheet_w: 32,
+            sheet_h: 40,
+        };
+        assert_eq!(layout.tile_origin(4), (10, 20));
+
+        let layout = SheetLayout {
+            padding: 2,
+            sheet_w: 34,
+            sheet_h: 42,
+            ..layout
+        };
+        assert_eq!(layout.tile_origin(4), (12, 22));
+        assert_eq!(layout.used_size(), (34, 42));
+    }
+
+    #[test]
+    fn test_compose() {
+        let layout = SheetLayout::MHW_STICKER;
+        let tiles = (0..5)
+            .map(|i| RgbaImage::from_pixel(120, 86, Rgba([i * 50, 0, 0, 255])))
+            .collect::<Vec<_>>();
+
+        let sheet = layout.compose(&tiles).unwrap();
+        assert_eq!(sheet.dimensions(), (128, 512));
+        // 填充列与底部保持透明
+        assert_eq!(*sheet.get_pixel(120, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*sheet.get_pixel(0, 86 * 5), Rgba([0, 0, 0, 0]));
+
+        let sliced = layout.slice(&sheet).unwrap().collect::<Vec<_>>();
+        assert_eq!(sliced, tiles);
+    }
+
+    #[test]
+    fn test_compose_errors() {
+        let layout = SheetLayout::MHW_STICKER;
+        let tiles = vec![RgbaImage::new(120, 86); 6];
+        assert!(matches!(
+            layout.compose(&tiles),
+            Err(Error::TileOutOfRange(5, 5))
+        ));
+        assert!(matches!(
+            layout.compose(&[RgbaImage::new(128, 86)]),
+            Err(Error::DimensionMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn test_put_tile() {
+        let layout = SheetLayout::MHW_STICKER;
+        let mut sheet = RgbaImage::from_pixel(128, 512, Rgba([1, 2, 3, 255]));
+        let tile = RgbaImage::from_pixel(120, 86, Rgba([255, 0, 0, 255]));
+
+        layout.put_tile(&mut sheet, 3, &tile).unwrap();
+        let tiles = layout.slice(&sheet).unwrap().collect::<Vec<_>>();
+        assert_eq!(tiles[3], tile);
+        assert_eq!(*tiles[2].get_pixel(0, 0), Rgba([1, 2, 3, 255]));
+        assert_eq!(*sheet.get_pixel(120, 86 * 3), Rgba([1, 2, 3, 255]));
+
+        assert!(matches!(
+            layout.put_tile(&mut sheet, 5, &tile),
+            Err(Error::TileOutOfRange(5, 5))
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashSet, fmt::Display};
+
+use image::RgbaImage;
+
+use crate::options::TargetFormat;
+
+/// 像素数不超过该值时不压缩，块压缩的失真比节省的体积更明显
+const TINY_PIXEL_COUNT: u64 = 16 * 16;
+/// 不透明图片的颜色数超过该值时认为是照片或渐变，BC1 会产生明显色带
+const COMPLEX_COLOR_COUNT: usize = 4096;
+
+/// 自动选择格式的依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatReason {
+    Tiny,
+    Opaque,
+    BinaryAlpha,
+    ComplexColor,
+    SmoothAlpha,
+}
+
+impl Display for FormatReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatReason::Tiny => write!(f, "image is too small to benefit from compression"),
+            FormatReason::Opaque => write!(f, "opaque image with few colors"),
+            FormatReason::BinaryAlpha => write!(f, "alpha is either fully transparent or opaque"),
+            FormatReason::ComplexColor => write!(f, "opaque image with many colors or gradients"),
+            FormatReason::SmoothAlpha => write!(f, "image has semi-transparent pixels"),
+        }
+    }
+}
+
+/// 自动选择的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatDecision {
+    pub format: TargetFormat,
+    pub reason: FormatReason,
+}
+
+impl Display for FormatDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({})", self.format, self.reason)
+    }
+}
+
+/// 根据图片内容选择编码格式
+///
+/// 依次考虑尺寸、透明度的使用方式以及颜色数量，
+/// 在体积与画质之间取一个不需要了解各 DXGI 格式也能接受的结果。
+pub fn select_format(image: &RgbaImage) -> FormatDecision {
+    let decision = |format, reason| FormatDecision { format, reason };
+
+    if image.width() as u64 * image.height() as u64 <= TINY_PIXEL_COUNT {
+        return decision(TargetFormat::Rgba8, FormatReason::Tiny);
+    }
+
+    let mut opaque = true;
+    let mut binary_alpha = true;
+    let mut colors = HashSet::new();
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        opaque &= a == 255;
+        binary_alpha &= a == 0 || a == 255;
+        // 完全透明像素的颜色不可见，不参与统计
+        if a == 0 {
+            continue;
+        }
+        if colors.len() <= COMPLEX_COLOR_COUNT {
+            colors.insert([r, g, b]);
+        }
+    }
+
+    if colors.len() > COMPLEX_COLOR_COUNT && binary_alpha {
+        decision(TargetFormat::Bc7, FormatReason::ComplexColor)
+    } else if opaque {
+        decision(TargetFormat::Bc1, FormatReason::Opaque)
+    } else if binary_alpha {
+        decision(TargetFormat::Bc1, FormatReason::BinaryAlpha)
+    } else {
+        decision(TargetFormat::Bc7, FormatReason::SmoothAlpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_select_format() {
+        let tiny = RgbaImage::new(8, 8);
+        assert_eq!(select_format(&tiny).reason, FormatReason::Tiny);
+
+        let gray = RgbaImage::from_fn(64, 64, |x, _| Rgba([x as u8, x as u8, x as u8, 255]));
+        // BC4 只有 R 通道，游戏中显示为红色，灰度图片同样使用 BC1
+        assert_eq!(select_format(&gray).format, TargetFormat::Bc1);
+
+        let mut flat = RgbaImage::from_pixel(64, 64, Rgba([255, 0, 0, 255]));
+        assert_eq!(select_format(&flat).format, TargetFormat::Bc1);
+        flat.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        assert_eq!(select_format(&flat).reason, FormatReason::BinaryAlpha);
+        flat.put_pixel(1, 0, Rgba([255, 0, 0, 128]));
+        assert_eq!(select_format(&flat).format, TargetFormat::Bc7);
+
+        let gradient = RgbaImage::from_fn(128, 128, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        assert_eq!(select_format(&gradient).reason, FormatReason::ComplexColor);
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+use rayon::prelude::*;
+
+use crate::{error::Result, options::ConvertOptions};
+
+/// 批量转换参数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BatchOptions {
+    /// 是否递归处理子目录，输出时保留相对目录结构
+    pub recursive: bool,
+    /// 图片转换为 tex 时使用的参数
+    pub convert: ConvertOptions,
+}
+
+/// 单个文件的转换结果
+#[derive(Debug)]
+pub struct BatchItem {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub result: Result<()>,
+}
+
+impl BatchItem {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// 将目录下所有 tex 文件转换为 png
+///
+/// 文件并行转换，单个文件失败不会中断整个批次，结果记录在 [BatchItem::result] 中。
+pub fn convert_dir_tex_to_png<P, Q>(
+    src: P,
+    dst: Q,
+    options: &BatchOptions,
+) -> Result<Vec<BatchItem>>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    convert_dir(
+        src.as_ref(),
+        dst.as_ref(),
+        options,
+        "tex",
+        "png",
+        |input, output| {
+            let img = load_tex_image(input)?;
+            img.save_with_format(output, ImageFormat::Png)?;
+            Ok(())
+        },
+    )
+}
+
+/// 将目录下所有 png 文件转换为 tex
+pub fn convert_dir_png_to_tex<P, Q>(
+    src: P,
+    dst: Q,
+    options: &BatchOptions,
+) -> Result<Vec<BatchItem>>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    convert_dir(
+        src.as_ref(),
+        dst.as_ref(),
+        options,
+        "png",
+        "tex",
+        |input, output| {
+            let img = image::open(input)?.to_rgba8();
+            let tex_data = crate::convert_image_to_tex(&img, &options.convert)?;
+            std::fs::write(output, tex_data)?;
+            Ok(())
+        },
+    )
+}
+
+fn convert_dir<F>(
+    src: &Path,
+    dst: &Path,
+    options: &BatchOptions,
+    input_ext: &str,
+    output_ext: &str,
+    convert: F,
+) -> Result<Vec<BatchItem>>
+where
+    F: Fn(&Path, &Path) -> Result<()> + Sync,
+{
+    let mut inputs = vec![];
+    collect_files(src, input_ext, options.recursive, &mut inputs)?;
+
+    let items = inputs
+        .into_par_iter()
+        .map(|input| {
+            let relative = input.strip_prefix(src).unwrap_or(&input);
+            let output = dst.join(relative).with_extension(output_ext);
+            let result = output
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .map_err(Into::into)
+                .and_then(|_| convert(&input, &output));
+
+            BatchItem {
+                input,
+                output,
+                result,
+            }
+        })
+        .collect();
+
+    Ok(items)
+}
+
+#[cfg(feature = "mmap")]
+fn load_tex_image(path: &Path) -> Result<image::RgbaImage> {
+    crate::mmap::load_tex_image(path)
+}
+
+#[cfg(not(feature = "mmap"))]
+fn load_tex_image(path: &Path) -> Result<image::RgbaImage> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    crate::load_tex_image(&mut reader)
+}
+
+fn collect_files(dir: &Path, ext: &str, recursive: bool, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, ext, recursive, out)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+        {
+            out.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use image::RgbaImage;
+
+/// 纹理数据的颜色空间
+///
+/// sRGB 格式的纹理在采样时由 GPU 转换到线性空间，线性格式则直接使用原始数值。
+/// 同一组像素值写入不同颜色空间的格式，在游戏中的显示效果会偏亮或偏暗。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+/// 将 sRGB 编码的分量转换为线性值
+pub fn srgb_to_linear(value: u8) -> u8 {
+    (srgb_to_linear_f32(value as f32 / 255.0) * 255.0).round() as u8
+}
+
+/// 将线性分量转换为 sRGB 编码
+pub fn linear_to_srgb(value: u8) -> u8 {
+    (linear_to_srgb_f32(value as f32 / 255.0) * 255.0).round() as u8
+}
+
+/// 同 [srgb_to_linear]，取值范围为 0.0 ~ 1.0
+pub fn srgb_to_linear_f32(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 同 [linear_to_srgb]，取值范围为 0.0 ~ 1.0
+pub fn linear_to_srgb_f32(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 转换图片的颜色空间，只处理 RGB 通道，透明度保持不变
+pub fn convert_color_space(image: &mut RgbaImage, from: ColorSpace, to: ColorSpace) {
+    let convert: fn(u8) -> u8 = match (from, to) {
+        (ColorSpace::Srgb, ColorSpace::Linear) => srgb_to_linear,
+        (ColorSpace::Linear, ColorSpace::Srgb) => linear_to_srgb,
+        _ => return,
+    };
+    // 查表，避免逐像素计算幂函数
+    let table: [u8; 256] = std::array::from_fn(|i| convert(i as u8));
+
+    for pixel in image.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = table[*channel as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_transfer_functions() {
+        assert_eq!(srgb_to_linear(0), 0);
+        assert_eq!(srgb_to_linear(255), 255);
+        assert_eq!(srgb_to_linear(188), 128);
+        assert_eq!(linear_to_srgb(128), 188);
+    }
+
+    #[test]
+    fn test_convert_color_space() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([188, 0, 255, 100]));
+        convert_color_space(&mut image, ColorSpace::Srgb, ColorSpace::Linear);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([128, 0, 255, 100]));
+
+        convert_color_space(&mut image, ColorSpace::Linear, ColorSpace::Linear);
+        assert_eq!(*image.get_pixel(1, 1), Rgba([128, 0, 255, 100]));
+    }
+}
@@ -1,36 +1,45 @@
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
-use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use byteorder::{ReadBytesExt, LE};
 use num_traits::FromPrimitive;
 
 use crate::{
+    color::ColorSpace,
     error::{Error, Result},
-    spec::{DxgiFormat, TexFormat},
+    options::ConvertOptions,
+    spec::{DxgiFormat, TexFormat, TexHeader, TexInfo},
 };
 
 const DDS_MAGIC: i32 = 0x20534444;
-
-const W_MAGIC_NUMBER_TEX: &[u8] = &[
-    0x54, 0x45, 0x58, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x02, 0x00, 0x00, 0x00,
-];
-const TEX_FIXED_UNKN: &[u8] = &[
-    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-];
-const TEX_OF_NEW_DDS: &[TexFormat] = &[
-    TexFormat::DxgiFormatBc7Unorm,
-    TexFormat::DxgiFormatBc7UnormSRGB,
-    TexFormat::DxgiFormatBc6hUf16,
-];
-const TEX_WITH_4BPP: &[TexFormat] = &[
-    TexFormat::DxgiFormatBc1Unorm,
-    TexFormat::DxgiFormatBc1UnormSRGB,
-    TexFormat::DxgiFormatBc4Unorm,
-];
-const TEX_WITH_16BPP: &[TexFormat] = &[TexFormat::DxgiFormatR8G8Unorm];
+const DDSD_DEPTH: u32 = 0x800000;
+/// D3D11 体积纹理的最大深度
+const MAX_DEPTH: i32 = 2048;
+/// 旧版 Photoshop / NVIDIA 插件导出的 BC2/BC3 格式
+const LEGACY_FOURCCS: &[&[u8; 4]] = &[b"DXT3", b"DXT5"];
 
 pub fn convert_to_tex<R>(reader: &mut R) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    convert(reader, None)
+}
+
+/// 同 [convert_to_tex]，直接传入文件数据
+pub fn convert_to_tex_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    convert_to_tex(&mut Cursor::new(data))
+}
+
+/// 转换为 tex，文件头的未知字段从原始 tex 复制
+///
+/// 见 [crate::spec::TexInfoBuilder::unknown_fields_from]。
+pub fn convert_to_tex_like<R>(reader: &mut R, reference: &TexHeader) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    convert(reader, Some(reference))
+}
+
+fn convert<R>(reader: &mut R, reference: Option<&TexHeader>) -> Result<Vec<u8>>
 where
     R: Read + Seek,
 {
@@ -40,31 +49,87 @@ where
         return Err(Error::BadMagic(DDS_MAGIC, magic));
     }
 
-    reader.seek(SeekFrom::Start(0x8))?;
-    let dds_flag = reader.read_i32::<LE>()?;
-    let is_raw = dds_flag & 0x8 == 0x8;
+    reader.seek(SeekFrom::Start(0xC))?;
     let height = reader.read_i32::<LE>()?;
     let width = reader.read_i32::<LE>()?;
 
+    for (offset, field, value) in [(0x0C, "height", height), (0x10, "width", width)] {
+        if value <= 0 {
+            return Err(Error::InvalidField {
+                offset,
+                field,
+                value: value as i64,
+            });
+        }
+    }
+
+    // 体积纹理，未设置 DDSD_DEPTH 时忽略深度字段
+    reader.seek(SeekFrom::Start(0x08))?;
+    let flags = reader.read_u32::<LE>()?;
+    reader.seek(SeekFrom::Start(0x18))?;
+    let depth = match reader.read_i32::<LE>()? {
+        depth if flags & DDSD_DEPTH != 0 => i32::max(depth, 1),
+        _ => 1,
+    };
+    if depth > MAX_DEPTH {
+        return Err(Error::InvalidField {
+            offset: 0x18,
+            field: "depth",
+            value: depth as i64,
+        });
+    }
+
+    // 未设置 mipmap 数量时视为只有 1 层
     reader.seek(SeekFrom::Start(0x1C))?;
-    let mipmap_count = reader.read_i32::<LE>()?;
+    let mipmap_count = i32::max(reader.read_i32::<LE>()?, 1);
+    if mipmap_count > TexInfo::MAX_MIP_MAP_COUNT {
+        return Err(Error::InvalidField {
+            offset: 0x1C,
+            field: "mipmap count",
+            value: mipmap_count as i64,
+        });
+    }
 
     reader.seek(SeekFrom::Start(0x54))?;
     let mut filetype_magic = [0u8; 4];
     reader.read_exact(&mut filetype_magic)?;
+    let has_dx10_header = &filetype_magic == b"DX10";
+    if LEGACY_FOURCCS.contains(&&filetype_magic) {
+        // 没有颜色空间信息，按贴纸常用的 sRGB 处理
+        return transcode_legacy(reader, reference, mipmap_count, ColorSpace::Srgb);
+    }
     let mut format = TexFormat::from_magic(&filetype_magic);
     if format == TexFormat::DxgiFormatUnknown {
-        return Err(Error::UnknownTexFormat);
+        return Err(Error::InvalidField {
+            offset: 0x54,
+            field: "dds FourCC",
+            value: u32::from_le_bytes(filetype_magic) as i64,
+        });
     }
-    if format == TexFormat::DxgiFormatBc7Unorm {
+    if has_dx10_header {
         // 进一步读取DX10专有字段确定具体类型
         reader.seek(SeekFrom::Start(0x80))?;
         let dxgi_format_code = reader.read_i32::<LE>()?;
-        let dxgi_format = DxgiFormat::from_i32(dxgi_format_code).ok_or(Error::UnknownTexFormat)?;
+        let invalid_format = || Error::InvalidField {
+            offset: 0x80,
+            field: "DXGI format",
+            value: dxgi_format_code as i64,
+        };
+        let dxgi_format = DxgiFormat::from_i32(dxgi_format_code).ok_or_else(invalid_format)?;
+        match dxgi_format {
+            DxgiFormat::Bc2Unorm | DxgiFormat::Bc3Unorm => {
+                return transcode_legacy(reader, reference, mipmap_count, ColorSpace::Linear);
+            }
+            DxgiFormat::Bc2UnormSrgb | DxgiFormat::Bc3UnormSrgb => {
+                return transcode_legacy(reader, reference, mipmap_count, ColorSpace::Srgb);
+            }
+            _ => {}
+        }
         format = dxgi_format.try_into()?;
     }
 
-    if format.magic() == b"DX10" && !is_raw {
+    // 以文件实际的 FourCC 为准，BC1/BC4/BC5 等格式也可能带有 DX10 扩展头
+    if has_dx10_header {
         reader.seek(SeekFrom::Start(0x94))?;
     } else {
         reader.seek(SeekFrom::Start(0x80))?;
@@ -72,78 +137,52 @@ where
 
     let mut data = vec![];
     reader.read_to_end(&mut data)?;
-
-    let mut out_tex = vec![];
-    out_tex.write_all(W_MAGIC_NUMBER_TEX)?;
-    out_tex.write_i32::<LE>(mipmap_count)?;
-    out_tex.write_i32::<LE>(width)?;
-    out_tex.write_i32::<LE>(height)?;
-    out_tex.write_i32::<LE>(1)?;
-    out_tex.write_i32::<LE>(format as i32)?;
-    out_tex.write_all(TEX_FIXED_UNKN)?;
-
-    if TEX_OF_NEW_DDS.contains(&format) {
-        out_tex.write_i32::<LE>(1)?;
-    } else {
-        out_tex.write_i32::<LE>(0)?;
+    let expected = format.volume_size(width, height, depth, mipmap_count);
+    if data.len() < expected {
+        return Err(Error::DataSizeMismatch(expected, data.len()));
     }
 
-    out_tex.write_all(&[0u8; 4 * 4])?;
-    out_tex.write_all(
-        &[-1_i32; 8]
-            .into_iter()
-            .flat_map(|x| x.to_le_bytes())
-            .collect::<Vec<u8>>(),
-    )?;
-    out_tex.write_i32::<LE>(width)?;
-
-    let is_full_width = is_raw || format == TexFormat::DxgiFormatR8G8Unorm;
-
-    // repeat 3 times
-    for _ in 0..3 {
-        if is_full_width {
-            out_tex.write_i16::<LE>(width as i16)?;
-        } else {
-            out_tex.write_i16::<LE>((width / 2) as i16)?;
-        }
-        out_tex.write_i16::<LE>(width as i16)?;
-        out_tex.write_all(&[0u8; 4 * 2])?;
+    let mut builder = TexInfo::builder()
+        .width(width)
+        .height(height)
+        .format(format)
+        .mipmaps(mipmap_count);
+    if depth > 1 {
+        builder = builder.depth(depth);
     }
-    out_tex.write_all(&[0u8; 4 * 6])?;
-
-    let mut cur_width: i32 = width;
-    let mut cur_height: i32 = height;
-    let mut base_loc: i32 = 0xb8 + mipmap_count * 8;
-    for _ in 0..mipmap_count {
-        out_tex.write_i32::<LE>(base_loc)?;
-        out_tex.write_i32::<LE>(0)?;
-
-        let max_width = if is_raw { 2 } else { 4 };
-        if TEX_WITH_4BPP.contains(&format) {
-            base_loc += cur_width * cur_height / 2;
-        } else if TEX_WITH_16BPP.contains(&format) {
-            base_loc += cur_width * cur_height * 2;
-        } else if is_raw {
-            base_loc += cur_width * cur_height * 4;
-        } else {
-            base_loc += cur_width * cur_height;
-        }
-
-        cur_width /= 2;
-        cur_height /= 2;
-
-        cur_width = i32::max(cur_width, max_width);
-        cur_height = i32::max(cur_height, max_width);
+    if let Some(reference) = reference {
+        builder = builder.unknown_fields_from(reference);
     }
-
+    let mut out_tex = vec![];
+    builder.write_to(&mut out_tex)?;
     out_tex.write_all(&data)?;
 
     Ok(out_tex)
 }
 
+/// tex 不支持 BC2/BC3，解码后重新编码为 BC7
+fn transcode_legacy<R>(
+    reader: &mut R,
+    reference: Option<&TexHeader>,
+    mipmap_count: i32,
+    color_space: ColorSpace,
+) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    reader.seek(SeekFrom::Start(0))?;
+    let image = crate::load_dds_image(reader)?;
+    let options = ConvertOptions::default()
+        .color_space(color_space)
+        .mipmaps(mipmap_count > 1);
+    let dds_data = crate::convert_image_to_dds(&image, &options)?;
+
+    convert(&mut Cursor::new(dds_data), reference)
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{fs::OpenOptions, io::Cursor};
+    use std::fs::OpenOptions;
 
     use super::*;
 
@@ -162,4 +201,150 @@ mod tests {
             .unwrap();
         std::io::copy(&mut Cursor::new(&tex_data), &mut file).unwrap();
     }
+
+    #[cfg(feature = "tex2dds")]
+    #[test]
+    fn test_volume_round_trip() {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .depth(4)
+            .mipmaps(2)
+            .format(TexFormat::DxgiFormatBc1Unorm)
+            .write_to(&mut tex)
+            .unwrap();
+        let data_len = TexFormat::DxgiFormatBc1Unorm.volume_size(8, 8, 4, 2);
+        tex.extend((0..data_len).map(|i| i as u8));
+
+        let dds = crate::tex2dds::convert_to_dds(&mut Cursor::new(&tex)).unwrap();
+        let rebuilt = convert_to_tex(&mut Cursor::new(dds)).unwrap();
+        assert_eq!(rebuilt, tex);
+    }
+    /// DXT3/DXT5 转码为 BC7，DX10 头和旧版 FourCC 两种写法都要识别
+    #[cfg(feature = "tex2dds")]
+    #[test]
+    fn test_transcode_legacy() {
+        use image::{Rgba, RgbaImage};
+        use image_dds::{
+            ddsfile::{
+                self, AlphaMode, D3D10ResourceDimension, D3DFormat, Dds, NewD3dParams,
+                NewDxgiParams,
+            },
+            ImageFormat, Mipmaps, Quality,
+        };
+
+        // image_dds 不能编码 BC2，手写 8x8 的 BC2 数据：每块一种颜色，透明度逐像素递增
+        const COLORS: [u16; 4] = [0xF800, 0x07E0, 0x001F, 0xFFFF];
+        let bc2_data = COLORS
+            .iter()
+            .flat_map(|color| {
+                let alpha = (0..8).map(|i| (i * 2) | ((i * 2 + 1) << 4));
+                let color = color.to_le_bytes().into_iter().chain([0; 6]);
+                alpha.chain(color).collect::<Vec<u8>>()
+            })
+            .collect::<Vec<_>>();
+        let bc2_image = RgbaImage::from_fn(8, 8, |x, y| {
+            let color = COLORS[(y / 4 * 2 + x / 4) as usize];
+            let expand = |value: u16, bits: u32| (value as u32 * 255 / ((1 << bits) - 1)) as u8;
+            Rgba([
+                expand(color >> 11, 5),
+                expand((color >> 5) & 0x3F, 6),
+                expand(color & 0x1F, 5),
+                ((y % 4 * 4 + x % 4) * 17) as u8,
+            ])
+        });
+        let write = |mut dds: Dds| {
+            dds.data = bc2_data.clone();
+            let mut data = vec![];
+            dds.write(&mut data).unwrap();
+            data
+        };
+        let bc2 = |format| {
+            write(
+                Dds::new_dxgi(NewDxgiParams {
+                    height: 8,
+                    width: 8,
+                    depth: None,
+                    format,
+                    mipmap_levels: None,
+                    array_layers: None,
+                    caps2: None,
+                    is_cubemap: false,
+                    resource_dimension: D3D10ResourceDimension::Texture2D,
+                    alpha_mode: AlphaMode::Unknown,
+                })
+                .unwrap(),
+            )
+        };
+        let dxt3 = write(
+            Dds::new_d3d(NewD3dParams {
+                height: 8,
+                width: 8,
+                depth: None,
+                format: D3DFormat::DXT3,
+                mipmap_levels: None,
+                caps2: None,
+            })
+            .unwrap(),
+        );
+
+        // 每个块内的颜色在一条直线上，块压缩的误差较小
+        let bc3_image = RgbaImage::from_fn(16, 16, |x, y| {
+            Rgba([x as u8 * 16, x as u8 * 8, 128, 255 - y as u8 * 16])
+        });
+        let bc3 = |format| {
+            let mut data = vec![];
+            image_dds::dds_from_image(&bc3_image, format, Quality::Fast, Mipmaps::Disabled)
+                .unwrap()
+                .write(&mut data)
+                .unwrap();
+            data
+        };
+        // 去掉 DX10 头，改为旧版插件导出的 FourCC
+        let mut dxt5 = bc3(ImageFormat::BC3RgbaUnorm);
+        dxt5[0x54..0x58].copy_from_slice(b"DXT5");
+        dxt5.drain(0x80..0x94);
+
+        let cases = [
+            (
+                bc2(ddsfile::DxgiFormat::BC2_UNorm),
+                &bc2_image,
+                TexFormat::DxgiFormatBc7Unorm,
+            ),
+            (
+                bc2(ddsfile::DxgiFormat::BC2_UNorm_sRGB),
+                &bc2_image,
+                TexFormat::DxgiFormatBc7UnormSRGB,
+            ),
+            (dxt3, &bc2_image, TexFormat::DxgiFormatBc7UnormSRGB),
+            (
+                bc3(ImageFormat::BC3RgbaUnorm),
+                &bc3_image,
+                TexFormat::DxgiFormatBc7Unorm,
+            ),
+            (
+                bc3(ImageFormat::BC3RgbaUnormSrgb),
+                &bc3_image,
+                TexFormat::DxgiFormatBc7UnormSRGB,
+            ),
+            (dxt5, &bc3_image, TexFormat::DxgiFormatBc7UnormSRGB),
+        ];
+
+        for (dds, image, format) in cases {
+            let tex = convert_to_tex(&mut Cursor::new(dds)).unwrap();
+            let info = TexInfo::from_reader(&mut Cursor::new(&tex)).unwrap();
+            assert_eq!(info.format, format);
+            assert_eq!((info.width as u32, info.height as u32), image.dimensions());
+
+            let decoded = crate::load_tex_image(&mut Cursor::new(&tex)).unwrap();
+            let max_error = image
+                .pixels()
+                .zip(decoded.pixels())
+                .flat_map(|(a, b)| a.0.into_iter().zip(b.0).map(|(a, b)| a.abs_diff(b)))
+                .max()
+                .unwrap();
+            assert!(max_error <= 16, "{:?}: {}", format, max_error);
+        }
+    }
 }
@@ -0,0 +1,145 @@
+use image::{Rgba, RgbaImage};
+
+use crate::error::{Error, Result};
+
+/// 差异像素在标注图中的颜色
+const DIFF_MARK: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// 图片比较参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// 各通道差值不超过该值时视为相同
+    pub tolerance: u8,
+    /// 忽略原图中完全透明的像素，这些像素的颜色在游戏中不可见
+    pub ignore_transparent: bool,
+    /// 是否生成标注差异像素的图片
+    pub annotate: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 0,
+            ignore_transparent: true,
+            annotate: false,
+        }
+    }
+}
+
+/// 图片比较结果
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    /// RGBA 各通道的最大差值
+    pub max_delta: [u8; 4],
+    pub differing_pixels: u64,
+    pub total_pixels: u64,
+    /// 以比较图为底，差异像素标记为品红色
+    pub diff_image: Option<RgbaImage>,
+}
+
+impl DiffReport {
+    pub fn is_identical(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+/// 逐像素比较两张图片
+pub fn diff_images(
+    original: &RgbaImage,
+    compared: &RgbaImage,
+    options: &DiffOptions,
+) -> Result<DiffReport> {
+    if original.dimensions() != compared.dimensions() {
+        return Err(Error::DimensionMismatch(
+            original.dimensions(),
+            compared.dimensions(),
+        ));
+    }
+
+    let mut max_delta = [0u8; 4];
+    let mut differing_pixels = 0;
+    let mut diff_image = options.annotate.then(|| compared.clone());
+
+    for (x, y, orig_pixel) in original.enumerate_pixels() {
+        if options.ignore_transparent && orig_pixel.0[3] == 0 {
+            continue;
+        }
+
+        let compared_pixel = compared.get_pixel(x, y);
+        let mut differs = false;
+        for (max, (a, b)) in max_delta
+            .iter_mut()
+            .zip(orig_pixel.0.iter().zip(compared_pixel.0))
+        {
+            let delta = a.abs_diff(b);
+            *max = (*max).max(delta);
+            differs |= delta > options.tolerance;
+        }
+
+        if differs {
+            differing_pixels += 1;
+            if let Some(diff_image) = &mut diff_image {
+                diff_image.put_pixel(x, y, DIFF_MARK);
+            }
+        }
+    }
+
+    Ok(DiffReport {
+        max_delta,
+        differing_pixels,
+        total_pixels: original.width() as u64 * original.height() as u64,
+        diff_image,
+    })
+}
+
+#[cfg(all(feature = "tex2dds", feature = "dds2tex"))]
+/// 验证压缩损失
+///
+/// image -> tex -> image，与原图比较
+pub fn verify_round_trip(
+    image: &RgbaImage,
+    convert_options: &crate::options::ConvertOptions,
+    diff_options: &DiffOptions,
+) -> Result<DiffReport> {
+    let tex_data = crate::convert_image_to_tex(image, convert_options)?;
+    let decoded = crate::load_tex_image(&mut std::io::Cursor::new(tex_data))?;
+
+    diff_images(image, &decoded, diff_options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_images() {
+        let original = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let mut compared = original.clone();
+        compared.put_pixel(1, 1, Rgba([12, 20, 30, 255]));
+        compared.put_pixel(2, 2, Rgba([10, 20, 40, 255]));
+
+        let report = diff_images(
+            &original,
+            &compared,
+            &DiffOptions {
+                tolerance: 2,
+                annotate: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.max_delta, [2, 0, 10, 0]);
+        assert_eq!(report.differing_pixels, 1);
+        assert_eq!(report.total_pixels, 16);
+        assert_eq!(*report.diff_image.unwrap().get_pixel(2, 2), DIFF_MARK);
+    }
+
+    #[test]
+    fn test_diff_images_ignore_transparent() {
+        let original = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 0, 0]));
+        let compared = RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 0]));
+
+        let report = diff_images(&original, &compared, &DiffOptions::default()).unwrap();
+        assert!(report.is_identical());
+    }
+}
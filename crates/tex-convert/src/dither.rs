@@ -0,0 +1,120 @@
+use image::RgbaImage;
+
+/// 抖动方式
+///
+/// 在块压缩前将颜色量化到 RGB565，把渐变中的色带分散为噪点。主要用于 BC1 这类低精度格式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Dither {
+    #[default]
+    None,
+    /// 4x4 Bayer 有序抖动，结果稳定，适合逐块压缩
+    Ordered,
+    /// Floyd–Steinberg 误差扩散，渐变更平滑
+    FloydSteinberg,
+}
+
+/// RGB 各通道的量化位数，与 BC1 端点颜色的精度一致
+const CHANNEL_BITS: [u32; 3] = [5, 6, 5];
+
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+/// 对图片的 RGB 通道进行抖动，透明度保持不变
+pub fn dither(image: &mut RgbaImage, method: Dither) {
+    match method {
+        Dither::None => {}
+        Dither::Ordered => dither_ordered(image),
+        Dither::FloydSteinberg => dither_floyd_steinberg(image),
+    }
+}
+
+fn dither_ordered(image: &mut RgbaImage) {
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        // 阈值范围 (-0.5, 0.5)，单位为一个量化步长
+        let threshold = (BAYER_4X4[y as usize % 4][x as usize % 4] + 0.5) / 16.0 - 0.5;
+        for (channel, bits) in pixel.0[..3].iter_mut().zip(CHANNEL_BITS) {
+            let step = 255.0 / ((1 << bits) - 1) as f32;
+            *channel = quantize(*channel as f32 + threshold * step, bits);
+        }
+    }
+}
+
+fn dither_floyd_steinberg(image: &mut RgbaImage) {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut values = image
+        .pixels()
+        .flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2]].map(|c| c as f32))
+        .collect::<Vec<_>>();
+
+    for y in 0..height {
+        for x in 0..width {
+            for (c, bits) in CHANNEL_BITS.into_iter().enumerate() {
+                let index = (y * width + x) * 3 + c;
+                let old = values[index];
+                let new = quantize(old, bits) as f32;
+                values[index] = new;
+
+                let error = old - new;
+                let mut spread = |dx: isize, dy: usize, weight: f32| {
+                    let nx = x as isize + dx;
+                    if nx < 0 || nx >= width as isize || y + dy >= height {
+                        return;
+                    }
+                    values[((y + dy) * width + nx as usize) * 3 + c] += error * weight;
+                };
+                spread(1, 0, 7.0 / 16.0);
+                spread(-1, 1, 3.0 / 16.0);
+                spread(0, 1, 5.0 / 16.0);
+                spread(1, 1, 1.0 / 16.0);
+            }
+        }
+    }
+
+    for (pixel, rgb) in image.pixels_mut().zip(values.chunks_exact(3)) {
+        for (channel, value) in pixel.0[..3].iter_mut().zip(rgb) {
+            *channel = *value as u8;
+        }
+    }
+}
+
+/// 量化到指定位数后再扩展回 8 位
+fn quantize(value: f32, bits: u32) -> u8 {
+    let max = ((1 << bits) - 1) as f32;
+    let level = (value.clamp(0.0, 255.0) / 255.0 * max).round();
+    (level / max * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_quantize() {
+        assert_eq!(quantize(0.0, 5), 0);
+        assert_eq!(quantize(255.0, 5), 255);
+        assert_eq!(quantize(300.0, 6), 255);
+        // 5 位量化的相邻级别为 0 与 8
+        assert_eq!(quantize(3.0, 5), 0);
+        assert_eq!(quantize(5.0, 5), 8);
+    }
+
+    #[test]
+    fn test_dither_keeps_average() {
+        for method in [Dither::Ordered, Dither::FloydSteinberg] {
+            // 4 介于 5 位量化的 0 与 8 之间，抖动后均值应接近原值
+            let mut image = RgbaImage::from_pixel(16, 16, Rgba([4, 4, 4, 200]));
+            dither(&mut image, method);
+
+            let sum = image.pixels().map(|p| p.0[0] as u32).sum::<u32>();
+            let average = sum as f32 / 256.0;
+            assert!((average - 4.0).abs() < 1.0, "{:?}: {}", method, average);
+            assert!(image.pixels().all(|p| p.0[3] == 200));
+        }
+    }
+}
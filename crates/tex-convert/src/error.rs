@@ -10,9 +10,37 @@ pub enum Error {
     CreateImageFromDds(#[from] image_dds::error::CreateImageError),
     #[error("Create dds from image error: {0}")]
     CreateDdsFromImage(#[from] image_dds::CreateDdsError),
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
 
     #[error("Invalid magic number: expected {0:#x}, got {1:#x}")]
     BadMagic(i32, i32),
     #[error("Unknown tex format")]
     UnknownTexFormat,
+    /// 能识别的 DXGI 格式，但 tex 没有对应的格式编号
+    #[error("DXGI format {0:?} is not supported by tex")]
+    UnsupportedDxgiFormat(crate::spec::DxgiFormat),
+    /// 解析文件时字段值无效，记录字段所在的文件偏移和读取到的原始值
+    #[error("Invalid {field} at offset {offset:#x}: {value} ({value:#x})")]
+    InvalidField {
+        offset: u64,
+        field: &'static str,
+        value: i64,
+    },
+    #[error("Unsupported tex version: {0}")]
+    UnsupportedTexVersion(u32),
+    #[error("Layer index out of range: {0} (image count {1})")]
+    LayerOutOfRange(u32, u32),
+    #[error("Depth slice out of range: {0} (depth {1})")]
+    SliceOutOfRange(u32, u32),
+    #[error("Mipmap level out of range: {0} (mipmap count {1})")]
+    MipLevelOutOfRange(u32, u32),
+    #[error("Data size mismatch: expected {0} bytes, got {1}")]
+    DataSizeMismatch(usize, usize),
+    #[error("Tile index out of range: {0} (tile count {1})")]
+    TileOutOfRange(u32, u32),
+    #[error("Invalid sheet layout: {0}")]
+    InvalidLayout(&'static str),
+    #[error("Image dimensions mismatch: {0:?} vs {1:?}")]
+    DimensionMismatch((u32, u32), (u32, u32)),
 }
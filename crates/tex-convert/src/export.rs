@@ -0,0 +1,102 @@
+use std::io::{Read, Seek, Write};
+
+use image::{ImageFormat, RgbaImage};
+
+use crate::error::Result;
+
+/// 导出的图片格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageOutputFormat {
+    Png,
+    /// 无损 WebP
+    #[cfg(feature = "webp")]
+    WebP,
+    /// JPEG 不支持透明度，导出时丢弃 alpha 通道
+    #[cfg(feature = "jpeg")]
+    Jpeg {
+        quality: u8,
+    },
+}
+
+impl ImageOutputFormat {
+    /// 文件扩展名，不含 `.`
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageOutputFormat::Png => "png",
+            #[cfg(feature = "webp")]
+            ImageOutputFormat::WebP => "webp",
+            #[cfg(feature = "jpeg")]
+            ImageOutputFormat::Jpeg { .. } => "jpg",
+        }
+    }
+}
+
+/// 将 tex 解码并编码为指定格式的图片
+///
+/// 数组纹理与立方体贴图只导出第一张图片。
+pub fn export_image<R, W>(reader: &mut R, writer: &mut W, format: ImageOutputFormat) -> Result<()>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+{
+    let image = crate::load_tex_image(reader)?;
+
+    write_image(&image, writer, format)
+}
+
+/// 导出 tex 的指定 mipmap 层，图片尺寸为该层的尺寸
+pub fn export_image_mipmap<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    format: ImageOutputFormat,
+    level: u32,
+) -> Result<()>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+{
+    let image = crate::load_tex_mipmap(reader, level)?;
+
+    write_image(&image, writer, format)
+}
+
+/// 将图片编码为指定格式
+pub fn write_image<W>(image: &RgbaImage, writer: &mut W, format: ImageOutputFormat) -> Result<()>
+where
+    W: Write + Seek,
+{
+    match format {
+        ImageOutputFormat::Png => image.write_to(writer, ImageFormat::Png)?,
+        #[cfg(feature = "webp")]
+        ImageOutputFormat::WebP => image.write_to(writer, ImageFormat::WebP)?,
+        #[cfg(feature = "jpeg")]
+        ImageOutputFormat::Jpeg { quality } => {
+            let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(writer, quality)
+                .encode_image(&rgb)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_write_image_png() {
+        let image = RgbaImage::from_pixel(4, 2, Rgba([1, 2, 3, 4]));
+        let mut data = Cursor::new(vec![]);
+        write_image(&image, &mut data, ImageOutputFormat::Png).unwrap();
+
+        let decoded = image::load_from_memory_with_format(data.get_ref(), ImageFormat::Png)
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded, image);
+    }
+}
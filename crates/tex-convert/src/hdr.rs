@@ -0,0 +1,148 @@
+use std::io::Read;
+
+use image::Rgba32FImage;
+
+use crate::{error::Result, options::ConvertOptions};
+
+#[cfg(feature = "tex2dds")]
+/// 读取 tex 为 [image::Rgba32FImage]
+///
+/// 与 [crate::load_tex_image] 不同，BC6H 等 HDR 格式的像素值不会被截断到 `[0, 1]`。
+pub fn load_tex_image_hdr<R: Read + std::io::Seek>(reader: &mut R) -> Result<Rgba32FImage> {
+    let dds_data = crate::tex2dds::convert_mip0_to_dds(reader)?;
+
+    load_dds_image_hdr(&mut &dds_data[..])
+}
+
+/// 读取 dds 为 [image::Rgba32FImage]
+pub fn load_dds_image_hdr<R: Read>(reader: &mut R) -> Result<Rgba32FImage> {
+    let dds = image_dds::ddsfile::Dds::read(reader)?;
+
+    Ok(image_dds::imagef32_from_dds(&dds, 0)?)
+}
+
+/// 将 HDR 图片编码为 BC6H dds
+///
+/// 只使用 [ConvertOptions] 中的质量和 mipmap 设置。
+/// tex 只支持无符号的 BC6H，负值会被截断为 0。
+pub fn convert_hdr_image_to_dds(image: &Rgba32FImage, options: &ConvertOptions) -> Result<Vec<u8>> {
+    // 编码器不处理负值，直接编码会得到半精度浮点的最大值
+    let mut image = image.clone();
+    image
+        .pixels_mut()
+        .flat_map(|pixel| pixel.0.iter_mut())
+        .for_each(|value| *value = value.max(0.0));
+    let mut dds = image_dds::dds_from_imagef32(
+        &image,
+        image_dds::ImageFormat::BC6hRgbUfloat,
+        options.quality.into(),
+        options.image_dds_mipmaps(),
+    )?;
+    dds.header.depth = Some(1);
+    if !options.mipmaps {
+        dds.header.mip_map_count = Some(1);
+    }
+
+    let mut dds_data = vec![];
+    dds.write(&mut dds_data)?;
+
+    Ok(dds_data)
+}
+
+#[cfg(feature = "dds2tex")]
+/// 将 HDR 图片编码为 BC6H tex
+///
+/// [image::Rgba32FImage] -> dds -> tex
+pub fn convert_hdr_image_to_tex(image: &Rgba32FImage, options: &ConvertOptions) -> Result<Vec<u8>> {
+    let dds_data = convert_hdr_image_to_dds(image, options)?;
+
+    crate::dds2tex::convert_to_tex(&mut std::io::Cursor::new(&dds_data))
+}
+
+#[cfg(feature = "exr")]
+/// 读取 OpenEXR 图片
+pub fn load_exr<R>(reader: &mut R) -> Result<Rgba32FImage>
+where
+    R: std::io::BufRead + std::io::Seek,
+{
+    let image = image::load(reader, image::ImageFormat::OpenExr)?;
+
+    Ok(image.to_rgba32f())
+}
+
+#[cfg(feature = "exr")]
+/// 保存为 OpenEXR 图片
+pub fn write_exr<W>(image: &Rgba32FImage, writer: &mut W) -> Result<()>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    image.write_to(writer, image::ImageFormat::OpenExr)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    /// 解码结果与原图的差异在 BC6H 的误差范围内
+    fn assert_close(image: &Rgba32FImage, decoded: &Rgba32FImage) {
+        assert_eq!(decoded.dimensions(), image.dimensions());
+        for (expected, actual) in image.pixels().zip(decoded.pixels()) {
+            for channel in 0..3 {
+                let (expected, actual) = (expected[channel].max(0.0), actual[channel]);
+                assert!(
+                    (expected - actual).abs() <= expected * 0.05 + 0.01,
+                    "{} != {}",
+                    expected,
+                    actual
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bc6h_round_trip() {
+        // 每个 4x4 块内颜色相同，只留下端点量化的误差；超出 [0, 1] 的值不能被截断，负值截断为 0
+        let image = Rgba32FImage::from_fn(16, 8, |x, y| {
+            let value = (x / 4) as f32 * 2.0;
+            match y {
+                0..4 => Rgba([value, value * 0.5 + 1.0, 4.0, 1.0]),
+                _ => Rgba([-1.0, value, 0.0, 1.0]),
+            }
+        });
+
+        let dds = convert_hdr_image_to_dds(&image, &ConvertOptions::default()).unwrap();
+        assert_close(&image, &load_dds_image_hdr(&mut &dds[..]).unwrap());
+
+        #[cfg(all(feature = "dds2tex", feature = "tex2dds"))]
+        {
+            use std::io::Cursor;
+
+            use crate::spec::{TexFormat, TexInfo};
+
+            let tex = convert_hdr_image_to_tex(&image, &ConvertOptions::default()).unwrap();
+            let info = TexInfo::from_reader(&mut Cursor::new(&tex)).unwrap();
+            assert_eq!(info.format, TexFormat::DxgiFormatBc6hUf16);
+            assert_close(&image, &load_tex_image_hdr(&mut Cursor::new(&tex)).unwrap());
+        }
+    }
+
+    #[cfg(feature = "exr")]
+    #[test]
+    fn test_exr_round_trip() {
+        use std::io::Cursor;
+
+        // 超出 [0, 1] 的值不能被截断
+        let image = Rgba32FImage::from_fn(8, 4, |x, y| Rgba([x as f32 * 2.5, y as f32, 0.25, 1.0]));
+
+        let mut data = Cursor::new(vec![]);
+        write_exr(&image, &mut data).unwrap();
+        data.set_position(0);
+        let loaded = load_exr(&mut data).unwrap();
+
+        assert_eq!(loaded, image);
+    }
+}
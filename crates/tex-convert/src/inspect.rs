@@ -0,0 +1,89 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{
+    color::ColorSpace,
+    error::Result,
+    spec::{TexDimension, TexFormat, TexInfo},
+};
+
+/// tex 文件概要
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TexSummary {
+    pub width: u32,
+    pub height: u32,
+    pub format: TexFormat,
+    pub color_space: ColorSpace,
+    pub dimension: TexDimension,
+    pub mip_map_count: u32,
+    pub image_count: u32,
+    /// 体积纹理的深度，其他纹理为 1
+    pub depth: u32,
+    /// 第一个 mipmap 在文件中的偏移，即文件头和偏移表的总大小
+    pub data_offset: u64,
+    /// 文件中像素数据的实际大小，即第一个 mipmap 偏移之后的全部字节
+    pub data_size: u64,
+    /// 按格式、尺寸和 mipmap 数量计算的显存占用估计
+    pub vram_size: u64,
+}
+
+/// 只读取文件头获取 tex 概要，不解码像素数据
+///
+/// 开销与文件大小无关，适合批量扫描大量文件。
+pub fn inspect<R>(reader: &mut R) -> Result<TexSummary>
+where
+    R: Read + Seek,
+{
+    let info = TexInfo::from_reader(reader)?;
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    let mip_map_count = info.mip_map_count.max(1);
+
+    Ok(TexSummary {
+        width: info.width as u32,
+        height: info.height as u32,
+        format: info.format,
+        color_space: info.color_space,
+        dimension: info.dimension,
+        mip_map_count: mip_map_count as u32,
+        image_count: info.image_count as u32,
+        depth: info.depth as u32,
+        data_offset: info.offset as u64,
+        data_size: stream_len.saturating_sub(info.offset as u64),
+        vram_size: info
+            .format
+            .volume_size(info.width, info.height, info.depth, mip_map_count)
+            as u64
+            * info.image_count as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_inspect() {
+        let mut data = vec![];
+        TexInfo::builder()
+            .width(128)
+            .height(512)
+            .format(TexFormat::DxgiFormatBc1UnormSRGB)
+            .mipmaps(2)
+            .write_to(&mut data)
+            .unwrap();
+        let pixels = 128 * 512 / 2 + 64 * 256 / 2;
+        data.resize(data.len() + pixels, 0);
+
+        let summary = inspect(&mut Cursor::new(&data)).unwrap();
+        assert_eq!((summary.width, summary.height), (128, 512));
+        assert_eq!(summary.format, TexFormat::DxgiFormatBc1UnormSRGB);
+        assert_eq!(summary.color_space, ColorSpace::Srgb);
+        assert_eq!(summary.mip_map_count, 2);
+        assert_eq!(summary.image_count, 1);
+        assert_eq!(summary.depth, 1);
+        assert_eq!(summary.data_offset + summary.data_size, data.len() as u64);
+        assert_eq!(summary.data_size, pixels as u64);
+        assert_eq!(summary.vram_size, pixels as u64);
+    }
+}
@@ -1,19 +1,129 @@
-use std::io::{Read, Seek};
+use std::{
+    borrow::Cow,
+    io::{BufRead, Read, Seek, SeekFrom},
+};
 
+#[cfg(feature = "export")]
+pub use export::{export_image, ImageOutputFormat};
 use image::RgbaImage;
-use image_dds::ddsfile::AlphaMode;
+use image_dds::ddsfile::{AlphaMode, D3D10ResourceDimension, Dds, DxgiFormat, NewDxgiParams};
+pub use inspect::{inspect, TexSummary};
+use options::ConvertOptions;
+pub use validate::{validate, ValidationReport};
 
+pub mod alpha;
+pub mod atlas;
+pub mod auto;
+#[cfg(feature = "batch")]
+pub mod batch;
+pub mod color;
+pub mod diff;
+pub mod dither;
 pub mod error;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod hdr;
+pub mod inspect;
+#[cfg(feature = "ispc")]
+mod intel;
+mod mipmap;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
 pub mod spec;
+pub mod swizzle;
+pub mod validate;
 
 #[cfg(feature = "dds2tex")]
 pub mod dds2tex;
+#[cfg(feature = "re-tex")]
+pub mod re_tex2dds;
 #[cfg(feature = "tex2dds")]
 pub mod tex2dds;
+#[cfg(feature = "tex2ktx2")]
+pub mod tex2ktx2;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[cfg(feature = "tex2dds")]
+/// Read the first image of a tex as [image::RgbaImage]
+///
+/// Only the data of mip 0 is read, the rest of the file is skipped.
 pub fn load_tex_image<R: Read + Seek>(reader: &mut R) -> Result<RgbaImage, error::Error> {
-    let dds_data = tex2dds::convert_to_dds(reader)?;
+    let dds_data = tex2dds::convert_mip0_to_dds(reader)?;
+
+    load_dds_image(&mut &dds_data[..])
+}
+
+#[cfg(feature = "tex2dds")]
+/// Same as [load_tex_image], reading from the file data directly
+pub fn load_tex_image_bytes(data: &[u8]) -> Result<RgbaImage, error::Error> {
+    load_tex_image(&mut std::io::Cursor::new(data))
+}
+
+#[cfg(feature = "tex2dds")]
+/// Read tex image as [image::RgbaImage], converting pixel values to the given color space
+///
+/// [load_tex_image] returns the raw values stored in the tex, whose meaning depends on
+/// [spec::TexInfo::color_space].
+pub fn load_tex_image_in<R: Read + Seek>(
+    reader: &mut R,
+    color_space: color::ColorSpace,
+) -> Result<RgbaImage, error::Error> {
+    reader.seek(std::io::SeekFrom::Start(0))?;
+    let info = spec::TexInfo::from_reader(reader)?;
+    let mut image = load_tex_image(reader)?;
+    color::convert_color_space(&mut image, info.color_space, color_space);
+
+    Ok(image)
+}
+
+#[cfg(feature = "tex2dds")]
+/// Read a single image (array element or cubemap face) of a tex as [image::RgbaImage]
+pub fn load_tex_layer<R: Read + Seek>(
+    reader: &mut R,
+    layer: u32,
+) -> Result<RgbaImage, error::Error> {
+    let dds_data = tex2dds::convert_layer_to_dds(reader, layer)?;
+
+    load_dds_image(&mut &dds_data[..])
+}
+
+#[cfg(feature = "tex2dds")]
+/// Read a single depth slice of a volume (3D) tex as [image::RgbaImage]
+pub fn load_tex_slice<R: Read + Seek>(
+    reader: &mut R,
+    slice: u32,
+) -> Result<RgbaImage, error::Error> {
+    let dds_data = tex2dds::convert_slice_to_dds(reader, slice)?;
+
+    load_dds_image(&mut &dds_data[..])
+}
+
+#[cfg(feature = "tex2dds")]
+/// Read the first image of a possibly truncated tex as [image::RgbaImage]
+///
+/// Succeeds as long as mip 0 is complete; the returned report lists the mipmap levels
+/// that were cut off. See [tex2dds::salvage_to_dds].
+pub fn salvage_tex_image<R: Read + Seek>(
+    reader: &mut R,
+) -> Result<(RgbaImage, tex2dds::SalvageReport), error::Error> {
+    let (dds_data, report) = tex2dds::salvage_to_dds(reader)?;
+
+    Ok((load_dds_image(&mut &dds_data[..])?, report))
+}
+
+#[cfg(feature = "tex2dds")]
+/// Read a single mipmap level of a tex as [image::RgbaImage]
+///
+/// The returned image has the dimensions of that level.
+pub fn load_tex_mipmap<R: Read + Seek>(
+    reader: &mut R,
+    level: u32,
+) -> Result<RgbaImage, error::Error> {
+    let dds_data = tex2dds::convert_surface_to_dds(reader, 0, level)?;
 
     load_dds_image(&mut &dds_data[..])
 }
@@ -22,25 +132,90 @@ pub fn load_tex_image<R: Read + Seek>(reader: &mut R) -> Result<RgbaImage, error
 /// Convert [image::RgbaImage] to tex image
 ///
 /// [image::RgbaImage] -> dds -> tex
-pub fn convert_image_to_tex(image: &RgbaImage) -> Result<Vec<u8>, error::Error> {
+pub fn convert_image_to_tex(
+    image: &RgbaImage,
+    options: &ConvertOptions,
+) -> Result<Vec<u8>, error::Error> {
     use std::io::Cursor;
 
-    let dds_data = convert_image_to_dds(image)?;
+    let dds_data = convert_image_to_dds(image, options)?;
 
     dds2tex::convert_to_tex(&mut Cursor::new(&dds_data))
 }
 
-pub fn convert_image_to_dds(image: &RgbaImage) -> Result<Vec<u8>, error::Error> {
-    let mut dds = image_dds::dds_from_image(
-        image,
-        image_dds::ImageFormat::BC7RgbaUnormSrgb,
-        image_dds::Quality::Slow,
-        image_dds::Mipmaps::Disabled,
-    )?;
+#[cfg(feature = "dds2tex")]
+/// Convert [image::RgbaImage] to tex image, copying unknown header fields from `reference`
+///
+/// Rebuilding a vanilla texture this way gives a file that only differs in pixel data.
+pub fn convert_image_to_tex_like(
+    image: &RgbaImage,
+    options: &ConvertOptions,
+    reference: &spec::TexHeader,
+) -> Result<Vec<u8>, error::Error> {
+    use std::io::Cursor;
+
+    let dds_data = convert_image_to_dds(image, options)?;
+
+    dds2tex::convert_to_tex_like(&mut Cursor::new(&dds_data), reference)
+}
+
+/// Convert [image::RgbaImage] to dds image
+///
+/// When [ConvertOptions::mipmaps] is enabled, a full mipmap chain is generated.
+pub fn convert_image_to_dds(
+    image: &RgbaImage,
+    options: &ConvertOptions,
+) -> Result<Vec<u8>, error::Error> {
+    let options = &options.resolve_format(image);
+    let target_color_space = options.target_color_space();
+    let mut image = match options.input_color_space {
+        Some(input) if input != target_color_space => {
+            let mut converted = image.clone();
+            color::convert_color_space(&mut converted, input, target_color_space);
+            Cow::Owned(converted)
+        }
+        _ => Cow::Borrowed(image),
+    };
+    let premultiplied = options.should_premultiply(&image);
+    if premultiplied {
+        alpha::premultiply(image.to_mut());
+    }
+    if options.dither != dither::Dither::None {
+        dither::dither(image.to_mut(), options.dither);
+    }
+    let image = image.as_ref();
+
+    let mut dds = match options.format.image_format(options.srgb) {
+        Some(_) if !options.format.is_block_compressed() => encode_uncompressed(image, options)?,
+        #[cfg(feature = "ispc")]
+        Some(_) if options.format == options::TargetFormat::Bc7 => {
+            intel::encode_bc7(image, options)?
+        }
+        #[cfg(feature = "parallel")]
+        Some(format) if parallel::should_split(image) => {
+            parallel::encode_strips(image, format, options)?
+        }
+        Some(format) if options.mipmaps || image.width() % 4 != 0 || image.height() % 4 != 0 => {
+            encode_padded(image, format, options)?
+        }
+        Some(format) => image_dds::dds_from_image(
+            image,
+            format,
+            options.quality.into(),
+            options.image_dds_mipmaps(),
+        )?,
+        None => encode_uncompressed(image, options)?,
+    };
     dds.header.depth = Some(1);
-    dds.header.mip_map_count = Some(1);
+    if !options.mipmaps {
+        dds.header.mip_map_count = Some(1);
+    }
     if let Some(header10) = &mut dds.header10 {
-        header10.alpha_mode = AlphaMode::Unknown;
+        header10.alpha_mode = if premultiplied {
+            AlphaMode::PreMultiplied
+        } else {
+            AlphaMode::Unknown
+        };
     }
 
     let mut dds_data = vec![];
@@ -49,10 +224,164 @@ pub fn convert_image_to_dds(image: &RgbaImage) -> Result<Vec<u8>, error::Error>
     Ok(dds_data)
 }
 
+/// 逐层编码尺寸不是 4 的倍数或需要生成 mipmap 的图片
+///
+/// 块压缩格式以 4x4 为单位编码，每层 mipmap 先复制边缘像素补齐到块边界再单独编码，
+/// dds 头中记录原始尺寸。mipmap 由 [encode_mip_chain] 在线性空间中生成。
+fn encode_padded(
+    image: &RgbaImage,
+    format: image_dds::ImageFormat,
+    options: &ConvertOptions,
+) -> Result<Dds, error::Error> {
+    let mut dxgi_format = None;
+    let (mip_map_count, data) = encode_mip_chain(image, options, |level| {
+        let surface = image_dds::dds_from_image(
+            &pad_to_block(level),
+            format,
+            options.quality.into(),
+            image_dds::Mipmaps::Disabled,
+        )?;
+        dxgi_format = surface.get_dxgi_format();
+        Ok(surface.data)
+    })?;
+
+    new_dds_2d(
+        image.dimensions(),
+        dxgi_format.ok_or(error::Error::UnknownTexFormat)?,
+        mip_map_count,
+        data,
+    )
+}
+
+/// 复制右侧和底部的边缘像素，将图片补齐到 4 的倍数
+pub(crate) fn pad_to_block(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn(
+        width.next_multiple_of(4),
+        height.next_multiple_of(4),
+        |x, y| *image.get_pixel(x.min(width - 1), y.min(height - 1)),
+    )
+}
+
+/// 编码为未压缩的 R8G8 或 RGBA8 dds，R8G8 只取 R、G 两个通道
+///
+/// 未压缩格式按像素存放，不需要补齐到块边界。
+fn encode_uncompressed(image: &RgbaImage, options: &ConvertOptions) -> Result<Dds, error::Error> {
+    let r8g8 = options.format == options::TargetFormat::R8G8;
+    let (mip_map_count, data) = encode_mip_chain(image, options, |level| {
+        Ok(if r8g8 {
+            level
+                .pixels()
+                .flat_map(|pixel| [pixel.0[0], pixel.0[1]])
+                .collect()
+        } else {
+            level.as_raw().clone()
+        })
+    })?;
+    let format = match (r8g8, options.srgb) {
+        (true, _) => DxgiFormat::R8G8_UNorm,
+        (false, false) => DxgiFormat::R8G8B8A8_UNorm,
+        (false, true) => DxgiFormat::R8G8B8A8_UNorm_sRGB,
+    };
+
+    new_dds_2d(image.dimensions(), format, mip_map_count, data)
+}
+
+/// 逐层缩小图片并编码，返回 mipmap 层数与拼接后的数据
+///
+/// 按 [ConvertOptions::mip_filter] 在线性空间中缩小，见 [mipmap::MipChain]。
+pub(crate) fn encode_mip_chain<F>(
+    image: &RgbaImage,
+    options: &ConvertOptions,
+    mut encode: F,
+) -> Result<(u32, Vec<u8>), error::Error>
+where
+    F: FnMut(&RgbaImage) -> Result<Vec<u8>, error::Error>,
+{
+    let (width, height) = image.dimensions();
+    let mip_map_count = if options.mipmaps {
+        u32::max(width, height).max(1).ilog2() + 1
+    } else {
+        1
+    };
+
+    let mut data = encode(image)?;
+    if mip_map_count > 1 {
+        let mut chain =
+            mipmap::MipChain::new(image, options.target_color_space(), options.mip_filter);
+        for _ in 1..mip_map_count {
+            data.extend(encode(&chain.next_level())?);
+        }
+    }
+
+    Ok((mip_map_count, data))
+}
+
+pub(crate) fn new_dds_2d(
+    (width, height): (u32, u32),
+    format: DxgiFormat,
+    mip_map_count: u32,
+    data: Vec<u8>,
+) -> Result<Dds, error::Error> {
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        height,
+        width,
+        depth: None,
+        format,
+        mipmap_levels: Some(mip_map_count),
+        array_layers: None,
+        caps2: None,
+        is_cubemap: false,
+        resource_dimension: D3D10ResourceDimension::Texture2D,
+        alpha_mode: AlphaMode::Unknown,
+    })?;
+    dds.data = data;
+
+    Ok(dds)
+}
+
+/// Decode an image file (png, tga, ...) as [image::RgbaImage]
+///
+/// Supported formats depend on the enabled `image` features (see the `tga` feature).
+/// Some exporters write 32-bit TGA files whose header declares no alpha bits;
+/// the fourth channel of such images is not alpha and they are treated as fully opaque.
+/// TGA files with declared alpha bits keep their alpha channel, even if it is all zero.
+pub fn load_image<R: BufRead + Seek>(
+    reader: &mut R,
+    format: image::ImageFormat,
+) -> Result<RgbaImage, error::Error> {
+    let unused_alpha = format == image::ImageFormat::Tga && tga_has_unused_alpha(reader)?;
+    let mut image = image::load(reader, format)?.to_rgba8();
+    if unused_alpha {
+        image.pixels_mut().for_each(|p| p.0[3] = 255);
+    }
+
+    Ok(image)
+}
+
+/// Whether the TGA header declares 32-bit pixels with zero alpha bits
+fn tga_has_unused_alpha<R: Read + Seek>(reader: &mut R) -> Result<bool, error::Error> {
+    let start = reader.stream_position()?;
+    let mut header = [0; 18];
+    reader.read_exact(&mut header)?;
+    reader.seek(SeekFrom::Start(start))?;
+    // byte 16: pixel depth, low 4 bits of byte 17: alpha bits
+    Ok(header[16] == 32 && header[17] & 0x0F == 0)
+}
+
 /// Read dds image as [image::RgbaImage]
+///
+/// Premultiplied alpha (as recorded in the DX10 header) is converted back to straight alpha.
 pub fn load_dds_image<R: Read>(reader: &mut R) -> Result<RgbaImage, error::Error> {
     let dds = image_dds::ddsfile::Dds::read(reader)?;
-    let image = image_dds::image_from_dds(&dds, 0)?;
+    let mut image = image_dds::image_from_dds(&dds, 0)?;
+    if dds
+        .header10
+        .as_ref()
+        .is_some_and(|header10| header10.alpha_mode == AlphaMode::PreMultiplied)
+    {
+        alpha::unpremultiply(&mut image);
+    }
 
     Ok(image)
 }
@@ -70,7 +399,7 @@ mod tests {
     fn test_convert_image_to_tex() {
         let img = image::open("../../test_data/chat_stamp00_ID.png").unwrap();
         if let DynamicImage::ImageRgba8(img) = img {
-            let tex_data = convert_image_to_tex(&img).unwrap();
+            let tex_data = convert_image_to_tex(&img, &ConvertOptions::default()).unwrap();
             let mut file = OpenOptions::new()
                 .create(true)
                 .truncate(true)
@@ -85,7 +414,7 @@ mod tests {
     fn test_convert_image_to_dds() {
         let img = image::open("../../test_data/chat_stamp00_ID.png").unwrap();
         if let DynamicImage::ImageRgba8(img) = img {
-            let dds_data = convert_image_to_dds(&img).unwrap();
+            let dds_data = convert_image_to_dds(&img, &ConvertOptions::default()).unwrap();
             let mut file = OpenOptions::new()
                 .create(true)
                 .truncate(true)
@@ -95,4 +424,95 @@ mod tests {
             file.write_all(&dds_data).unwrap();
         }
     }
+
+    #[test]
+    fn test_convert_image_to_tex_r8g8() {
+        use image::Rgba;
+
+        use crate::{options::TargetFormat, spec::TexHeader};
+
+        let img = RgbaImage::from_pixel(8, 4, Rgba([1, 2, 3, 4]));
+        let options = ConvertOptions::new()
+            .format(TargetFormat::R8G8)
+            .mipmaps(true);
+        let tex_data = convert_image_to_tex(&img, &options).unwrap();
+
+        let header = TexHeader::from_reader(&mut &tex_data[..]).unwrap();
+        assert_eq!(header.format, spec::TexFormat::DxgiFormatR8G8Unorm);
+        assert_eq!(header.mip_map_count, 4);
+        // 8x4 + 4x2 + 2x1 + 1x1
+        let pixel_count = 32 + 8 + 2 + 1;
+        assert_eq!(tex_data.len() as u64, header.size() + pixel_count * 2);
+        let base = header.size();
+        assert_eq!(
+            header.mip_offsets,
+            vec![base, base + 64, base + 64 + 16, base + 64 + 16 + 4]
+        );
+        assert_eq!(&tex_data[base as usize..][..2], &[1, 2]);
+    }
+
+    #[test]
+    fn test_convert_image_to_dds_rgba8() {
+        use std::io::Cursor;
+
+        use image::Rgba;
+
+        use crate::options::TargetFormat;
+
+        // 未压缩格式不补齐到 4x4 块
+        let convert = |width, height, mipmaps| {
+            let img = RgbaImage::from_pixel(width, height, Rgba([1, 2, 3, 4]));
+            let options = ConvertOptions::new()
+                .format(TargetFormat::Rgba8)
+                .mipmaps(mipmaps);
+            let dds_data = convert_image_to_dds(&img, &options).unwrap();
+            Dds::read(&mut Cursor::new(dds_data)).unwrap()
+        };
+
+        let dds = convert(8, 8, true);
+        assert_eq!(dds.get_num_mipmap_levels(), 4);
+        // 8x8 + 4x4 + 2x2 + 1x1
+        assert_eq!(dds.data.len(), (64 + 16 + 4 + 1) * 4);
+        let dds = convert(5, 3, false);
+        assert_eq!(dds.data.len(), 5 * 3 * 4);
+        let dds = convert(5, 3, true);
+        // 5x3 + 2x1 + 1x1
+        assert_eq!(dds.data.len(), (15 + 2 + 1) * 4);
+        assert_eq!(&dds.data[..4], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_load_tga_without_alpha() {
+        use std::io::Cursor;
+
+        use image::Rgba;
+
+        let img = RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 0]));
+        let mut tga = Cursor::new(vec![]);
+        img.write_to(&mut tga, image::ImageFormat::Tga).unwrap();
+
+        // declared alpha bits: a fully transparent image stays transparent
+        tga.set_position(0);
+        let loaded = load_image(&mut tga, image::ImageFormat::Tga).unwrap();
+        assert_eq!(*loaded.get_pixel(1, 1), Rgba([10, 20, 30, 0]));
+
+        // no alpha bits: the fourth channel is unused
+        tga.get_mut()[17] &= 0xF0;
+        tga.set_position(0);
+        let loaded = load_image(&mut tga, image::ImageFormat::Tga).unwrap();
+        assert_eq!(*loaded.get_pixel(1, 1), Rgba([10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_pad_to_block() {
+        use image::Rgba;
+
+        let mut img = RgbaImage::from_pixel(5, 3, Rgba([0, 0, 0, 255]));
+        img.put_pixel(4, 2, Rgba([255, 0, 0, 255]));
+
+        let padded = pad_to_block(&img);
+        assert_eq!(padded.dimensions(), (8, 4));
+        assert_eq!(*padded.get_pixel(7, 3), Rgba([255, 0, 0, 255]));
+        assert_eq!(*padded.get_pixel(0, 3), Rgba([0, 0, 0, 255]));
+    }
 }
@@ -0,0 +1,129 @@
+//! mipmap 缩小
+//!
+//! 直接平均 sRGB 编码的像素值会使亮暗交界处偏暗，贴纸的描边在远处会变得发黑。
+//! 这里先转换到线性空间再缩小，最后转换回目标颜色空间。
+
+use image::{imageops, Rgba, Rgba32FImage, RgbaImage};
+
+use crate::{
+    color::{self, ColorSpace},
+    options::MipFilter,
+};
+
+/// 逐层生成 mipmap，第 0 层为原图
+pub(crate) struct MipChain {
+    /// 当前层的线性值，缩小后的各层都由它计算以避免重复量化
+    linear: Rgba32FImage,
+    color_space: ColorSpace,
+    filter: MipFilter,
+}
+
+impl MipChain {
+    /// `color_space` 为图片像素值（即目标格式）的颜色空间
+    pub(crate) fn new(image: &RgbaImage, color_space: ColorSpace, filter: MipFilter) -> Self {
+        Self {
+            linear: to_linear(image, color_space),
+            color_space,
+            filter,
+        }
+    }
+
+    /// 缩小为下一层，宽高减半且不小于 1
+    pub(crate) fn next_level(&mut self) -> RgbaImage {
+        let width = u32::max(self.linear.width() / 2, 1);
+        let height = u32::max(self.linear.height() / 2, 1);
+        self.linear = downscale(&self.linear, width, height, self.filter);
+        from_linear(&self.linear, self.color_space)
+    }
+}
+
+fn to_linear(image: &RgbaImage, color_space: ColorSpace) -> Rgba32FImage {
+    let table: [f32; 256] = std::array::from_fn(|i| match color_space {
+        ColorSpace::Srgb => color::srgb_to_linear_f32(i as f32 / 255.0),
+        ColorSpace::Linear => i as f32 / 255.0,
+    });
+    Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        Rgba([
+            table[r as usize],
+            table[g as usize],
+            table[b as usize],
+            a as f32 / 255.0,
+        ])
+    })
+}
+
+fn from_linear(image: &Rgba32FImage, color_space: ColorSpace) -> RgbaImage {
+    let encode = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        let c = match color_space {
+            ColorSpace::Srgb => color::linear_to_srgb_f32(c),
+            ColorSpace::Linear => c,
+        };
+        (c * 255.0).round() as u8
+    };
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        Rgba([
+            encode(r),
+            encode(g),
+            encode(b),
+            (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ])
+    })
+}
+
+fn downscale(image: &Rgba32FImage, width: u32, height: u32, filter: MipFilter) -> Rgba32FImage {
+    let filter = match filter {
+        MipFilter::Box => return box_downscale(image, width, height),
+        MipFilter::Triangle => imageops::FilterType::Triangle,
+        MipFilter::CatmullRom => imageops::FilterType::CatmullRom,
+        MipFilter::Lanczos3 => imageops::FilterType::Lanczos3,
+    };
+    imageops::resize(image, width, height, filter)
+}
+
+/// 每个目标像素取对应 2x2 区域的平均值，奇数尺寸时舍弃最后一行或一列
+fn box_downscale(image: &Rgba32FImage, width: u32, height: u32) -> Rgba32FImage {
+    let (src_width, src_height) = image.dimensions();
+    Rgba32FImage::from_fn(width, height, |x, y| {
+        let xs = (x * 2)..u32::min(x * 2 + 2, src_width);
+        let ys = (y * 2)..u32::min(y * 2 + 2, src_height);
+        let mut sum = [0f32; 4];
+        let mut count = 0.0;
+        for sy in ys {
+            for sx in xs.clone() {
+                for (total, value) in sum.iter_mut().zip(image.get_pixel(sx, sy).0) {
+                    *total += value;
+                }
+                count += 1.0;
+            }
+        }
+        Rgba(sum.map(|total| total / count))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 黑白相间的 sRGB 图片缩小后应为线性 50% 灰，而不是 sRGB 的 128
+    #[test]
+    fn test_downscale_in_linear_light() {
+        let image = RgbaImage::from_fn(2, 2, |x, _| {
+            let value = if x == 0 { 0 } else { 255 };
+            Rgba([value, value, value, 255])
+        });
+
+        let mut chain = MipChain::new(&image, ColorSpace::Srgb, MipFilter::Box);
+        let level = chain.next_level();
+        assert_eq!(level.dimensions(), (1, 1));
+        assert_eq!(*level.get_pixel(0, 0), Rgba([188, 188, 188, 255]));
+
+        let mut chain = MipChain::new(&image, ColorSpace::Linear, MipFilter::Box);
+        assert_eq!(
+            *chain.next_level().get_pixel(0, 0),
+            Rgba([128, 128, 128, 255])
+        );
+    }
+}
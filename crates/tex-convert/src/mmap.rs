@@ -0,0 +1,106 @@
+use std::{fs::File, io::Cursor, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    error::Result,
+    spec::{TexHeader, TexInfo},
+};
+
+/// 内存映射的输入文件
+///
+/// 解析时直接读取映射的内存，不会将整个文件复制到 `Vec` 中。
+///
+/// 映射期间文件必须保持不变：被截断时访问截掉的部分会触发 SIGBUS（Windows 上为访问冲突），
+/// 被其他进程修改则违反了 `&[u8]` 不可变的前提，属于未定义行为。
+/// 只应对不会被同时写入的文件使用，例如游戏目录或工作区中的原版 tex 文件，
+/// 无法保证时请读取到内存中再解析。
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: 映射在 MappedFile 存在期间有效，要求这段时间内文件不被截断或修改，
+        // 否则会触发 SIGBUS 或读取到变化中的内存（未定义行为）。操作系统无法阻止其他进程这样做，
+        // 这一前提由调用方保证，见 MappedFile 的说明。
+        let map = unsafe { Mmap::map(&file)? };
+
+        Ok(Self { map })
+    }
+
+    /// 用于各解析函数的 reader
+    pub fn reader(&self) -> Cursor<&[u8]> {
+        Cursor::new(&self.map)
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl TexInfo {
+    /// 以内存映射方式读取文件头
+    pub fn from_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        TexInfo::from_reader(&mut MappedFile::open(path)?.reader())
+    }
+}
+
+impl TexHeader {
+    /// 以内存映射方式读取完整文件头
+    pub fn from_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        TexHeader::from_reader(&mut MappedFile::open(path)?.reader())
+    }
+}
+
+#[cfg(feature = "tex2dds")]
+/// 以内存映射方式读取 tex 图片，见 [crate::load_tex_image]
+pub fn load_tex_image<P: AsRef<Path>>(path: P) -> Result<image::RgbaImage> {
+    crate::load_tex_image(&mut MappedFile::open(path)?.reader())
+}
+
+#[cfg(feature = "tex2dds")]
+/// 以内存映射方式将 tex 转换为 dds
+pub fn convert_tex_to_dds<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    crate::tex2dds::convert_to_dds(&mut MappedFile::open(path)?.reader())
+}
+
+#[cfg(feature = "dds2tex")]
+/// 以内存映射方式将 dds 转换为 tex
+pub fn convert_dds_to_tex<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    crate::dds2tex::convert_to_tex(&mut MappedFile::open(path)?.reader())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::TexFormat;
+
+    #[test]
+    fn test_tex_info_from_mmap() {
+        let mut data = vec![];
+        TexInfo::builder()
+            .width(4)
+            .height(4)
+            .format(TexFormat::DxgiFormatBc1Unorm)
+            .write_to(&mut data)
+            .unwrap();
+        data.resize(data.len() + 8, 0);
+
+        let dir = std::env::temp_dir().join(format!("tex-convert-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.tex");
+        std::fs::write(&path, &data).unwrap();
+
+        let info = TexInfo::from_mmap(&path).unwrap();
+        assert_eq!((info.width, info.height), (4, 4));
+        assert_eq!(&*MappedFile::open(&path).unwrap(), &data[..]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+use image::RgbaImage;
+
+use crate::{color::ColorSpace, dither::Dither, spec::TexFormat};
+
+/// 编码质量，越高越慢
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quality {
+    Fast,
+    Normal,
+    Slow,
+}
+
+impl From<Quality> for image_dds::Quality {
+    fn from(value: Quality) -> Self {
+        match value {
+            Quality::Fast => image_dds::Quality::Fast,
+            Quality::Normal => image_dds::Quality::Normal,
+            Quality::Slow => image_dds::Quality::Slow,
+        }
+    }
+}
+
+/// 生成 mipmap 时的缩小滤波器
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MipFilter {
+    /// 2x2 平均
+    Box,
+    #[default]
+    Triangle,
+    CatmullRom,
+    /// 最锐利，边缘可能出现振铃
+    Lanczos3,
+}
+
+/// 预设的编码参数组合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// 近乎即时的预览，[Quality::Fast] 且不生成 mipmap
+    Preview,
+    /// [Quality::Normal]，不改变 mipmap 设置
+    Balanced,
+    /// 打包发布用，[Quality::Slow]，不改变 mipmap 设置
+    Release,
+}
+
+/// 图片编码的目标格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetFormat {
+    /// 4bpp，体积最小，只有 1 bit 透明度
+    Bc1,
+    /// 单通道，适用于遮罩
+    Bc4,
+    /// 双通道，适用于法线贴图
+    Bc5,
+    Bc7,
+    /// 未压缩的双通道格式
+    R8G8,
+    /// 未压缩的四通道格式
+    Rgba8,
+    /// 根据图片内容自动选择，见 [crate::auto::select_format]
+    ///
+    /// 编码时解析为具体格式；直接查询 [TargetFormat::image_format] 等时按 BC7 处理。
+    Auto,
+}
+
+impl TargetFormat {
+    /// 对应的 [image_dds::ImageFormat]
+    ///
+    /// image_dds 不支持的格式返回 `None`，由本库自行编码。
+    pub fn image_format(&self, srgb: bool) -> Option<image_dds::ImageFormat> {
+        use image_dds::ImageFormat;
+
+        match (self, srgb) {
+            (TargetFormat::Bc1, false) => Some(ImageFormat::BC1RgbaUnorm),
+            (TargetFormat::Bc1, true) => Some(ImageFormat::BC1RgbaUnormSrgb),
+            (TargetFormat::Bc4, _) => Some(ImageFormat::BC4RUnorm),
+            (TargetFormat::Bc5, _) => Some(ImageFormat::BC5RgUnorm),
+            (TargetFormat::Bc7 | TargetFormat::Auto, false) => Some(ImageFormat::BC7RgbaUnorm),
+            (TargetFormat::Bc7 | TargetFormat::Auto, true) => Some(ImageFormat::BC7RgbaUnormSrgb),
+            (TargetFormat::R8G8, _) => None,
+            (TargetFormat::Rgba8, false) => Some(ImageFormat::Rgba8Unorm),
+            (TargetFormat::Rgba8, true) => Some(ImageFormat::Rgba8UnormSrgb),
+        }
+    }
+
+    /// 是否为以 4x4 为单位编码的块压缩格式
+    pub(crate) fn is_block_compressed(&self) -> bool {
+        !matches!(self, TargetFormat::R8G8 | TargetFormat::Rgba8)
+    }
+
+    /// 对应的 [TexFormat]
+    pub fn tex_format(&self, srgb: bool) -> TexFormat {
+        match (self, srgb) {
+            (TargetFormat::Bc1, false) => TexFormat::DxgiFormatBc1Unorm,
+            (TargetFormat::Bc1, true) => TexFormat::DxgiFormatBc1UnormSRGB,
+            (TargetFormat::Bc4, _) => TexFormat::DxgiFormatBc4Unorm,
+            (TargetFormat::Bc5, _) => TexFormat::DxgiFormatBc5Unorm,
+            (TargetFormat::Bc7 | TargetFormat::Auto, false) => TexFormat::DxgiFormatBc7Unorm,
+            (TargetFormat::Bc7 | TargetFormat::Auto, true) => TexFormat::DxgiFormatBc7UnormSRGB,
+            (TargetFormat::R8G8, _) => TexFormat::DxgiFormatR8G8Unorm,
+            (TargetFormat::Rgba8, false) => TexFormat::DxgiFormatR8G8B8A8Unorm,
+            (TargetFormat::Rgba8, true) => TexFormat::DxgiFormatR8G8B8A8UnormSRGB,
+        }
+    }
+
+    /// 能够编码为该 [TexFormat] 的目标格式，不支持编码的格式返回 `None`
+    pub fn from_tex_format(format: TexFormat) -> Option<Self> {
+        match format {
+            TexFormat::DxgiFormatR8G8B8A8Unorm | TexFormat::DxgiFormatR8G8B8A8UnormSRGB => {
+                Some(TargetFormat::Rgba8)
+            }
+            TexFormat::DxgiFormatR8G8Unorm => Some(TargetFormat::R8G8),
+            TexFormat::DxgiFormatBc1Unorm | TexFormat::DxgiFormatBc1UnormSRGB => {
+                Some(TargetFormat::Bc1)
+            }
+            TexFormat::DxgiFormatBc4Unorm => Some(TargetFormat::Bc4),
+            TexFormat::DxgiFormatBc5Unorm => Some(TargetFormat::Bc5),
+            TexFormat::DxgiFormatBc7Unorm | TexFormat::DxgiFormatBc7UnormSRGB => {
+                Some(TargetFormat::Bc7)
+            }
+            TexFormat::DxgiFormatUnknown | TexFormat::DxgiFormatBc6hUf16 => None,
+        }
+    }
+}
+
+/// 编码前是否预乘透明度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PremultiplyAlpha {
+    #[default]
+    Off,
+    On,
+    /// 根据 [crate::alpha::has_soft_edges] 判断
+    Auto,
+}
+
+/// 图片转换参数
+///
+/// 默认值与旧版行为一致：BC7 sRGB，[Quality::Slow]，不生成 mipmap。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConvertOptions {
+    pub format: TargetFormat,
+    pub quality: Quality,
+    pub mipmaps: bool,
+    /// 生成 mipmap 的滤波器，sRGB 格式总是在线性空间中缩小
+    pub mip_filter: MipFilter,
+    pub srgb: bool,
+    /// 输入图片的颜色空间
+    ///
+    /// 与目标格式的颜色空间不同时，编码前会先转换像素值。
+    /// 为 `None` 时视为与目标格式一致，不做转换。
+    pub input_color_space: Option<ColorSpace>,
+    pub premultiply_alpha: PremultiplyAlpha,
+    /// 编码前的抖动，用于减少低精度格式的色带
+    pub dither: Dither,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            format: TargetFormat::Bc7,
+            quality: Quality::Slow,
+            mipmaps: false,
+            mip_filter: MipFilter::Triangle,
+            srgb: true,
+            input_color_space: None,
+            premultiply_alpha: PremultiplyAlpha::Off,
+            dither: Dither::None,
+        }
+    }
+}
+
+impl ConvertOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn format(mut self, format: TargetFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn quality(mut self, quality: Quality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// 按预设设置编码质量和 mipmap
+    pub fn preset(self, preset: Preset) -> Self {
+        match preset {
+            Preset::Preview => self.quality(Quality::Fast).mipmaps(false),
+            Preset::Balanced => self.quality(Quality::Normal),
+            Preset::Release => self.quality(Quality::Slow),
+        }
+    }
+
+    /// 是否生成完整的 mipmap 链
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn mip_filter(mut self, mip_filter: MipFilter) -> Self {
+        self.mip_filter = mip_filter;
+        self
+    }
+
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// 按颜色空间选择格式，通常传入原始 tex 的 [crate::spec::TexInfo::color_space]
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.srgb = color_space == ColorSpace::Srgb;
+        self
+    }
+
+    /// 沿用原始 tex 的格式和颜色空间，重新编码后文件大小和 sRGB 行为保持不变
+    ///
+    /// 无法编码为原始格式时只沿用颜色空间。
+    pub fn same_format_as(self, format: TexFormat) -> Self {
+        let options = self.color_space(format.color_space());
+        match TargetFormat::from_tex_format(format) {
+            Some(target) => options.format(target),
+            None => options,
+        }
+    }
+
+    pub fn input_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.input_color_space = Some(color_space);
+        self
+    }
+
+    pub fn premultiply_alpha(mut self, premultiply_alpha: PremultiplyAlpha) -> Self {
+        self.premultiply_alpha = premultiply_alpha;
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// [TargetFormat::Auto] 时按图片内容确定格式，其他格式原样返回
+    pub fn resolve_format(&self, image: &RgbaImage) -> Self {
+        match self.format {
+            TargetFormat::Auto => self.format(crate::auto::select_format(image).format),
+            _ => *self,
+        }
+    }
+
+    /// 对该图片是否需要预乘透明度
+    pub fn should_premultiply(&self, image: &RgbaImage) -> bool {
+        match self.premultiply_alpha {
+            PremultiplyAlpha::Off => false,
+            PremultiplyAlpha::On => true,
+            PremultiplyAlpha::Auto => crate::alpha::has_soft_edges(image),
+        }
+    }
+
+    /// 编码结果实际使用的颜色空间，不支持 sRGB 的格式总是线性的
+    pub fn target_color_space(&self) -> ColorSpace {
+        self.format.tex_format(self.srgb).color_space()
+    }
+
+    pub(crate) fn image_dds_mipmaps(&self) -> image_dds::Mipmaps {
+        if self.mipmaps {
+            image_dds::Mipmaps::GeneratedAutomatic
+        } else {
+            image_dds::Mipmaps::Disabled
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_legacy() {
+        let options = ConvertOptions::default();
+        assert_eq!(
+            options.format.image_format(options.srgb),
+            Some(image_dds::ImageFormat::BC7RgbaUnormSrgb)
+        );
+        assert_eq!(options.quality, Quality::Slow);
+        assert!(!options.mipmaps);
+    }
+
+    #[test]
+    fn test_builder() {
+        let options = ConvertOptions::new()
+            .format(TargetFormat::Bc1)
+            .quality(Quality::Fast)
+            .mipmaps(true)
+            .srgb(false);
+        assert_eq!(
+            options.format.tex_format(options.srgb),
+            TexFormat::DxgiFormatBc1Unorm
+        );
+        assert!(matches!(
+            options.image_dds_mipmaps(),
+            image_dds::Mipmaps::GeneratedAutomatic
+        ));
+    }
+
+    #[test]
+    fn test_target_color_space() {
+        let options = ConvertOptions::new().color_space(ColorSpace::Linear);
+        assert_eq!(options.target_color_space(), ColorSpace::Linear);
+        // BC4 没有 sRGB 变体
+        let options = ConvertOptions::new().format(TargetFormat::Bc4);
+        assert_eq!(options.target_color_space(), ColorSpace::Linear);
+        assert_eq!(
+            ConvertOptions::default().target_color_space(),
+            ColorSpace::Srgb
+        );
+    }
+
+    #[test]
+    fn test_preset() {
+        let options = ConvertOptions::new().mipmaps(true).preset(Preset::Preview);
+        assert_eq!(options.quality, Quality::Fast);
+        assert!(!options.mipmaps);
+
+        let options = ConvertOptions::new().mipmaps(true).preset(Preset::Release);
+        assert_eq!(options.quality, Quality::Slow);
+        assert!(options.mipmaps);
+    }
+
+    #[test]
+    fn test_same_format_as() {
+        for format in [
+            TexFormat::DxgiFormatR8G8B8A8UnormSRGB,
+            TexFormat::DxgiFormatBc1Unorm,
+            TexFormat::DxgiFormatBc1UnormSRGB,
+            TexFormat::DxgiFormatBc4Unorm,
+            TexFormat::DxgiFormatBc7Unorm,
+        ] {
+            let options = ConvertOptions::new().same_format_as(format);
+            assert_eq!(options.format.tex_format(options.srgb), format);
+        }
+
+        // BC6H 无法编码，保持默认格式
+        let options = ConvertOptions::new().same_format_as(TexFormat::DxgiFormatBc6hUf16);
+        assert_eq!(options.format, TargetFormat::Bc7);
+    }
+}
@@ -0,0 +1,91 @@
+use image::{imageops, RgbaImage};
+use image_dds::ddsfile::Dds;
+use rayon::prelude::*;
+
+use crate::{
+    error::{Error, Result},
+    options::ConvertOptions,
+};
+
+/// 每个并行任务编码的行数，必须是 4 的倍数
+const STRIP_HEIGHT: u32 = 64;
+
+/// 图片高度不足两个条带时单线程编码即可
+pub(crate) fn should_split(image: &RgbaImage) -> bool {
+    image.height() > STRIP_HEIGHT
+}
+
+/// 按块行将图片切分为条带，在 rayon 线程池中分别编码后拼接
+///
+/// 块压缩数据按块行顺序存放，各条带的编码结果依次拼接即为整张图片的数据。
+pub(crate) fn encode_strips(
+    image: &RgbaImage,
+    format: image_dds::ImageFormat,
+    options: &ConvertOptions,
+) -> Result<Dds> {
+    let mut dxgi_format = None;
+    let (mip_map_count, data) = crate::encode_mip_chain(image, options, |level| {
+        let surfaces = split_strips(&crate::pad_to_block(level))
+            .par_iter()
+            .map(|strip| {
+                image_dds::dds_from_image(
+                    strip,
+                    format,
+                    options.quality.into(),
+                    image_dds::Mipmaps::Disabled,
+                )
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        dxgi_format = surfaces
+            .first()
+            .and_then(|surface| surface.get_dxgi_format());
+
+        Ok(surfaces
+            .into_iter()
+            .flat_map(|surface| surface.data)
+            .collect())
+    })?;
+
+    crate::new_dds_2d(
+        image.dimensions(),
+        dxgi_format.ok_or(Error::UnknownTexFormat)?,
+        mip_map_count,
+        data,
+    )
+}
+
+fn split_strips(image: &RgbaImage) -> Vec<RgbaImage> {
+    (0..image.height())
+        .step_by(STRIP_HEIGHT as usize)
+        .map(|y| {
+            let height = STRIP_HEIGHT.min(image.height() - y);
+            imageops::crop_imm(image, 0, y, image.width(), height).to_image()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_split_strips() {
+        let image = RgbaImage::from_fn(8, 150, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        let strips = split_strips(&image);
+        assert_eq!(
+            strips
+                .iter()
+                .map(|strip| strip.height())
+                .collect::<Vec<_>>(),
+            vec![64, 64, 22]
+        );
+
+        let stitched: Vec<u8> = strips
+            .iter()
+            .flat_map(|strip| strip.as_raw().clone())
+            .collect();
+        assert_eq!(&stitched, image.as_raw());
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use image_dds::ddsfile::{
+    AlphaMode, D3D10ResourceDimension, DataFormat, Dds, DxgiFormat, NewDxgiParams,
+};
+use num_traits::FromPrimitive;
+
+use crate::{
+    error::{Error, Result},
+    spec::{self, ReTexHeader},
+};
+
+/// 将 RE Engine (MHRise 等) 的 tex 文件转换为 dds
+pub fn convert_to_dds<R>(reader: &mut R) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let header = ReTexHeader::from_reader(reader)?;
+    let format = DxgiFormat::from_u32(header.format).ok_or(Error::InvalidField {
+        offset: ReTexHeader::FORMAT_OFFSET,
+        field: "RE tex format",
+        value: header.format as i64,
+    })?;
+
+    let is_volume = header.depth > 1;
+    let mut dds = Dds::new_dxgi(NewDxgiParams {
+        height: header.height as u32,
+        width: header.width as u32,
+        depth: is_volume.then_some(header.depth as u32),
+        format,
+        mipmap_levels: Some(header.mip_count as u32),
+        array_layers: (header.image_count > 1).then_some(header.image_count as u32),
+        caps2: None,
+        is_cubemap: header.cubemap_marker != 0,
+        resource_dimension: if is_volume {
+            D3D10ResourceDimension::Texture3D
+        } else {
+            D3D10ResourceDimension::Texture2D
+        },
+        alpha_mode: AlphaMode::Unknown,
+    })?;
+
+    let mut data = vec![];
+    for (index, mip) in header.mips.iter().enumerate() {
+        let level = index % header.mip_count as usize;
+        let width = u32::max(header.width as u32 >> level, 1);
+
+        // 版本号不是 MHW 的 tex 都会交给这里，文件头中的偏移和大小不可信
+        spec::ensure_in_stream(reader, mip.offset, mip.size as u64)?;
+        reader.seek(SeekFrom::Start(mip.offset))?;
+        let mut mip_data = vec![0u8; mip.size as usize];
+        reader.read_exact(&mut mip_data)?;
+
+        // 部分版本的行宽按对齐要求填充过，需要去掉填充数据
+        match format.get_pitch(width) {
+            Some(pitch) if pitch < mip.pitch => {
+                for row in mip_data.chunks(mip.pitch as usize) {
+                    data.extend_from_slice(&row[..usize::min(pitch as usize, row.len())]);
+                }
+            }
+            _ => data.extend_from_slice(&mip_data),
+        }
+    }
+    dds.data = data;
+
+    let mut out_data = vec![];
+    dds.write(&mut out_data)?;
+
+    Ok(out_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::{WriteBytesExt, LE};
+
+    use super::*;
+
+    /// 写入 RGBA8 格式的 RE tex 文件头，`mips` 为各层的 (pitch, size)，数据紧跟在文件头之后
+    fn re_tex(version: u32, width: u16, height: u16, mips: &[(u32, u32)]) -> Vec<u8> {
+        let mut tex = vec![];
+        tex.extend(b"TEX\0");
+        tex.write_u32::<LE>(version).unwrap();
+        tex.write_u16::<LE>(width).unwrap();
+        tex.write_u16::<LE>(height).unwrap();
+        tex.write_u16::<LE>(1).unwrap();
+        let new_layout = version > 11;
+        if new_layout {
+            tex.extend([1, mips.len() as u8 * 16]);
+        } else {
+            tex.extend([mips.len() as u8, 1]);
+        }
+        tex.write_u32::<LE>(DxgiFormat::R8G8B8A8_UNorm as u32)
+            .unwrap();
+        tex.extend([0; 12]);
+        if version > 27 {
+            tex.extend([0; 8]);
+        }
+
+        let mut offset = (tex.len() + mips.len() * 16) as u64;
+        for &(pitch, size) in mips {
+            tex.write_u64::<LE>(offset).unwrap();
+            tex.write_u32::<LE>(pitch).unwrap();
+            tex.write_u32::<LE>(size).unwrap();
+            offset += size as u64;
+        }
+        tex
+    }
+
+    #[test]
+    fn test_convert_to_dds() {
+        // 旧版布局：4x2 和 2x1 两层，行宽没有填充
+        let pixels = (0..40).collect::<Vec<u8>>();
+        let mut tex = re_tex(11, 4, 2, &[(16, 32), (8, 8)]);
+        tex.extend(&pixels);
+        let dds = Dds::read(&mut Cursor::new(
+            convert_to_dds(&mut Cursor::new(tex)).unwrap(),
+        ))
+        .unwrap();
+        assert_eq!((dds.get_width(), dds.get_height()), (4, 2));
+        assert_eq!(dds.get_num_mipmap_levels(), 2);
+        assert_eq!(dds.data, pixels);
+
+        // 新版布局：行宽填充到 32 字节，填充数据被去掉
+        let mut tex = re_tex(28, 4, 2, &[(32, 64)]);
+        for row in pixels[..32].chunks(16) {
+            tex.extend(row);
+            tex.extend([0xFF; 16]);
+        }
+        let dds = Dds::read(&mut Cursor::new(
+            convert_to_dds(&mut Cursor::new(tex)).unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.data, pixels[..32]);
+    }
+
+    #[test]
+    fn test_convert_to_dds_rejects_malformed() {
+        // mipmap 大小超出文件，不按该大小分配内存
+        let tex = re_tex(11, 4, 2, &[(16, u32::MAX)]);
+        assert!(matches!(
+            convert_to_dds(&mut Cursor::new(tex)),
+            Err(Error::DataSizeMismatch(..))
+        ));
+
+        let mut tex = re_tex(11, 0, 2, &[(16, 0)]);
+        assert!(matches!(
+            convert_to_dds(&mut Cursor::new(&tex)),
+            Err(Error::InvalidField { offset: 0x08, .. })
+        ));
+        tex = re_tex(28, 4, 2, &[]);
+        assert!(matches!(
+            convert_to_dds(&mut Cursor::new(&tex)),
+            Err(Error::InvalidField { offset: 0x0E, .. })
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::Write;
+
+use crate::error::Result;
+
+use super::{FormatInfo, TexDimension, TexFormat, TexHeader, TexInfo, TexPitch};
+
+/// 0x28 起的固定字段
+const TEX_FIXED_UNKN: [u8; 0x1C] = [
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const TEX_OF_NEW_DDS: &[TexFormat] = &[
+    TexFormat::DxgiFormatBc7Unorm,
+    TexFormat::DxgiFormatBc7UnormSRGB,
+    TexFormat::DxgiFormatBc6hUf16,
+];
+
+/// tex 文件头构建器
+///
+/// 根据尺寸、格式和 mipmap 数量生成完整的 [TexHeader]，包括 mipmap 偏移表。
+#[derive(Debug, Clone)]
+pub struct TexInfoBuilder {
+    width: i32,
+    height: i32,
+    format: TexFormat,
+    mip_map_count: i32,
+    image_count: i32,
+    depth: i32,
+    dimension: TexDimension,
+    reference: Option<TexHeader>,
+}
+
+impl Default for TexInfoBuilder {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            format: TexFormat::DxgiFormatBc7UnormSRGB,
+            mip_map_count: 1,
+            image_count: 1,
+            depth: 1,
+            dimension: TexDimension::Texture2D,
+            reference: None,
+        }
+    }
+}
+
+impl TexInfo {
+    pub fn builder() -> TexInfoBuilder {
+        TexInfoBuilder::default()
+    }
+}
+
+impl TexInfoBuilder {
+    pub fn width(mut self, width: i32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: i32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn format(mut self, format: TexFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// mipmap 数量，至少为 1
+    pub fn mipmaps(mut self, mip_map_count: i32) -> Self {
+        self.mip_map_count = mip_map_count.max(1);
+        self
+    }
+
+    /// 图片数量，数组纹理为数组长度，立方体贴图为面数
+    pub fn image_count(mut self, image_count: i32) -> Self {
+        self.image_count = image_count.max(1);
+        self
+    }
+
+    /// 体积纹理的深度，同时将纹理类型设为 [TexDimension::Texture3D]
+    pub fn depth(mut self, depth: i32) -> Self {
+        self.depth = depth.max(1);
+        self.dimension = TexDimension::Texture3D;
+        self
+    }
+
+    pub fn dimension(mut self, dimension: TexDimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// 从原始 tex 的文件头复制未知字段，而不是写入固定值
+    ///
+    /// 重建的文件除像素数据和尺寸、格式等已知字段外与原始文件完全相同。
+    pub fn unknown_fields_from(mut self, reference: &TexHeader) -> Self {
+        self.reference = Some(reference.clone());
+        self
+    }
+
+    pub fn build(&self) -> TexHeader {
+        // 块压缩格式的行宽按块对齐
+        let pitch = if self.format.is_block_compressed() {
+            (self.width.max(1) as u32).div_ceil(self.format.block_size()) as i32 * 2
+        } else {
+            self.width
+        };
+
+        // 体积纹理的深度记录在图片数量字段中
+        let (image_count, depth) = if self.dimension == TexDimension::Texture3D {
+            (1, self.depth)
+        } else {
+            (self.image_count, 1)
+        };
+
+        // 数据紧跟在文件头之后，每张图片依次存放所有 mipmap
+        let offset_count = (self.mip_map_count * image_count) as u64;
+        let mut offset = TexHeader::FIXED_SIZE + offset_count * 8;
+        let mut mip_offsets = Vec::with_capacity(offset_count as usize);
+        for _ in 0..image_count {
+            for level in 0..self.mip_map_count {
+                mip_offsets.push(offset);
+                offset += self.format.volume_size(
+                    self.width >> level,
+                    self.height >> level,
+                    depth >> level,
+                    1,
+                ) as u64;
+            }
+        }
+
+        let mut header = TexHeader {
+            version: 0x10,
+            unk_08: [0; 8],
+            dimension: self.dimension as i32,
+            mip_map_count: self.mip_map_count,
+            width: self.width,
+            height: self.height,
+            image_count: if self.dimension == TexDimension::Texture3D {
+                depth
+            } else {
+                image_count
+            },
+            format: self.format,
+            unk_28: TEX_FIXED_UNKN,
+            new_dds_flag: TEX_OF_NEW_DDS.contains(&self.format) as i32,
+            unk_48: [0; 0x10],
+            unk_58: [0xFF; 0x20],
+            row_width: self.width,
+            pitches: [TexPitch {
+                pitch: pitch as i16,
+                width: self.width as i16,
+                unk: [0; 8],
+            }; 3],
+            unk_a0: [0; 0x18],
+            mip_offsets,
+        };
+        if let Some(reference) = &self.reference {
+            header.unk_08 = reference.unk_08;
+            header.unk_28 = reference.unk_28;
+            header.unk_48 = reference.unk_48;
+            header.unk_58 = reference.unk_58;
+            header.unk_a0 = reference.unk_a0;
+            for (pitch, reference) in header.pitches.iter_mut().zip(&reference.pitches) {
+                pitch.unk = reference.unk;
+            }
+        }
+
+        header
+    }
+
+    /// 构建并写入文件头
+    pub fn write_to<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        self.build().write_to(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_bc7_header() {
+        let header = TexInfo::builder()
+            .width(128)
+            .height(512)
+            .format(TexFormat::DxgiFormatBc7UnormSRGB)
+            .mipmaps(3)
+            .build();
+
+        let mut data = vec![];
+        header.write_to(&mut data).unwrap();
+        assert_eq!(&data[..4], b"TEX\0");
+        assert_eq!(data.len(), 0xB8 + 3 * 8);
+        assert_eq!(header.new_dds_flag, 1);
+        assert_eq!(header.pitches[0].pitch, 64);
+
+        let base = 0xB8 + 3 * 8;
+        assert_eq!(
+            header.mip_offsets,
+            vec![base, base + 128 * 512, base + 128 * 512 + 64 * 256]
+        );
+
+        let parsed = TexHeader::from_reader(&mut &data[..]).unwrap();
+        assert_eq!(parsed, header);
+    }
+
+    #[test]
+    fn test_unknown_fields_from_reference() {
+        let builder = TexInfo::builder()
+            .width(128)
+            .height(512)
+            .format(TexFormat::DxgiFormatBc7UnormSRGB);
+        let mut reference = builder.build();
+        reference.unk_08 = [0x11; 8];
+        reference.unk_28 = [0x22; 0x1C];
+        reference.unk_a0 = [0x33; 0x18];
+        reference.pitches[1].unk = [0x44; 8];
+
+        let mut original = vec![];
+        reference.write_to(&mut original).unwrap();
+        let mut rebuilt = vec![];
+        builder
+            .unknown_fields_from(&reference)
+            .write_to(&mut rebuilt)
+            .unwrap();
+        assert_eq!(rebuilt, original);
+    }
+}
@@ -0,0 +1,87 @@
+use super::TexFormat;
+
+/// 像素格式的存储布局，行宽、mipmap 大小和偏移都由此计算
+pub trait FormatInfo {
+    /// 每像素位数，块压缩格式按整块平均
+    fn bits_per_pixel(&self) -> u32;
+
+    /// 压缩块的边长（像素），未压缩格式为 1
+    fn block_size(&self) -> u32;
+
+    /// mipmap 实际占用的最小边长，块压缩格式不足一块时按一块存放
+    fn min_mip_dimension(&self) -> u32 {
+        self.block_size()
+    }
+
+    fn is_block_compressed(&self) -> bool {
+        self.block_size() > 1
+    }
+
+    /// 每块的字节数，未压缩格式即每像素字节数
+    fn block_bytes(&self) -> usize {
+        let block_size = self.block_size() as usize;
+        self.bits_per_pixel() as usize * block_size * block_size / 8
+    }
+
+    /// 一行块的字节数
+    fn row_pitch(&self, width: u32) -> usize {
+        let width = width.max(self.min_mip_dimension());
+        width.div_ceil(self.block_size()) as usize * self.block_bytes()
+    }
+
+    /// 单层表面的字节数
+    fn surface_bytes(&self, width: u32, height: u32) -> usize {
+        let height = height.max(self.min_mip_dimension());
+        self.row_pitch(width) * height.div_ceil(self.block_size()) as usize
+    }
+}
+
+impl FormatInfo for TexFormat {
+    fn bits_per_pixel(&self) -> u32 {
+        match self {
+            TexFormat::DxgiFormatUnknown => 0,
+            TexFormat::DxgiFormatR8G8B8A8Unorm | TexFormat::DxgiFormatR8G8B8A8UnormSRGB => 32,
+            TexFormat::DxgiFormatR8G8Unorm => 16,
+            TexFormat::DxgiFormatBc1Unorm
+            | TexFormat::DxgiFormatBc1UnormSRGB
+            | TexFormat::DxgiFormatBc4Unorm => 4,
+            TexFormat::DxgiFormatBc5Unorm
+            | TexFormat::DxgiFormatBc6hUf16
+            | TexFormat::DxgiFormatBc7Unorm
+            | TexFormat::DxgiFormatBc7UnormSRGB => 8,
+        }
+    }
+
+    fn block_size(&self) -> u32 {
+        match self {
+            TexFormat::DxgiFormatR8G8B8A8Unorm
+            | TexFormat::DxgiFormatR8G8B8A8UnormSRGB
+            | TexFormat::DxgiFormatR8G8Unorm => 1,
+            _ => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_surface_bytes() {
+        let format = TexFormat::DxgiFormatBc7UnormSRGB;
+        assert_eq!(format.block_bytes(), 16);
+        assert_eq!(format.row_pitch(128), 32 * 16);
+        assert_eq!(format.surface_bytes(128, 512), 128 * 512);
+        // 不足一块按一块计算
+        assert_eq!(format.surface_bytes(1, 2), 16);
+
+        let format = TexFormat::DxgiFormatBc4Unorm;
+        assert_eq!(format.surface_bytes(6, 6), 4 * 8);
+
+        let format = TexFormat::DxgiFormatR8G8Unorm;
+        assert_eq!(format.row_pitch(3), 6);
+        assert_eq!(format.surface_bytes(3, 5), 30);
+
+        assert_eq!(TexFormat::DxgiFormatUnknown.surface_bytes(16, 16), 0);
+    }
+}
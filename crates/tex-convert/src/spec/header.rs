@@ -0,0 +1,233 @@
+use crate::error::{Error, Result};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use num_traits::FromPrimitive;
+use std::io::{Read, Write};
+
+use super::TexFormat;
+
+/// tex 文件头中的行宽信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TexPitch {
+    pub pitch: i16,
+    pub width: i16,
+    pub unk: [u8; 8],
+}
+
+/// 完整的 tex 文件头
+///
+/// 与 [super::TexInfo] 不同，该结构保存文件头中的全部字段，
+/// 未知字段以原始字节形式保留，读取后再写回可以得到完全相同的数据。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TexHeader {
+    /// 0x04
+    pub version: i32,
+    /// 0x08
+    pub unk_08: [u8; 8],
+    /// 0x10
+    pub dimension: i32,
+    /// 0x14
+    pub mip_map_count: i32,
+    /// 0x18
+    pub width: i32,
+    /// 0x1C
+    pub height: i32,
+    /// 0x20
+    pub image_count: i32,
+    /// 0x24
+    pub format: TexFormat,
+    /// 0x28
+    pub unk_28: [u8; 0x1C],
+    /// 0x44，BC6H/BC7 格式为 1
+    pub new_dds_flag: i32,
+    /// 0x48
+    pub unk_48: [u8; 0x10],
+    /// 0x58
+    pub unk_58: [u8; 0x20],
+    /// 0x78
+    pub row_width: i32,
+    /// 0x7C
+    pub pitches: [TexPitch; 3],
+    /// 0xA0
+    pub unk_a0: [u8; 0x18],
+    /// 0xB8，每层 mipmap 数据在文件中的偏移，按 `image * mip_map_count + mip` 排列
+    pub mip_offsets: Vec<u64>,
+}
+
+impl TexHeader {
+    pub const MAGIC: i32 = 0x00584554;
+    /// 不包含 mipmap 偏移表的文件头大小
+    pub const FIXED_SIZE: u64 = 0xB8;
+
+    pub fn from_reader<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let magic = reader.read_i32::<LE>()?;
+        if magic != Self::MAGIC {
+            return Err(Error::BadMagic(Self::MAGIC, magic));
+        }
+
+        let version = reader.read_i32::<LE>()?;
+        let unk_08 = read_bytes(reader)?;
+        let dimension = reader.read_i32::<LE>()?;
+        let mip_map_count = reader.read_i32::<LE>()?;
+        let width = reader.read_i32::<LE>()?;
+        let height = reader.read_i32::<LE>()?;
+        let image_count = reader.read_i32::<LE>()?;
+        let format_code = reader.read_i32::<LE>()?;
+        let format = TexFormat::from_i32(format_code)
+            .filter(|format| *format != TexFormat::DxgiFormatUnknown)
+            .ok_or(Error::InvalidField {
+                offset: 0x24,
+                field: "tex format",
+                value: format_code as i64,
+            })?;
+        let unk_28 = read_bytes(reader)?;
+        let new_dds_flag = reader.read_i32::<LE>()?;
+        let unk_48 = read_bytes(reader)?;
+        let unk_58 = read_bytes(reader)?;
+        let row_width = reader.read_i32::<LE>()?;
+
+        let mut pitches = [TexPitch::default(); 3];
+        for pitch in &mut pitches {
+            pitch.pitch = reader.read_i16::<LE>()?;
+            pitch.width = reader.read_i16::<LE>()?;
+            pitch.unk = read_bytes(reader)?;
+        }
+        let unk_a0 = read_bytes(reader)?;
+
+        // 每张图片各有一组 mipmap 偏移
+        let mip_offset_count = mip_map_count.max(0) as u64 * image_count.max(1) as u64;
+        // 字段可能已损坏，预分配的大小不直接信任文件中的数值
+        let mut mip_offsets = Vec::with_capacity(mip_offset_count.min(1024) as usize);
+        for _ in 0..mip_offset_count {
+            mip_offsets.push(reader.read_u64::<LE>()?);
+        }
+
+        Ok(TexHeader {
+            version,
+            unk_08,
+            dimension,
+            mip_map_count,
+            width,
+            height,
+            image_count,
+            format,
+            unk_28,
+            new_dds_flag,
+            unk_48,
+            unk_58,
+            row_width,
+            pitches,
+            unk_a0,
+            mip_offsets,
+        })
+    }
+
+    pub fn write_to<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        writer.write_i32::<LE>(Self::MAGIC)?;
+        writer.write_i32::<LE>(self.version)?;
+        writer.write_all(&self.unk_08)?;
+        writer.write_i32::<LE>(self.dimension)?;
+        writer.write_i32::<LE>(self.mip_map_count)?;
+        writer.write_i32::<LE>(self.width)?;
+        writer.write_i32::<LE>(self.height)?;
+        writer.write_i32::<LE>(self.image_count)?;
+        writer.write_i32::<LE>(self.format as i32)?;
+        writer.write_all(&self.unk_28)?;
+        writer.write_i32::<LE>(self.new_dds_flag)?;
+        writer.write_all(&self.unk_48)?;
+        writer.write_all(&self.unk_58)?;
+        writer.write_i32::<LE>(self.row_width)?;
+
+        for pitch in &self.pitches {
+            writer.write_i16::<LE>(pitch.pitch)?;
+            writer.write_i16::<LE>(pitch.width)?;
+            writer.write_all(&pitch.unk)?;
+        }
+        writer.write_all(&self.unk_a0)?;
+
+        for offset in &self.mip_offsets {
+            writer.write_u64::<LE>(*offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// 文件头总大小，包含 mipmap 偏移表
+    pub fn size(&self) -> u64 {
+        Self::FIXED_SIZE + self.mip_offsets.len() as u64 * 8
+    }
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_header() -> TexHeader {
+        TexHeader {
+            version: 0x10,
+            unk_08: [0; 8],
+            dimension: 2,
+            mip_map_count: 2,
+            width: 128,
+            height: 512,
+            image_count: 1,
+            format: TexFormat::DxgiFormatBc7UnormSRGB,
+            unk_28: [0x11; 0x1C],
+            new_dds_flag: 1,
+            unk_48: [0x22; 0x10],
+            unk_58: [0xFF; 0x20],
+            row_width: 128,
+            pitches: [TexPitch {
+                pitch: 64,
+                width: 128,
+                unk: [0x33; 8],
+            }; 3],
+            unk_a0: [0x44; 0x18],
+            mip_offsets: vec![0xC8, 0xC8 + 128 * 512],
+        }
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = sample_header();
+        let mut data = vec![];
+        header.write_to(&mut data).unwrap();
+        assert_eq!(data.len() as u64, header.size());
+
+        let parsed = TexHeader::from_reader(&mut &data[..]).unwrap();
+        assert_eq!(parsed, header);
+
+        let mut rewritten = vec![];
+        parsed.write_to(&mut rewritten).unwrap();
+        assert_eq!(rewritten, data);
+    }
+
+    #[test]
+    fn test_unknown_format_reports_offset() {
+        let mut data = vec![];
+        sample_header().write_to(&mut data).unwrap();
+        data[0x24..0x28].copy_from_slice(&99i32.to_le_bytes());
+
+        let err = TexHeader::from_reader(&mut &data[..]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidField {
+                offset: 0x24,
+                field: "tex format",
+                value: 99,
+            }
+        ));
+    }
+}
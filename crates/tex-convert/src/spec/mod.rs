@@ -1,5 +1,15 @@
+mod builder;
 mod dxgi;
+mod format;
+mod header;
+#[cfg(feature = "re-tex")]
+mod re_tex;
 mod tex;
 
+pub use builder::*;
 pub use dxgi::*;
+pub use format::*;
+pub use header::*;
+#[cfg(feature = "re-tex")]
+pub use re_tex::*;
 pub use tex::*;
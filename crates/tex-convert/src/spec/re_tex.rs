@@ -0,0 +1,119 @@
+use crate::error::{Error, Result};
+
+use byteorder::{ReadBytesExt, LE};
+use std::io::{Read, Seek, SeekFrom};
+
+use super::TexHeader;
+
+/// RE Engine (MHRise 等) tex 文件中每层 mipmap 的描述
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReTexMip {
+    pub offset: u64,
+    pub pitch: u32,
+    pub size: u32,
+}
+
+/// RE Engine tex 文件头
+///
+/// 与 MHW 共用 `TEX\0` 魔数，通过版本号区分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReTexHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    pub depth: u16,
+    pub image_count: u8,
+    pub mip_count: u8,
+    /// DXGI 格式代码
+    pub format: u32,
+    pub swizzle_control: i32,
+    pub cubemap_marker: u32,
+    /// 每张图片的每层 mipmap，按 `image * mip_count + mip` 排列
+    pub mips: Vec<ReTexMip>,
+}
+
+impl ReTexHeader {
+    /// RE3 使用的特殊版本号
+    const VERSION_RE3: u32 = 190820018;
+    /// 格式字段的偏移，各版本相同
+    pub const FORMAT_OFFSET: u64 = 0x10;
+    /// 宽度和高度字段的偏移，各版本相同
+    const SIZE_OFFSET: u64 = 0x08;
+    /// mipmap 数量（旧版本）或 mipmap 描述总字节数（新版本）的偏移
+    const MIP_COUNT_OFFSET: u64 = 0x0E;
+
+    pub fn from_reader<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        reader.seek(SeekFrom::Start(0))?;
+        let magic = reader.read_i32::<LE>()?;
+        if magic != TexHeader::MAGIC {
+            return Err(Error::BadMagic(TexHeader::MAGIC, magic));
+        }
+
+        let version = reader.read_u32::<LE>()?;
+        let width = reader.read_u16::<LE>()?;
+        let height = reader.read_u16::<LE>()?;
+        let depth = reader.read_u16::<LE>()?;
+
+        let (image_count, mip_count) = if version > 11 && version != Self::VERSION_RE3 {
+            let image_count = reader.read_u8()?;
+            // 新版本存储的是 mipmap 描述的总字节数
+            let mip_header_size = reader.read_u8()?;
+            (image_count, mip_header_size / 16)
+        } else {
+            let mip_count = reader.read_u8()?;
+            let image_count = reader.read_u8()?;
+            (image_count, mip_count)
+        };
+
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidField {
+                offset: Self::SIZE_OFFSET,
+                field: "RE tex size",
+                value: if width == 0 { width } else { height } as i64,
+            });
+        }
+        if mip_count == 0 {
+            return Err(Error::InvalidField {
+                offset: Self::MIP_COUNT_OFFSET,
+                field: "RE tex mip count",
+                value: 0,
+            });
+        }
+
+        let format = reader.read_u32::<LE>()?;
+        let swizzle_control = reader.read_i32::<LE>()?;
+        let cubemap_marker = reader.read_u32::<LE>()?;
+        // unkn04, unkn05, null0
+        reader.seek(SeekFrom::Current(4))?;
+        if version > 27 && version != Self::VERSION_RE3 {
+            // swizzle 相关字段，PC 版本未使用
+            reader.seek(SeekFrom::Current(8))?;
+        }
+
+        let mip_total = image_count as usize * mip_count as usize;
+        let mut mips = Vec::with_capacity(mip_total);
+        for _ in 0..mip_total {
+            mips.push(ReTexMip {
+                offset: reader.read_u64::<LE>()?,
+                pitch: reader.read_u32::<LE>()?,
+                size: reader.read_u32::<LE>()?,
+            });
+        }
+
+        Ok(ReTexHeader {
+            version,
+            width,
+            height,
+            depth,
+            image_count,
+            mip_count,
+            format,
+            swizzle_control,
+            cubemap_marker,
+            mips,
+        })
+    }
+}
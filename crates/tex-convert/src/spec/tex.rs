@@ -5,61 +5,241 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use std::io::{Read, Seek, SeekFrom};
 
-use super::DxgiFormat;
+use crate::color::ColorSpace;
+
+use super::{DxgiFormat, FormatInfo};
 
 pub struct TexInfo {
     pub magic: i32,
+    pub version: i32,
 
+    pub dimension: TexDimension,
     pub mip_map_count: i32,
     pub width: i32,
     pub height: i32,
+    /// 图片数量，数组纹理为数组长度，立方体贴图为面数
+    pub image_count: i32,
+    /// 体积纹理的深度，其他纹理为 1
+    pub depth: i32,
 
     pub format: TexFormat,
+    /// 由格式决定，重新编码时应沿用
+    pub color_space: ColorSpace,
 
     pub offset: i64,
+    /// 所有图片所有 mipmap 的数据偏移，按 `image * mip_map_count + mip` 排列
+    pub mip_offsets: Vec<u64>,
 }
 
 impl TexInfo {
     const MAGIC: i32 = 0x00584554;
+    /// mipmap 数量上限，超出说明字段已损坏
+    pub const MAX_MIP_MAP_COUNT: i32 = 32;
+
+    // 文件头中各字段的偏移，本体与 Iceborne 相同
+    pub(crate) const DIMENSION_OFFSET: u64 = 0x10;
+    /// mipmap 数量，其后紧跟宽度和高度
+    pub(crate) const MIP_MAP_COUNT_OFFSET: u64 = 0x14;
+    pub(crate) const IMAGE_COUNT_OFFSET: u64 = 0x20;
+    pub(crate) const FORMAT_OFFSET: u64 = 0x24;
+    pub(crate) const MIP_OFFSETS_OFFSET: u64 = 0xB8;
 
+    /// 读取文件头
+    ///
+    /// 各字段会与文件长度比较，损坏或截断的文件返回错误，而不是按错误的数值分配内存。
     pub fn from_reader<R>(reader: &mut R) -> Result<Self>
     where
         R: Read + Seek,
     {
+        Self::read(reader, false)
+    }
+
+    /// 同 [TexInfo::from_reader]，但允许 mipmap 偏移超出文件，用于恢复截断的文件
+    ///
+    /// 偏移表本身仍须完整，读取数据前需自行检查各 mipmap 是否完整。
+    pub fn from_reader_truncated<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        Self::read(reader, true)
+    }
+
+    fn read<R>(reader: &mut R, allow_truncated: bool) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let start = reader.stream_position()?;
+        let stream_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
         let magic = reader.read_i32::<LE>()?;
         if magic != Self::MAGIC {
             return Err(Error::BadMagic(Self::MAGIC, magic));
         }
+        let version = reader.read_i32::<LE>()?;
+        if version != TexVariant::MHW_VERSION {
+            return Err(Error::UnsupportedTexVersion(version as u32));
+        }
+
+        reader.seek(SeekFrom::Start(Self::DIMENSION_OFFSET))?;
+        let dimension =
+            TexDimension::from_i32(reader.read_i32::<LE>()?).unwrap_or(TexDimension::Texture2D);
 
-        reader.seek(SeekFrom::Start(0x14))?;
+        reader.seek(SeekFrom::Start(Self::MIP_MAP_COUNT_OFFSET))?;
         let mip_map_count = reader.read_i32::<LE>()?;
         let width = reader.read_i32::<LE>()?;
         let height = reader.read_i32::<LE>()?;
+        if !(0..=Self::MAX_MIP_MAP_COUNT).contains(&mip_map_count) {
+            return Err(Error::InvalidField {
+                offset: Self::MIP_MAP_COUNT_OFFSET,
+                field: "mipmap count",
+                value: mip_map_count as i64,
+            });
+        }
+        for (offset, field, value) in [
+            (Self::MIP_MAP_COUNT_OFFSET + 4, "width", width),
+            (Self::MIP_MAP_COUNT_OFFSET + 8, "height", height),
+        ] {
+            if value <= 0 {
+                return Err(Error::InvalidField {
+                    offset,
+                    field,
+                    value: value as i64,
+                });
+            }
+        }
 
-        reader.seek(SeekFrom::Start(0x24))?;
+        reader.seek(SeekFrom::Start(Self::IMAGE_COUNT_OFFSET))?;
+        let image_count = reader.read_i32::<LE>()?;
+        if image_count < 0 {
+            return Err(Error::InvalidField {
+                offset: Self::IMAGE_COUNT_OFFSET,
+                field: "image count",
+                value: image_count as i64,
+            });
+        }
+        // 体积纹理只有一张图片，每层 mipmap 依次存放所有深度切片
+        let (image_count, depth) = if dimension == TexDimension::Texture3D {
+            (1, i32::max(image_count, 1))
+        } else {
+            (i32::max(image_count, 1), 1)
+        };
+
+        reader.seek(SeekFrom::Start(Self::FORMAT_OFFSET))?;
         let r#type = reader.read_i32::<LE>()?;
         let format = TexFormat::from_i32(r#type).unwrap_or(TexFormat::DxgiFormatUnknown);
         if format == TexFormat::DxgiFormatUnknown {
-            return Err(Error::UnknownTexFormat);
+            return Err(Error::InvalidField {
+                offset: Self::FORMAT_OFFSET,
+                field: "tex format",
+                value: r#type as i64,
+            });
+        }
+
+        // 偏移表必须完整位于文件内
+        let offset_count = i32::max(mip_map_count, 1) as u64 * image_count as u64;
+        let table_end = Self::MIP_OFFSETS_OFFSET + offset_count * 8;
+        if table_end > stream_len {
+            return Err(Error::DataSizeMismatch(
+                table_end as usize,
+                stream_len as usize,
+            ));
         }
 
-        reader.seek(SeekFrom::Start(0xB8))?;
-        let offset = reader.read_i64::<LE>()?;
+        reader.seek(SeekFrom::Start(Self::MIP_OFFSETS_OFFSET))?;
+        let mut mip_offsets = Vec::with_capacity(offset_count as usize);
+        for index in 0..offset_count {
+            let mip_offset = reader.read_u64::<LE>()?;
+            if mip_offset < table_end || (mip_offset >= stream_len && !allow_truncated) {
+                return Err(Error::InvalidField {
+                    offset: Self::MIP_OFFSETS_OFFSET + index * 8,
+                    field: "mip offset",
+                    value: mip_offset as i64,
+                });
+            }
+            mip_offsets.push(mip_offset);
+        }
+        let offset = mip_offsets[0] as i64;
 
         // read size unused
         // skip
 
         Ok(TexInfo {
             magic,
+            version,
+            dimension,
             mip_map_count,
             width,
             height,
+            image_count,
+            depth,
             format,
+            color_space: format.color_space(),
             offset,
+            mip_offsets,
         })
     }
 }
 
+/// 确认 `[offset, offset + size)` 位于流内，避免按损坏的字段分配内存
+#[cfg(any(feature = "tex2dds", feature = "tex2ktx2"))]
+pub(crate) fn ensure_in_stream<R: Seek>(reader: &mut R, offset: u64, size: u64) -> Result<()> {
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    let end = offset.saturating_add(size);
+    if end > stream_len {
+        return Err(Error::DataSizeMismatch(end as usize, stream_len as usize));
+    }
+
+    Ok(())
+}
+
+/// tex 纹理类型
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
+pub enum TexDimension {
+    Texture2D = 2,
+    /// 立方体贴图，每个立方体占用 6 张图片
+    Cube = 3,
+    /// 体积纹理，深度记录在图片数量字段中
+    Texture3D = 4,
+}
+
+/// tex 文件变体
+///
+/// MHW 与 RE Engine 的 tex 文件使用相同的魔数，通过版本号区分。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TexVariant {
+    /// Monster Hunter: World
+    Mhw,
+    /// RE Engine (Monster Hunter Rise 等)，附带文件版本号
+    ReEngine(u32),
+}
+
+impl TexVariant {
+    /// 本体与 Iceborne 的文件版本号
+    pub const MHW_VERSION: i32 = 0x10;
+
+    /// 根据文件头判断 tex 文件变体，读取后 reader 会回到文件开头
+    pub fn detect<R>(reader: &mut R) -> Result<Self>
+    where
+        R: Read + Seek,
+    {
+        reader.seek(SeekFrom::Start(0))?;
+        let magic = reader.read_i32::<LE>()?;
+        if magic != TexInfo::MAGIC {
+            return Err(Error::BadMagic(TexInfo::MAGIC, magic));
+        }
+        let version = reader.read_i32::<LE>()?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        if version == Self::MHW_VERSION {
+            Ok(TexVariant::Mhw)
+        } else {
+            Ok(TexVariant::ReEngine(version as u32))
+        }
+    }
+}
+
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
 pub enum TexFormat {
@@ -126,22 +306,155 @@ impl TexFormat {
     }
 }
 
+impl TexFormat {
+    pub fn color_space(&self) -> ColorSpace {
+        match self {
+            TexFormat::DxgiFormatR8G8B8A8UnormSRGB
+            | TexFormat::DxgiFormatBc1UnormSRGB
+            | TexFormat::DxgiFormatBc7UnormSRGB => ColorSpace::Srgb,
+            _ => ColorSpace::Linear,
+        }
+    }
+
+    /// 是否为块压缩格式
+    pub fn is_compressed(&self) -> bool {
+        self.is_block_compressed()
+    }
+
+    /// 单张图片单层 mipmap 的数据大小，见 [FormatInfo::surface_bytes]
+    pub fn surface_size(&self, width: i32, height: i32) -> usize {
+        self.surface_bytes(width.max(1) as u32, height.max(1) as u32)
+    }
+
+    /// 单张图片包含所有 mipmap 的数据大小
+    pub fn image_size(&self, width: i32, height: i32, mip_map_count: i32) -> usize {
+        self.volume_size(width, height, 1, mip_map_count)
+    }
+
+    /// 体积纹理包含所有 mipmap 的数据大小，每层 mipmap 的深度减半
+    pub fn volume_size(&self, width: i32, height: i32, depth: i32, mip_map_count: i32) -> usize {
+        (0..mip_map_count.max(1))
+            .map(|level| {
+                self.surface_size(width >> level, height >> level)
+                    * (depth >> level).max(1) as usize
+            })
+            .sum()
+    }
+}
+
+/// Typeless 格式按 Unorm 处理，数据布局相同。
+///
+/// tex 没有对应编码的格式（BC2/BC3、B8G8R8A8、R16 浮点等）返回
+/// [crate::error::Error::UnsupportedDxgiFormat]，只有 [DxgiFormat::Unknown] 返回
+/// [crate::error::Error::UnknownTexFormat]。
 impl TryFrom<DxgiFormat> for TexFormat {
     type Error = crate::error::Error;
 
     fn try_from(value: DxgiFormat) -> std::result::Result<Self, Self::Error> {
         match value {
-            DxgiFormat::R8G8B8A8Unorm => Ok(TexFormat::DxgiFormatR8G8B8A8Unorm),
+            DxgiFormat::R8G8B8A8Unorm | DxgiFormat::R8G8B8A8Typeless => {
+                Ok(TexFormat::DxgiFormatR8G8B8A8Unorm)
+            }
             DxgiFormat::R8G8B8A8UnormSrgb => Ok(TexFormat::DxgiFormatR8G8B8A8UnormSRGB),
-            DxgiFormat::R8G8Unorm => Ok(TexFormat::DxgiFormatR8G8Unorm),
-            DxgiFormat::Bc1Unorm => Ok(TexFormat::DxgiFormatBc1Unorm),
+            DxgiFormat::R8G8Unorm | DxgiFormat::R8G8Typeless => Ok(TexFormat::DxgiFormatR8G8Unorm),
+            DxgiFormat::Bc1Unorm | DxgiFormat::Bc1Typeless => Ok(TexFormat::DxgiFormatBc1Unorm),
             DxgiFormat::Bc1UnormSrgb => Ok(TexFormat::DxgiFormatBc1UnormSRGB),
-            DxgiFormat::Bc4Unorm => Ok(TexFormat::DxgiFormatBc4Unorm),
-            DxgiFormat::Bc5Unorm => Ok(TexFormat::DxgiFormatBc5Unorm),
-            DxgiFormat::Bc6hUf16 => Ok(TexFormat::DxgiFormatBc6hUf16),
-            DxgiFormat::Bc7Unorm => Ok(TexFormat::DxgiFormatBc7Unorm),
+            DxgiFormat::Bc4Unorm | DxgiFormat::Bc4Typeless => Ok(TexFormat::DxgiFormatBc4Unorm),
+            DxgiFormat::Bc5Unorm | DxgiFormat::Bc5Typeless => Ok(TexFormat::DxgiFormatBc5Unorm),
+            DxgiFormat::Bc6hUf16 | DxgiFormat::Bc6hTypeless => Ok(TexFormat::DxgiFormatBc6hUf16),
+            DxgiFormat::Bc7Unorm | DxgiFormat::Bc7Typeless => Ok(TexFormat::DxgiFormatBc7Unorm),
             DxgiFormat::Bc7UnormSrgb => Ok(TexFormat::DxgiFormatBc7UnormSRGB),
-            _ => Err(crate::error::Error::UnknownTexFormat),
+            DxgiFormat::Unknown => Err(crate::error::Error::UnknownTexFormat),
+            _ => Err(crate::error::Error::UnsupportedDxgiFormat(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn sample_tex() -> Vec<u8> {
+        let mut data = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc7Unorm)
+            .mipmaps(2)
+            .write_to(&mut data)
+            .unwrap();
+        data.resize(data.len() + 64 + 16, 0);
+        data
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let info = TexInfo::from_reader(&mut Cursor::new(sample_tex())).unwrap();
+        assert_eq!((info.width, info.height, info.mip_map_count), (8, 8, 2));
+        assert_eq!(info.offset, 0xB8 + 2 * 8);
+    }
+
+    #[test]
+    fn test_from_reader_rejects_malformed() {
+        let mut data = sample_tex();
+        data[0x14..0x18].copy_from_slice(&1000i32.to_le_bytes());
+        assert!(matches!(
+            TexInfo::from_reader(&mut Cursor::new(&data)),
+            Err(Error::InvalidField {
+                offset: 0x14,
+                field: "mipmap count",
+                ..
+            })
+        ));
+
+        // 图片数量导致偏移表超出文件
+        let mut data = sample_tex();
+        data[0x20..0x24].copy_from_slice(&0x7FFF_FFFFi32.to_le_bytes());
+        assert!(matches!(
+            TexInfo::from_reader(&mut Cursor::new(&data)),
+            Err(Error::DataSizeMismatch(..))
+        ));
+
+        // 偏移超出文件
+        let mut data = sample_tex();
+        data.truncate(0xB8 + 2 * 8 + 10);
+        data[0xC0..0xC8].copy_from_slice(&0x1000u64.to_le_bytes());
+        assert!(matches!(
+            TexInfo::from_reader(&mut Cursor::new(&data)),
+            Err(Error::InvalidField {
+                offset: 0xC0,
+                field: "mip offset",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_from_dxgi_format() {
+        assert_eq!(
+            TexFormat::try_from(DxgiFormat::Bc7Typeless).unwrap(),
+            TexFormat::DxgiFormatBc7Unorm
+        );
+        assert_eq!(
+            TexFormat::try_from(DxgiFormat::Bc1UnormSrgb).unwrap(),
+            TexFormat::DxgiFormatBc1UnormSRGB
+        );
+        for format in [
+            DxgiFormat::Bc2Unorm,
+            DxgiFormat::Bc3UnormSrgb,
+            DxgiFormat::B8G8R8A8Unorm,
+            DxgiFormat::R16G16B16A16Float,
+        ] {
+            assert!(matches!(
+                TexFormat::try_from(format),
+                Err(Error::UnsupportedDxgiFormat(f)) if f == format
+            ));
         }
+        assert!(matches!(
+            TexFormat::try_from(DxgiFormat::Unknown),
+            Err(Error::UnknownTexFormat)
+        ));
     }
 }
@@ -0,0 +1,148 @@
+//! 纹理数据的 swizzle / deswizzle
+//!
+//! 主机版游戏导出的 tex 通常以 Morton (Z-order) 顺序存放压缩块，
+//! 需要先转换为线性排列才能交给常规的转换流程。PC 版文件不需要处理。
+
+use crate::{
+    error::{Error, Result},
+    spec::{FormatInfo, TexFormat},
+};
+
+/// 表面按块划分后的尺寸：`(横向块数, 纵向块数, 每块字节数)`
+///
+/// 块压缩格式以 4x4 像素为一块，未压缩格式以单个像素为一块。
+pub fn surface_blocks(format: TexFormat, width: u32, height: u32) -> (u32, u32, usize) {
+    let block_size = format.block_size();
+    (
+        width.div_ceil(block_size),
+        height.div_ceil(block_size),
+        format.block_bytes(),
+    )
+}
+
+/// 将 Morton 顺序的数据转换为线性排列
+///
+/// 宽高（以块为单位）不是 2 的幂时，输入数据按补齐后的尺寸存放，输出只保留有效区域。
+pub fn deswizzle_morton(
+    data: &[u8],
+    width_blocks: u32,
+    height_blocks: u32,
+    block_bytes: usize,
+) -> Result<Vec<u8>> {
+    let (padded_width, padded_height) = padded_size(width_blocks, height_blocks);
+    let expected = padded_width as usize * padded_height as usize * block_bytes;
+    if data.len() < expected {
+        return Err(Error::DataSizeMismatch(expected, data.len()));
+    }
+
+    let mut out = Vec::with_capacity(width_blocks as usize * height_blocks as usize * block_bytes);
+    for y in 0..height_blocks {
+        for x in 0..width_blocks {
+            let src = morton_index(x, y, padded_width, padded_height) * block_bytes;
+            out.extend_from_slice(&data[src..src + block_bytes]);
+        }
+    }
+
+    Ok(out)
+}
+
+/// 将线性排列的数据转换为 Morton 顺序，[deswizzle_morton] 的逆操作
+///
+/// 输出按补齐到 2 的幂后的尺寸存放，补齐部分填充 0。
+pub fn swizzle_morton(
+    data: &[u8],
+    width_blocks: u32,
+    height_blocks: u32,
+    block_bytes: usize,
+) -> Result<Vec<u8>> {
+    let expected = width_blocks as usize * height_blocks as usize * block_bytes;
+    if data.len() < expected {
+        return Err(Error::DataSizeMismatch(expected, data.len()));
+    }
+
+    let (padded_width, padded_height) = padded_size(width_blocks, height_blocks);
+    let mut out = vec![0u8; padded_width as usize * padded_height as usize * block_bytes];
+    for y in 0..height_blocks {
+        for x in 0..width_blocks {
+            let src = (y as usize * width_blocks as usize + x as usize) * block_bytes;
+            let dst = morton_index(x, y, padded_width, padded_height) * block_bytes;
+            out[dst..dst + block_bytes].copy_from_slice(&data[src..src + block_bytes]);
+        }
+    }
+
+    Ok(out)
+}
+
+/// 按格式 deswizzle 单层表面，尺寸以像素为单位
+pub fn deswizzle_surface(
+    data: &[u8],
+    format: TexFormat,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>> {
+    let (width_blocks, height_blocks, block_bytes) = surface_blocks(format, width, height);
+    deswizzle_morton(data, width_blocks, height_blocks, block_bytes)
+}
+
+/// 按格式 swizzle 单层表面，尺寸以像素为单位
+pub fn swizzle_surface(data: &[u8], format: TexFormat, width: u32, height: u32) -> Result<Vec<u8>> {
+    let (width_blocks, height_blocks, block_bytes) = surface_blocks(format, width, height);
+    swizzle_morton(data, width_blocks, height_blocks, block_bytes)
+}
+
+fn padded_size(width: u32, height: u32) -> (u32, u32) {
+    (
+        width.max(1).next_power_of_two(),
+        height.max(1).next_power_of_two(),
+    )
+}
+
+/// 宽高均为 2 的幂，按较短边划分为若干正方形，正方形内部交错 x、y 的各个位
+fn morton_index(x: u32, y: u32, width: u32, height: u32) -> usize {
+    let bits = width.min(height).trailing_zeros();
+    let mut index = 0usize;
+    for bit in 0..bits {
+        index |= (((x >> bit) & 1) as usize) << (2 * bit);
+        index |= (((y >> bit) & 1) as usize) << (2 * bit + 1);
+    }
+    let rest = if width > height { x >> bits } else { y >> bits };
+
+    index | (rest as usize) << (2 * bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morton_index() {
+        assert_eq!(morton_index(1, 0, 4, 4), 1);
+        assert_eq!(morton_index(0, 1, 4, 4), 2);
+        assert_eq!(morton_index(2, 0, 4, 4), 4);
+        assert_eq!(morton_index(3, 3, 4, 4), 15);
+        // 8x4 由两个 4x4 正方形组成
+        assert_eq!(morton_index(4, 0, 8, 4), 16);
+    }
+
+    #[test]
+    fn test_swizzle_round_trip() {
+        let linear = (0..6 * 3 * 2).map(|i| i as u8).collect::<Vec<_>>();
+        let swizzled = swizzle_morton(&linear, 6, 3, 2).unwrap();
+        assert_eq!(swizzled.len(), 8 * 4 * 2);
+
+        let deswizzled = deswizzle_morton(&swizzled, 6, 3, 2).unwrap();
+        assert_eq!(deswizzled, linear);
+    }
+
+    #[test]
+    fn test_surface_blocks() {
+        assert_eq!(
+            surface_blocks(TexFormat::DxgiFormatBc7UnormSRGB, 128, 510),
+            (32, 128, 16)
+        );
+        assert_eq!(
+            surface_blocks(TexFormat::DxgiFormatR8G8Unorm, 3, 5),
+            (3, 5, 2)
+        );
+    }
+}
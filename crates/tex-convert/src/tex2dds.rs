@@ -1,30 +1,40 @@
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
-use byteorder::{WriteBytesExt, LE};
+use image_dds::ddsfile::{
+    AlphaMode, Caps2, D3D10ResourceDimension, Dds, FourCC, Header, Header10, PixelFormat,
+    PixelFormatFlags,
+};
+use num_traits::FromPrimitive;
 
 use crate::{
-    error::Result,
-    spec::{self, TexFormat, TexInfo},
+    error::{Error, Result},
+    options::{ConvertOptions, TargetFormat},
+    spec::{self, TexDimension, TexInfo, TexVariant},
 };
 
-const W_MAGIC_NUMBER_DDS: &[u8] = &[
-    0x44, 0x44, 0x53, 0x20, 0x7C, 0x00, 0x00, 0x00, 0x07, 0x10, 0x0A, 0x00,
-];
-const COMPRESS_OPTION: &[u8] = &[0x08, 0x10, 0x40, 0x00];
-const DX10_FIXED_FLAGS: &[u8] = &[
-    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-];
-const TEX_WITH_4BPP: &[TexFormat] = &[
-    TexFormat::DxgiFormatBc1Unorm,
-    TexFormat::DxgiFormatBc1UnormSRGB,
-    TexFormat::DxgiFormatBc4Unorm,
-];
-const TEX_WITH_16BPP: &[TexFormat] = &[TexFormat::DxgiFormatR8G8Unorm];
+/// 输出 dds 的表面布局
+struct DdsLayout {
+    width: i32,
+    height: i32,
+    mip_map_count: i32,
+    image_count: i32,
+    /// 体积纹理的深度，其他纹理为 1
+    depth: i32,
+    cubemap: bool,
+}
 
 pub fn convert_to_dds<R>(reader: &mut R) -> Result<Vec<u8>>
 where
     R: Read + Seek,
 {
+    match TexVariant::detect(reader)? {
+        TexVariant::Mhw => {}
+        #[cfg(feature = "re-tex")]
+        TexVariant::ReEngine(_) => return crate::re_tex2dds::convert_to_dds(reader),
+        #[cfg(not(feature = "re-tex"))]
+        TexVariant::ReEngine(version) => return Err(Error::UnsupportedTexVersion(version)),
+    }
+
     let info = TexInfo::from_reader(reader)?;
 
     // read data
@@ -33,54 +43,366 @@ where
     reader.read_to_end(&mut data)?;
 
     let mut out_data = Vec::new();
+    write_dds_header(
+        &mut out_data,
+        &info,
+        &DdsLayout {
+            width: info.width,
+            height: info.height,
+            mip_map_count: info.mip_map_count,
+            image_count: info.image_count,
+            depth: info.depth,
+            cubemap: info.dimension == TexDimension::Cube,
+        },
+    )?;
 
-    // dds header
-    out_data.write_all(W_MAGIC_NUMBER_DDS)?;
-    out_data.write_i32::<LE>(info.height)?;
-    out_data.write_i32::<LE>(info.width)?;
+    // write data
+    out_data.write_all(&data)?;
 
-    if TEX_WITH_4BPP.contains(&info.format) {
-        out_data.write_i32::<LE>(info.width * info.height / 2)?;
-    } else if TEX_WITH_16BPP.contains(&info.format) {
-        out_data.write_i32::<LE>(info.width * info.height * 2)?;
-    } else {
-        // 8bpp
-        out_data.write_i32::<LE>(info.width * info.height)?;
+    Ok(out_data)
+}
+
+/// 导出 dds 时 mipmap 的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MipPolicy {
+    /// 保留 tex 中的完整 mipmap 链
+    #[default]
+    KeepAll,
+    /// 只保留 mip 0，文件更小，便于编辑
+    OnlyBase,
+    /// 丢弃原有 mipmap，由 mip 0 重新生成完整的 mipmap 链，mip 0 的数据保持不变
+    ///
+    /// 体积纹理和无法编码的格式（BC6H）保留原有 mipmap。
+    Regenerate,
+}
+
+/// 同 [convert_to_dds]，按 [MipPolicy] 处理 mipmap
+///
+/// RE Engine 的 tex 总是保留原有 mipmap。
+pub fn convert_to_dds_with<R>(reader: &mut R, policy: MipPolicy) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    if policy == MipPolicy::KeepAll {
+        return convert_to_dds(reader);
+    }
+    if let TexVariant::ReEngine(_) = TexVariant::detect(reader)? {
+        return convert_to_dds(reader);
     }
 
-    out_data.write_i32::<LE>(1)?; // depth
-    out_data.write_i32::<LE>(info.mip_map_count)?;
-    out_data.write_all(&[0u8; 11 * 4])?; // reserved 11*4
+    let info = TexInfo::from_reader(reader)?;
+    let regenerate = policy == MipPolicy::Regenerate;
+    if regenerate && (info.depth > 1 || TargetFormat::from_tex_format(info.format).is_none()) {
+        return convert_to_dds(reader);
+    }
 
-    // ddspf
-    out_data.write_i32::<LE>(32)?;
-    out_data.write_i32::<LE>(4)?;
-    out_data.write_all(info.format.magic())?;
-    out_data.write_all(&[0u8; 5 * 4])?;
+    let mut data = vec![];
+    let mut mip_map_count = 1;
+    for layer in 0..info.image_count as u32 {
+        reader.seek(SeekFrom::Start(0))?;
+        let base = convert_surface_to_dds(reader, layer, 0)?;
+        let base_data = Dds::read(&mut Cursor::new(&base))?.data;
+        if regenerate {
+            let image = crate::load_dds_image(&mut Cursor::new(base))?;
+            let options = ConvertOptions::default()
+                .same_format_as(info.format)
+                .mipmaps(true);
+            let dds = crate::convert_image_to_dds(&image, &options)?;
+            let dds = Dds::read(&mut Cursor::new(dds))?;
+            mip_map_count = dds.get_num_mipmap_levels() as i32;
+            // mip 0 保留原始数据，不经过有损的重新编码，只取重新生成的其余各层
+            let levels = dds
+                .data
+                .get(base_data.len()..)
+                .ok_or(Error::DataSizeMismatch(base_data.len(), dds.data.len()))?;
+            data.extend(&base_data);
+            data.extend(levels);
+        } else {
+            data.extend(base_data);
+        }
+    }
 
-    out_data.write_all(COMPRESS_OPTION)?;
-    out_data.write_all(&[0u8; 4 * 4])?;
+    let mut out_data = Vec::new();
+    write_dds_header(
+        &mut out_data,
+        &info,
+        &DdsLayout {
+            width: info.width,
+            height: info.height,
+            mip_map_count,
+            image_count: info.image_count,
+            depth: info.depth,
+            cubemap: info.dimension == TexDimension::Cube,
+        },
+    )?;
+    out_data.write_all(&data)?;
+
+    Ok(out_data)
+}
+
+/// [salvage_to_dds] 的恢复结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// 恢复的 mipmap 层数，从 mip 0 开始连续
+    pub recovered_mips: u32,
+    /// 数据不完整而丢弃的 mipmap 层
+    pub missing_mips: Vec<u32>,
+}
 
-    // ds header dxt10
-    if info.format.magic() == b"DX10" {
-        let dds_format: spec::DxgiFormat = info.format.try_into().unwrap();
-        out_data.write_i32::<LE>(dds_format as i32)?;
-        out_data.write_all(DX10_FIXED_FLAGS)?;
+impl SalvageReport {
+    pub fn is_complete(&self) -> bool {
+        self.missing_mips.is_empty()
     }
+}
 
-    // write data
+/// 宽松地转换截断的 tex，尽可能多地恢复 mipmap
+///
+/// 从 mip 0 开始，保留所有图片数据都完整的连续 mipmap 层，其余层记入
+/// [SalvageReport::missing_mips]。连 mip 0 都不完整时返回 [Error::DataSizeMismatch]。
+pub fn salvage_to_dds<R>(reader: &mut R) -> Result<(Vec<u8>, SalvageReport)>
+where
+    R: Read + Seek,
+{
+    let info = TexInfo::from_reader_truncated(reader)?;
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    let mip_map_count = i32::max(info.mip_map_count, 1) as u32;
+    let level_size = |level: u32| {
+        info.format.volume_size(
+            info.width >> level,
+            info.height >> level,
+            info.depth >> level,
+            1,
+        ) as u64
+    };
+    let level_complete = |level: u32| {
+        (0..info.image_count as u32).all(|image| {
+            let offset = info.mip_offsets[(image * mip_map_count + level) as usize];
+            offset.saturating_add(level_size(level)) <= stream_len
+        })
+    };
+
+    let recovered_mips = (0..mip_map_count)
+        .take_while(|&level| level_complete(level))
+        .count() as u32;
+    if recovered_mips == 0 {
+        let expected = info.mip_offsets[0] + level_size(0);
+        return Err(Error::DataSizeMismatch(
+            expected as usize,
+            stream_len as usize,
+        ));
+    }
+
+    let mut data = vec![];
+    for image in 0..info.image_count as u32 {
+        for level in 0..recovered_mips {
+            let offset = info.mip_offsets[(image * mip_map_count + level) as usize];
+            let mut surface = vec![0u8; level_size(level) as usize];
+            reader.seek(SeekFrom::Start(offset))?;
+            reader.read_exact(&mut surface)?;
+            data.extend(surface);
+        }
+    }
+
+    let mut out_data = Vec::new();
+    write_dds_header(
+        &mut out_data,
+        &info,
+        &DdsLayout {
+            width: info.width,
+            height: info.height,
+            mip_map_count: recovered_mips as i32,
+            image_count: info.image_count,
+            depth: info.depth,
+            cubemap: info.dimension == TexDimension::Cube,
+        },
+    )?;
+    out_data.write_all(&data)?;
+
+    let report = SalvageReport {
+        recovered_mips,
+        missing_mips: (recovered_mips..mip_map_count).collect(),
+    };
+    Ok((out_data, report))
+}
+
+/// 同 [convert_to_dds]，直接传入文件数据
+pub fn convert_to_dds_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    convert_to_dds(&mut Cursor::new(data))
+}
+
+/// 只读取第一张图片的 mip 0 并转换为 dds
+///
+/// 读取到 mip 0 的数据后即停止，不会读取整个文件，适合批量生成缩略图。
+/// RE Engine 的 tex 仍读取整个文件。
+pub fn convert_mip0_to_dds<R>(reader: &mut R) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    match TexVariant::detect(reader)? {
+        TexVariant::Mhw => convert_surface_to_dds(reader, 0, 0),
+        TexVariant::ReEngine(_) => convert_to_dds(reader),
+    }
+}
+
+/// 将 tex 中指定图片（数组元素或立方体贴图的面）的 mip 0 转换为单张 2D dds
+pub fn convert_layer_to_dds<R>(reader: &mut R, layer: u32) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    convert_surface_to_dds(reader, layer, 0)
+}
+
+/// 将 tex 中指定图片的指定 mipmap 层转换为单张 dds
+///
+/// 体积纹理输出该层 mipmap 的全部深度切片。
+pub fn convert_surface_to_dds<R>(reader: &mut R, layer: u32, level: u32) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let info = TexInfo::from_reader(reader)?;
+    if layer >= info.image_count as u32 {
+        return Err(Error::LayerOutOfRange(layer, info.image_count as u32));
+    }
+    let mip_map_count = i32::max(info.mip_map_count, 1) as u32;
+    if level >= mip_map_count {
+        return Err(Error::MipLevelOutOfRange(level, mip_map_count));
+    }
+
+    let width = i32::max(info.width >> level, 1);
+    let height = i32::max(info.height >> level, 1);
+    let depth = i32::max(info.depth >> level, 1);
+    let offset = info.mip_offsets[(layer * mip_map_count + level) as usize];
+    let size = info.format.volume_size(width, height, depth, 1);
+    spec::ensure_in_stream(reader, offset, size as u64)?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = vec![0u8; size];
+    reader.read_exact(&mut data)?;
+
+    let mut out_data = Vec::new();
+    write_dds_header(
+        &mut out_data,
+        &info,
+        &DdsLayout {
+            width,
+            height,
+            mip_map_count: 1,
+            image_count: 1,
+            depth,
+            cubemap: false,
+        },
+    )?;
+    out_data.write_all(&data)?;
+
+    Ok(out_data)
+}
+
+/// 将体积纹理 mip 0 的指定深度切片转换为单张 2D dds
+pub fn convert_slice_to_dds<R>(reader: &mut R, slice: u32) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let info = TexInfo::from_reader(reader)?;
+    if slice >= info.depth as u32 {
+        return Err(Error::SliceOutOfRange(slice, info.depth as u32));
+    }
+
+    let size = info.format.surface_size(info.width, info.height);
+    let offset = info.mip_offsets[0] + slice as u64 * size as u64;
+    spec::ensure_in_stream(reader, offset, size as u64)?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = vec![0u8; size];
+    reader.read_exact(&mut data)?;
+
+    let mut out_data = Vec::new();
+    write_dds_header(
+        &mut out_data,
+        &info,
+        &DdsLayout {
+            width: info.width,
+            height: info.height,
+            mip_map_count: 1,
+            image_count: 1,
+            depth: 1,
+            cubemap: false,
+        },
+    )?;
     out_data.write_all(&data)?;
 
     Ok(out_data)
 }
 
+fn write_dds_header<W>(out_data: &mut W, info: &TexInfo, layout: &DdsLayout) -> Result<()>
+where
+    W: Write,
+{
+    // 数组与立方体贴图需要 DX10 扩展头记录数组长度
+    let use_dx10 = info.format.magic() == b"DX10" || layout.image_count > 1;
+
+    let dxgi_format: spec::DxgiFormat = info.format.try_into()?;
+    let dxgi_format = image_dds::ddsfile::DxgiFormat::from_u32(dxgi_format as u32)
+        .ok_or(Error::UnknownTexFormat)?;
+    let array_size = if layout.cubemap {
+        i32::max(layout.image_count / 6, 1)
+    } else {
+        layout.image_count
+    } as u32;
+
+    // 块压缩格式的数据按 4x4 块对齐，linear size 由 ddsfile 按块计算
+    let is_volume = layout.depth > 1;
+    let caps2 = if layout.cubemap {
+        Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES)
+    } else {
+        is_volume.then_some(Caps2::VOLUME)
+    };
+    let mut header = Header::new_dxgi(
+        layout.height as u32,
+        layout.width as u32,
+        Some(layout.depth as u32),
+        dxgi_format,
+        Some(layout.mip_map_count as u32),
+        Some(array_size),
+        caps2,
+    )?;
+    let header10 = if use_dx10 {
+        Some(Header10::new(
+            dxgi_format,
+            layout.cubemap,
+            if is_volume {
+                D3D10ResourceDimension::Texture3D
+            } else {
+                D3D10ResourceDimension::Texture2D
+            },
+            array_size,
+            AlphaMode::Unknown,
+        ))
+    } else {
+        // 旧格式直接使用 FourCC，不写入 DX10 扩展头
+        header.spf = PixelFormat {
+            flags: PixelFormatFlags::FOURCC,
+            fourcc: Some(FourCC(u32::from_le_bytes(*info.format.magic()))),
+            ..Default::default()
+        };
+        None
+    };
+
+    Dds {
+        header,
+        header10,
+        data: vec![],
+    }
+    .write(out_data)?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{fs::OpenOptions, io::Cursor};
+    use std::fs::OpenOptions;
 
-    use image_dds::ddsfile::Dds;
+    use image_dds::ddsfile::MiscFlag;
 
     use super::*;
+    use crate::spec::{TexFormat, TexHeader, TexPitch};
 
     const DATA: &[u8] = include_bytes!("../../../test_data/chat_stamp00_ID.tex");
 
@@ -102,4 +424,328 @@ mod tests {
         let img = image_dds::image_from_dds(&dds, 0).unwrap();
         img.save("../../test_data/chat_stamp00_ID.png").unwrap();
     }
+
+    /// 6 张 4x4 RGBA8 图片组成的立方体贴图，每张图片的像素值等于其序号
+    fn cubemap_tex() -> Vec<u8> {
+        let surface_size = 4 * 4 * 4;
+        let header = TexHeader {
+            version: 0x10,
+            unk_08: [0; 8],
+            dimension: TexDimension::Cube as i32,
+            mip_map_count: 1,
+            width: 4,
+            height: 4,
+            image_count: 6,
+            format: TexFormat::DxgiFormatR8G8B8A8Unorm,
+            unk_28: [0; 0x1C],
+            new_dds_flag: 0,
+            unk_48: [0; 0x10],
+            unk_58: [0xFF; 0x20],
+            row_width: 4,
+            pitches: [TexPitch::default(); 3],
+            unk_a0: [0; 0x18],
+            mip_offsets: (0..6).map(|i| 0xB8 + 6 * 8 + i * surface_size).collect(),
+        };
+
+        let mut data = vec![];
+        header.write_to(&mut data).unwrap();
+        for face in 0..6u8 {
+            data.extend(std::iter::repeat_n(face, surface_size as usize));
+        }
+        data
+    }
+
+    #[test]
+    fn test_convert_cubemap_to_dds() {
+        let dds = convert_to_dds(&mut Cursor::new(cubemap_tex())).unwrap();
+        assert_eq!(convert_to_dds_bytes(&cubemap_tex()).unwrap(), dds);
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+
+        let header10 = dds.header10.unwrap();
+        assert!(header10.misc_flag.contains(MiscFlag::TEXTURECUBE));
+        assert_eq!(header10.array_size, 1);
+        assert_eq!(dds.data.len(), 6 * 4 * 4 * 4);
+    }
+
+    #[test]
+    fn test_convert_layer_to_dds() {
+        let dds = convert_layer_to_dds(&mut Cursor::new(cubemap_tex()), 3).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+
+        assert_eq!(dds.get_num_array_layers(), 1);
+        assert!(dds.data.iter().all(|&b| b == 3));
+
+        let err = convert_layer_to_dds(&mut Cursor::new(cubemap_tex()), 6).unwrap_err();
+        assert!(matches!(err, Error::LayerOutOfRange(6, 6)));
+    }
+
+    /// 重构前以字节常量写入的 dds 头，用于比较
+    fn legacy_dds_header(info: &TexInfo, layout: &DdsLayout) -> Vec<u8> {
+        use byteorder::{WriteBytesExt, LE};
+
+        let use_dx10 = info.format.magic() == b"DX10" || layout.image_count > 1;
+        let mut out = vec![];
+        out.extend_from_slice(&[
+            0x44, 0x44, 0x53, 0x20, 0x7C, 0x00, 0x00, 0x00, 0x07, 0x10, 0x0A, 0x00,
+        ]);
+        out.write_i32::<LE>(layout.height).unwrap();
+        out.write_i32::<LE>(layout.width).unwrap();
+        out.write_i32::<LE>(info.format.surface_size(layout.width, layout.height) as i32)
+            .unwrap();
+        out.write_i32::<LE>(1).unwrap();
+        out.write_i32::<LE>(layout.mip_map_count).unwrap();
+        out.extend_from_slice(&[0u8; 11 * 4]);
+        out.write_i32::<LE>(32).unwrap();
+        out.write_i32::<LE>(4).unwrap();
+        out.extend_from_slice(if use_dx10 {
+            b"DX10"
+        } else {
+            info.format.magic()
+        });
+        out.extend_from_slice(&[0u8; 5 * 4]);
+        out.extend_from_slice(&[0x08, 0x10, 0x40, 0x00]);
+        out.write_i32::<LE>(if layout.cubemap { 0xFE00 } else { 0 })
+            .unwrap();
+        out.extend_from_slice(&[0u8; 3 * 4]);
+        if use_dx10 {
+            let dds_format: spec::DxgiFormat = info.format.try_into().unwrap();
+            let (misc_flag, array_size) = if layout.cubemap {
+                (0x4, i32::max(layout.image_count / 6, 1))
+            } else {
+                (0, layout.image_count)
+            };
+            for value in [dds_format as i32, 3, misc_flag, array_size, 0] {
+                out.write_i32::<LE>(value).unwrap();
+            }
+        }
+        out
+    }
+
+    fn header_pair(format: TexFormat, layout: DdsLayout) -> (Vec<u8>, Vec<u8>) {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(layout.width)
+            .height(layout.height)
+            .format(format)
+            .mipmaps(layout.mip_map_count)
+            .image_count(layout.image_count)
+            .write_to(&mut tex)
+            .unwrap();
+        tex.resize(
+            tex.len()
+                + format.image_size(layout.width, layout.height, layout.mip_map_count)
+                    * layout.image_count as usize,
+            0,
+        );
+        let info = TexInfo::from_reader(&mut Cursor::new(tex)).unwrap();
+
+        let mut header = vec![];
+        write_dds_header(&mut header, &info, &layout).unwrap();
+        (header, legacy_dds_header(&info, &layout))
+    }
+
+    #[test]
+    fn test_dds_header_matches_legacy() {
+        for format in [
+            TexFormat::DxgiFormatBc1Unorm,
+            TexFormat::DxgiFormatBc1UnormSRGB,
+            TexFormat::DxgiFormatBc4Unorm,
+            TexFormat::DxgiFormatBc5Unorm,
+            TexFormat::DxgiFormatBc6hUf16,
+            TexFormat::DxgiFormatBc7Unorm,
+            TexFormat::DxgiFormatBc7UnormSRGB,
+        ] {
+            for (width, height) in [(128, 512), (30, 18)] {
+                let (header, legacy) = header_pair(
+                    format,
+                    DdsLayout {
+                        width,
+                        height,
+                        mip_map_count: 3,
+                        image_count: 1,
+                        depth: 1,
+                        cubemap: false,
+                    },
+                );
+                assert_eq!(header, legacy, "{:?} {}x{}", format, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dds_header_single_mip() {
+        // 只有一层 mipmap 时不再设置 MIPMAPCOUNT 标志和 MIPMAP caps，其余字节不变
+        let (header, legacy) = header_pair(
+            TexFormat::DxgiFormatBc7UnormSRGB,
+            DdsLayout {
+                width: 128,
+                height: 512,
+                mip_map_count: 1,
+                image_count: 1,
+                depth: 1,
+                cubemap: false,
+            },
+        );
+        assert_eq!(header.len(), legacy.len());
+        assert_eq!(&header[0xC..0x6C], &legacy[0xC..0x6C]);
+        assert_eq!(&header[0x70..], &legacy[0x70..]);
+
+        let dds = Dds::read(&mut Cursor::new(&header)).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+    }
+
+    #[test]
+    fn test_convert_mip0_to_dds() {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc7Unorm)
+            .mipmaps(2)
+            .write_to(&mut tex)
+            .unwrap();
+        tex.extend(std::iter::repeat_n(1u8, 64));
+        tex.extend(std::iter::repeat_n(2u8, 16));
+
+        let dds = convert_mip0_to_dds(&mut Cursor::new(tex)).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.data, vec![1u8; 64]);
+    }
+
+    #[test]
+    fn test_convert_to_dds_only_base() {
+        let dds =
+            convert_to_dds_with(&mut Cursor::new(cubemap_tex()), MipPolicy::OnlyBase).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.data.len(), 6 * 4 * 4 * 4);
+
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc1Unorm)
+            .mipmaps(2)
+            .image_count(2)
+            .write_to(&mut tex)
+            .unwrap();
+        for layer in 0..2u8 {
+            tex.extend(std::iter::repeat_n(layer, 32));
+            tex.extend(std::iter::repeat_n(0xFF, 8));
+        }
+
+        let dds = convert_to_dds_with(&mut Cursor::new(tex), MipPolicy::OnlyBase).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.get_num_array_layers(), 2);
+        assert_eq!(dds.data, [[0u8; 32], [1u8; 32]].concat());
+    }
+
+    #[test]
+    fn test_convert_to_dds_regenerate() {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc1Unorm)
+            .mipmaps(2)
+            .write_to(&mut tex)
+            .unwrap();
+        let base = (0..32).map(|i| i as u8 * 7).collect::<Vec<_>>();
+        tex.extend(&base);
+        tex.extend(std::iter::repeat_n(0xFF, 8));
+
+        let dds = convert_to_dds_with(&mut Cursor::new(tex), MipPolicy::Regenerate).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        // 8x8 + 4x4 + 2x2 + 1x1，每层至少一个块
+        assert_eq!(dds.get_num_mipmap_levels(), 4);
+        assert_eq!(dds.data.len(), 32 + 8 * 3);
+        assert_eq!(dds.data[..32], base);
+    }
+
+    #[test]
+    fn test_salvage_to_dds() {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc1Unorm)
+            .mipmaps(3)
+            .write_to(&mut tex)
+            .unwrap();
+        tex.extend(std::iter::repeat_n(1u8, 32));
+        tex.extend(std::iter::repeat_n(2u8, 8));
+        tex.extend(std::iter::repeat_n(3u8, 8));
+
+        let (_, report) = salvage_to_dds(&mut Cursor::new(&tex)).unwrap();
+        assert!(report.is_complete());
+
+        // 截断在 mip 1 中间
+        tex.truncate(tex.len() - 12);
+        assert!(convert_mip0_to_dds(&mut Cursor::new(&tex)).is_err());
+        let (dds, report) = salvage_to_dds(&mut Cursor::new(&tex)).unwrap();
+        assert_eq!(report.recovered_mips, 1);
+        assert_eq!(report.missing_mips, vec![1, 2]);
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_num_mipmap_levels(), 1);
+        assert_eq!(dds.data, vec![1u8; 32]);
+
+        // mip 0 不完整
+        tex.truncate(tex.len() - 30);
+        assert!(matches!(
+            salvage_to_dds(&mut Cursor::new(&tex)),
+            Err(Error::DataSizeMismatch(..))
+        ));
+    }
+
+    /// 4x4x3 RGBA8 体积纹理，每个切片的像素值等于其序号
+    fn volume_tex() -> Vec<u8> {
+        let mut data = vec![];
+        TexInfo::builder()
+            .width(4)
+            .height(4)
+            .depth(3)
+            .format(TexFormat::DxgiFormatR8G8B8A8Unorm)
+            .write_to(&mut data)
+            .unwrap();
+        for slice in 0..3u8 {
+            data.extend(std::iter::repeat_n(slice, 4 * 4 * 4));
+        }
+        data
+    }
+
+    #[test]
+    fn test_convert_volume_to_dds() {
+        let info = TexInfo::from_reader(&mut Cursor::new(volume_tex())).unwrap();
+        assert_eq!(info.dimension, TexDimension::Texture3D);
+        assert_eq!((info.image_count, info.depth), (1, 3));
+
+        let dds = convert_to_dds(&mut Cursor::new(volume_tex())).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_depth(), 3);
+        assert!(dds.header.caps2.contains(Caps2::VOLUME));
+        assert_eq!(
+            dds.header10.unwrap().resource_dimension,
+            D3D10ResourceDimension::Texture3D
+        );
+        assert_eq!(dds.data.len(), 3 * 4 * 4 * 4);
+    }
+
+    #[test]
+    fn test_convert_slice_to_dds() {
+        let dds = convert_slice_to_dds(&mut Cursor::new(volume_tex()), 2).unwrap();
+        let dds = Dds::read(&mut Cursor::new(&dds)).unwrap();
+        assert_eq!(dds.get_depth(), 1);
+        assert!(dds.data.iter().all(|&b| b == 2));
+
+        let err = convert_slice_to_dds(&mut Cursor::new(volume_tex()), 3).unwrap_err();
+        assert!(matches!(err, Error::SliceOutOfRange(3, 3)));
+    }
+
+    #[test]
+    fn test_convert_surface_to_dds() {
+        let err = convert_surface_to_dds(&mut Cursor::new(cubemap_tex()), 0, 1).unwrap_err();
+        assert!(matches!(err, Error::MipLevelOutOfRange(1, 1)));
+    }
 }
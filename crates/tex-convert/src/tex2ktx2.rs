@@ -0,0 +1,318 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{WriteBytesExt, LE};
+
+use crate::{
+    color::ColorSpace,
+    error::Result,
+    spec::{self, TexDimension, TexFormat, TexInfo},
+};
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+/// 文件头与段索引的大小
+const KTX2_HEADER_SIZE: usize = 80;
+
+/// KHR_DF_MODEL_RGBSDA
+const DF_MODEL_RGBSDA: u8 = 1;
+/// KHR_DF_PRIMARIES_BT709
+const DF_PRIMARIES_BT709: u8 = 1;
+const DF_TRANSFER_LINEAR: u8 = 1;
+const DF_TRANSFER_SRGB: u8 = 2;
+const DF_CHANNEL_RED: u8 = 0;
+const DF_CHANNEL_GREEN: u8 = 1;
+const DF_CHANNEL_BLUE: u8 = 2;
+const DF_CHANNEL_ALPHA: u8 = 15;
+const DF_QUALIFIER_LINEAR: u8 = 0x10;
+const DF_QUALIFIER_FLOAT: u8 = 0x80;
+
+/// 数据格式描述中的一个样本
+struct Sample {
+    bit_offset: u16,
+    bit_length: u8,
+    channel: u8,
+    lower: u32,
+    upper: u32,
+}
+
+/// KTX2 需要的格式信息
+struct Ktx2Format {
+    vk_format: u32,
+    type_size: u32,
+    color_model: u8,
+    /// 块尺寸
+    block_dim: u8,
+    block_bytes: u8,
+    samples: Vec<Sample>,
+}
+
+impl Ktx2Format {
+    fn from_tex_format(format: TexFormat) -> Self {
+        let block = |vk_format, color_model, block_bytes, samples| Ktx2Format {
+            vk_format,
+            type_size: 1,
+            color_model,
+            block_dim: 4,
+            block_bytes,
+            samples,
+        };
+        let unorm_block = |bit_offset, bit_length, channel| Sample {
+            bit_offset,
+            bit_length,
+            channel,
+            lower: 0,
+            upper: u32::MAX,
+        };
+        let unorm8 = |index: u16, channel| Sample {
+            bit_offset: index * 8,
+            bit_length: 8,
+            channel,
+            lower: 0,
+            upper: 255,
+        };
+
+        // colorModel 取值见 KHR Data Format Specification，BC1 = 128 ... BC7 = 134
+        match format {
+            TexFormat::DxgiFormatR8G8B8A8Unorm | TexFormat::DxgiFormatR8G8B8A8UnormSRGB => {
+                Ktx2Format {
+                    vk_format: if format == TexFormat::DxgiFormatR8G8B8A8Unorm {
+                        37
+                    } else {
+                        43
+                    },
+                    type_size: 1,
+                    color_model: DF_MODEL_RGBSDA,
+                    block_dim: 1,
+                    block_bytes: 4,
+                    samples: vec![
+                        unorm8(0, DF_CHANNEL_RED),
+                        unorm8(1, DF_CHANNEL_GREEN),
+                        unorm8(2, DF_CHANNEL_BLUE),
+                        unorm8(3, DF_CHANNEL_ALPHA),
+                    ],
+                }
+            }
+            TexFormat::DxgiFormatR8G8Unorm => Ktx2Format {
+                vk_format: 16,
+                type_size: 1,
+                color_model: DF_MODEL_RGBSDA,
+                block_dim: 1,
+                block_bytes: 2,
+                samples: vec![unorm8(0, DF_CHANNEL_RED), unorm8(1, DF_CHANNEL_GREEN)],
+            },
+            TexFormat::DxgiFormatBc1Unorm => block(133, 128, 8, vec![unorm_block(0, 64, 0)]),
+            TexFormat::DxgiFormatBc1UnormSRGB => block(134, 128, 8, vec![unorm_block(0, 64, 0)]),
+            TexFormat::DxgiFormatBc4Unorm => block(139, 131, 8, vec![unorm_block(0, 64, 0)]),
+            TexFormat::DxgiFormatBc5Unorm => block(
+                141,
+                132,
+                16,
+                vec![unorm_block(0, 64, 0), unorm_block(64, 64, 1)],
+            ),
+            TexFormat::DxgiFormatBc6hUf16 => block(
+                143,
+                133,
+                16,
+                vec![Sample {
+                    bit_offset: 0,
+                    bit_length: 128,
+                    channel: DF_QUALIFIER_FLOAT,
+                    lower: 0,
+                    // 65504.0f32，半精度浮点的最大值
+                    upper: 0x477F_E000,
+                }],
+            ),
+            TexFormat::DxgiFormatBc7Unorm | TexFormat::DxgiFormatUnknown => {
+                block(145, 134, 16, vec![unorm_block(0, 128, 0)])
+            }
+            TexFormat::DxgiFormatBc7UnormSRGB => block(146, 134, 16, vec![unorm_block(0, 128, 0)]),
+        }
+    }
+
+    /// 数据格式描述 (DFD)，包含开头的总长度
+    fn dfd(&self, color_space: ColorSpace) -> Vec<u8> {
+        let block_size = 24 + 16 * self.samples.len() as u16;
+        let transfer = match color_space {
+            ColorSpace::Srgb => DF_TRANSFER_SRGB,
+            ColorSpace::Linear => DF_TRANSFER_LINEAR,
+        };
+
+        let mut dfd = vec![];
+        dfd.extend((4 + block_size as u32).to_le_bytes());
+        // vendorId = 0 (Khronos), descriptorType = 0 (basic)
+        dfd.extend(0u32.to_le_bytes());
+        // versionNumber = 2 (KDFS 1.3)
+        dfd.extend(2u16.to_le_bytes());
+        dfd.extend(block_size.to_le_bytes());
+        dfd.extend([self.color_model, DF_PRIMARIES_BT709, transfer, 0]);
+        let dim = self.block_dim - 1;
+        dfd.extend([dim, dim, 0, 0]);
+        dfd.extend([self.block_bytes, 0, 0, 0, 0, 0, 0, 0]);
+
+        for sample in &self.samples {
+            let mut channel = sample.channel;
+            // sRGB 格式的透明度仍为线性
+            if color_space == ColorSpace::Srgb && channel == DF_CHANNEL_ALPHA {
+                channel |= DF_QUALIFIER_LINEAR;
+            }
+            dfd.extend(sample.bit_offset.to_le_bytes());
+            dfd.extend([sample.bit_length - 1, channel]);
+            dfd.extend([0u8; 4]);
+            dfd.extend(sample.lower.to_le_bytes());
+            dfd.extend(sample.upper.to_le_bytes());
+        }
+
+        dfd
+    }
+}
+
+/// 将 tex 转换为 KTX2
+///
+/// 保留全部 mipmap、数组元素与立方体贴图的面，不进行超压缩。
+pub fn convert_to_ktx2<R>(reader: &mut R) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let info = TexInfo::from_reader(reader)?;
+    // 全部图片与 mipmap 的数据必须位于文件内
+    let image_size =
+        info.format
+            .volume_size(info.width, info.height, info.depth, info.mip_map_count);
+    spec::ensure_in_stream(
+        reader,
+        info.mip_offsets[0],
+        image_size as u64 * info.image_count as u64,
+    )?;
+    let ktx2_format = Ktx2Format::from_tex_format(info.format);
+
+    let mip_map_count = info.mip_map_count.max(1);
+    let cubemap = info.dimension == TexDimension::Cube;
+    let (face_count, layer_count) = if cubemap {
+        (6, (info.image_count / 6).max(1))
+    } else {
+        (1, info.image_count)
+    };
+
+    // 每层 mipmap 包含所有数组元素与面
+    let mut levels = Vec::with_capacity(mip_map_count as usize);
+    for level in 0..mip_map_count {
+        let width = (info.width >> level).max(1);
+        let height = (info.height >> level).max(1);
+        let depth = (info.depth >> level).max(1);
+        // 体积纹理每层 mipmap 的所有深度切片连续存放
+        let surface_size = info.format.volume_size(width, height, depth, 1);
+
+        let mut data = Vec::with_capacity(surface_size * info.image_count as usize);
+        for image in 0..info.image_count {
+            let offset = info.mip_offsets[(image * mip_map_count + level) as usize];
+            reader.seek(SeekFrom::Start(offset))?;
+            let start = data.len();
+            data.resize(start + surface_size, 0);
+            reader.read_exact(&mut data[start..])?;
+        }
+        levels.push(data);
+    }
+
+    let dfd = ktx2_format.dfd(info.color_space);
+    let dfd_offset = KTX2_HEADER_SIZE + levels.len() * 24;
+    // 每层数据按 lcm(块大小, 4) 对齐
+    let alignment = lcm(ktx2_format.block_bytes as usize, 4);
+
+    // 文件中从最小的 mipmap 开始存放
+    let mut level_index = vec![(0u64, 0u64); levels.len()];
+    let mut offset = dfd_offset + dfd.len();
+    for (level, data) in levels.iter().enumerate().rev() {
+        offset = offset.next_multiple_of(alignment);
+        level_index[level] = (offset as u64, data.len() as u64);
+        offset += data.len();
+    }
+
+    let mut out = Vec::with_capacity(offset);
+    out.write_all(&KTX2_IDENTIFIER)?;
+    out.write_u32::<LE>(ktx2_format.vk_format)?;
+    out.write_u32::<LE>(ktx2_format.type_size)?;
+    out.write_u32::<LE>(info.width as u32)?;
+    out.write_u32::<LE>(info.height as u32)?;
+    // pixelDepth
+    out.write_u32::<LE>(if info.depth > 1 { info.depth as u32 } else { 0 })?;
+    out.write_u32::<LE>(if layer_count > 1 {
+        layer_count as u32
+    } else {
+        0
+    })?;
+    out.write_u32::<LE>(face_count)?;
+    out.write_u32::<LE>(mip_map_count as u32)?;
+    out.write_u32::<LE>(0)?; // supercompressionScheme
+
+    out.write_u32::<LE>(dfd_offset as u32)?;
+    out.write_u32::<LE>(dfd.len() as u32)?;
+    out.write_u32::<LE>(0)?; // kvdByteOffset
+    out.write_u32::<LE>(0)?; // kvdByteLength
+    out.write_u64::<LE>(0)?; // sgdByteOffset
+    out.write_u64::<LE>(0)?; // sgdByteLength
+
+    for (offset, length) in &level_index {
+        out.write_u64::<LE>(*offset)?;
+        out.write_u64::<LE>(*length)?;
+        out.write_u64::<LE>(*length)?; // uncompressedByteLength
+    }
+    out.write_all(&dfd)?;
+
+    for (level, data) in levels.iter().enumerate().rev() {
+        out.resize(level_index[level].0 as usize, 0);
+        out.write_all(data)?;
+    }
+
+    Ok(out)
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_convert_to_ktx2() {
+        let mut tex = vec![];
+        TexInfo::builder()
+            .width(8)
+            .height(8)
+            .format(TexFormat::DxgiFormatBc7UnormSRGB)
+            .mipmaps(2)
+            .write_to(&mut tex)
+            .unwrap();
+        // mip 0 = 64 字节，mip 1 = 16 字节
+        tex.extend((0..64).map(|_| 0xAA));
+        tex.extend((0..16).map(|_| 0xBB));
+
+        let data = convert_to_ktx2(&mut Cursor::new(tex)).unwrap();
+        let reader = ktx2::Reader::new(&data).unwrap();
+        let header = reader.header();
+        assert_eq!(header.format, Some(ktx2::Format::BC7_SRGB_BLOCK));
+        assert_eq!((header.pixel_width, header.pixel_height), (8, 8));
+        assert_eq!(header.level_count, 2);
+        assert_eq!(header.face_count, 1);
+
+        let levels = reader.levels().collect::<Vec<_>>();
+        assert_eq!(levels[0].data, &[0xAA; 64]);
+        assert_eq!(levels[1].data, &[0xBB; 16]);
+
+        let block = reader.dfd_blocks().next().unwrap();
+        let basic = ktx2::DfdBlockBasic::parse(block.data).unwrap();
+        assert_eq!(
+            basic.header.transfer_function,
+            Some(ktx2::TransferFunction::SRGB)
+        );
+        assert_eq!(basic.sample_information().count(), 1);
+    }
+}
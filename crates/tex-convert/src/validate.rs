@@ -0,0 +1,388 @@
+use std::{
+    fmt::Display,
+    io::{Read, Seek, SeekFrom},
+};
+
+use byteorder::{ReadBytesExt, LE};
+use num_traits::FromPrimitive;
+
+use crate::{
+    error::Result,
+    spec::{FormatInfo, TexDimension, TexFormat, TexHeader, TexInfo, TexVariant},
+};
+
+/// 问题严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// 文件可以使用，但可能与预期不符
+    Warning,
+    /// 文件已损坏或无法被正确读取
+    Error,
+}
+
+/// 校验发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    BadMagic(i32),
+    UnsupportedVersion(i32),
+    UnknownFormat(i32),
+    InvalidDimensions {
+        width: i32,
+        height: i32,
+    },
+    InvalidMipMapCount(i32),
+    InvalidImageCount(i32),
+    /// 文件头中的数量和尺寸计算出的大小超出 u64 范围
+    SizeOverflow,
+    MipOffsetNotIncreasing {
+        index: usize,
+        offset: u64,
+        previous: u64,
+    },
+    MipOffsetOutOfBounds {
+        index: usize,
+        offset: u64,
+        file_size: u64,
+    },
+    DataTooShort {
+        expected: u64,
+        actual: u64,
+    },
+    TrailingData {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl ValidationIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValidationIssue::TrailingData { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::BadMagic(magic) => write!(f, "invalid magic number: {:#x}", magic),
+            ValidationIssue::UnsupportedVersion(version) => {
+                write!(f, "unsupported tex version: {}", version)
+            }
+            ValidationIssue::UnknownFormat(format) => write!(f, "unknown tex format: {}", format),
+            ValidationIssue::InvalidDimensions { width, height } => {
+                write!(f, "invalid dimensions: {}x{}", width, height)
+            }
+            ValidationIssue::InvalidMipMapCount(count) => {
+                write!(f, "invalid mipmap count: {}", count)
+            }
+            ValidationIssue::InvalidImageCount(count) => {
+                write!(f, "invalid image count: {}", count)
+            }
+            ValidationIssue::SizeOverflow => {
+                write!(f, "header describes a data size that overflows")
+            }
+            ValidationIssue::MipOffsetNotIncreasing {
+                index,
+                offset,
+                previous,
+            } => write!(
+                f,
+                "mip offset #{} ({:#x}) is not greater than the previous one ({:#x})",
+                index, offset, previous
+            ),
+            ValidationIssue::MipOffsetOutOfBounds {
+                index,
+                offset,
+                file_size,
+            } => write!(
+                f,
+                "mip offset #{} ({:#x}) is beyond the end of file ({:#x})",
+                index, offset, file_size
+            ),
+            ValidationIssue::DataTooShort { expected, actual } => write!(
+                f,
+                "pixel data too short: expected {} bytes, got {}",
+                expected, actual
+            ),
+            ValidationIssue::TrailingData { expected, actual } => write!(
+                f,
+                "pixel data longer than expected: expected {} bytes, got {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+/// tex 文件校验结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// 没有发现 [Severity::Error] 级别的问题
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Warning)
+    }
+}
+
+/// 校验 tex 文件
+///
+/// 与 [crate::spec::TexInfo::from_reader] 不同，遇到问题时不会立即返回，
+/// 而是尽可能检查所有项目并汇总到报告中。只有 IO 错误会以 `Err` 返回。
+pub fn validate<R>(reader: &mut R) -> Result<ValidationReport>
+where
+    R: Read + Seek,
+{
+    let mut report = ValidationReport::default();
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    if file_size < TexHeader::FIXED_SIZE {
+        report.issues.push(ValidationIssue::DataTooShort {
+            expected: TexHeader::FIXED_SIZE,
+            actual: file_size,
+        });
+        return Ok(report);
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    let magic = reader.read_i32::<LE>()?;
+    if magic != TexHeader::MAGIC {
+        report.issues.push(ValidationIssue::BadMagic(magic));
+        return Ok(report);
+    }
+    let version = reader.read_i32::<LE>()?;
+    if version != TexVariant::MHW_VERSION {
+        report
+            .issues
+            .push(ValidationIssue::UnsupportedVersion(version));
+        return Ok(report);
+    }
+
+    reader.seek(SeekFrom::Start(TexInfo::DIMENSION_OFFSET))?;
+    let is_volume = reader.read_i32::<LE>()? == TexDimension::Texture3D as i32;
+    reader.seek(SeekFrom::Start(TexInfo::MIP_MAP_COUNT_OFFSET))?;
+    let mip_map_count = reader.read_i32::<LE>()?;
+    let width = reader.read_i32::<LE>()?;
+    let height = reader.read_i32::<LE>()?;
+    reader.seek(SeekFrom::Start(TexInfo::IMAGE_COUNT_OFFSET))?;
+    let raw_image_count = reader.read_i32::<LE>()?;
+    // 体积纹理的深度记录在图片数量字段中
+    let (image_count, depth) = match i32::max(raw_image_count, 1) {
+        depth if is_volume => (1, depth),
+        image_count => (image_count, 1),
+    };
+    reader.seek(SeekFrom::Start(TexInfo::FORMAT_OFFSET))?;
+    let format_code = reader.read_i32::<LE>()?;
+
+    let format = TexFormat::from_i32(format_code).filter(|f| *f != TexFormat::DxgiFormatUnknown);
+    if format.is_none() {
+        report
+            .issues
+            .push(ValidationIssue::UnknownFormat(format_code));
+    }
+    if width <= 0 || height <= 0 {
+        report
+            .issues
+            .push(ValidationIssue::InvalidDimensions { width, height });
+    }
+    if !(1..=TexInfo::MAX_MIP_MAP_COUNT).contains(&mip_map_count) {
+        report
+            .issues
+            .push(ValidationIssue::InvalidMipMapCount(mip_map_count));
+    }
+    if raw_image_count < 0 {
+        report
+            .issues
+            .push(ValidationIssue::InvalidImageCount(raw_image_count));
+    }
+    if !report.is_valid() {
+        return Ok(report);
+    }
+
+    // mipmap 偏移表，数量来自文件，先扩展到 u64 再计算，避免溢出
+    let offset_count = mip_map_count as u64 * image_count as u64;
+    let Some(table_end) = offset_count
+        .checked_mul(8)
+        .and_then(|size| size.checked_add(TexInfo::MIP_OFFSETS_OFFSET))
+    else {
+        report.issues.push(ValidationIssue::SizeOverflow);
+        return Ok(report);
+    };
+    if table_end > file_size {
+        report.issues.push(ValidationIssue::DataTooShort {
+            expected: table_end,
+            actual: file_size,
+        });
+        return Ok(report);
+    }
+    reader.seek(SeekFrom::Start(TexInfo::MIP_OFFSETS_OFFSET))?;
+    let mut offsets = Vec::with_capacity(offset_count as usize);
+    for _ in 0..offset_count {
+        offsets.push(reader.read_u64::<LE>()?);
+    }
+    for (index, &offset) in offsets.iter().enumerate() {
+        if offset >= file_size {
+            report.issues.push(ValidationIssue::MipOffsetOutOfBounds {
+                index,
+                offset,
+                file_size,
+            });
+        }
+        if index > 0 && offset <= offsets[index - 1] {
+            report.issues.push(ValidationIssue::MipOffsetNotIncreasing {
+                index,
+                offset,
+                previous: offsets[index - 1],
+            });
+        }
+    }
+
+    // 数据大小
+    if let (Some(format), Some(&first_offset)) = (format, offsets.first()) {
+        let Some(expected) = data_size(format, width, height, depth, mip_map_count, image_count)
+        else {
+            report.issues.push(ValidationIssue::SizeOverflow);
+            return Ok(report);
+        };
+        let actual = file_size.saturating_sub(first_offset);
+        if actual < expected {
+            report
+                .issues
+                .push(ValidationIssue::DataTooShort { expected, actual });
+        } else if actual > expected {
+            report
+                .issues
+                .push(ValidationIssue::TrailingData { expected, actual });
+        }
+    }
+
+    Ok(report)
+}
+
+/// 同 [TexFormat::volume_size] 乘以图片数量，溢出时返回 None
+fn data_size(
+    format: TexFormat,
+    width: i32,
+    height: i32,
+    depth: i32,
+    mip_map_count: i32,
+    image_count: i32,
+) -> Option<u64> {
+    let mut size = 0u64;
+    for level in 0..mip_map_count.max(1) {
+        let width = (width >> level).max(1) as u32;
+        let height = (height >> level)
+            .max(1)
+            .max(format.min_mip_dimension() as i32) as u32;
+        let surface = (format.row_pitch(width) as u64)
+            .checked_mul(height.div_ceil(format.block_size()) as u64)?;
+        let slices = (depth >> level).max(1) as u64;
+        size = size.checked_add(surface.checked_mul(slices)?)?;
+    }
+
+    size.checked_mul(image_count as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::spec::TexPitch;
+
+    fn sample_tex(mip_offsets: Vec<u64>, data_len: usize) -> Vec<u8> {
+        let header = TexHeader {
+            version: 0x10,
+            unk_08: [0; 8],
+            dimension: 2,
+            mip_map_count: mip_offsets.len() as i32,
+            width: 8,
+            height: 8,
+            image_count: 1,
+            format: TexFormat::DxgiFormatBc7Unorm,
+            unk_28: [0; 0x1C],
+            new_dds_flag: 1,
+            unk_48: [0; 0x10],
+            unk_58: [0xFF; 0x20],
+            row_width: 8,
+            pitches: [TexPitch::default(); 3],
+            unk_a0: [0; 0x18],
+            mip_offsets,
+        };
+        let mut data = vec![];
+        header.write_to(&mut data).unwrap();
+        data.resize(data.len() + data_len, 0);
+        data
+    }
+
+    #[test]
+    fn test_validate_ok() {
+        // 8x8 BC7: mip 0 = 64 字节, mip 1 = 16 字节
+        let tex = sample_tex(vec![0xC8, 0xC8 + 64], 80);
+        let report = validate(&mut Cursor::new(tex)).unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_validate_issues() {
+        let tex = sample_tex(vec![0xC8, 0xC8], 40);
+        let report = validate(&mut Cursor::new(tex)).unwrap();
+        assert!(!report.is_valid());
+        assert!(report
+            .errors()
+            .any(|issue| matches!(issue, ValidationIssue::MipOffsetNotIncreasing { .. })));
+        assert!(report
+            .errors()
+            .any(|issue| matches!(issue, ValidationIssue::DataTooShort { .. })));
+    }
+
+    #[test]
+    fn test_validate_untrusted_counts() {
+        let patch = |fields: &[(u64, i32)]| {
+            let mut tex = sample_tex(vec![0xC8], 64);
+            for &(offset, value) in fields {
+                let offset = offset as usize;
+                tex[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            validate(&mut Cursor::new(tex)).unwrap()
+        };
+
+        let report = patch(&[(TexInfo::IMAGE_COUNT_OFFSET, -1)]);
+        assert_eq!(report.issues, [ValidationIssue::InvalidImageCount(-1)]);
+
+        // 偏移表超出文件，不会溢出
+        let report = patch(&[
+            (TexInfo::MIP_MAP_COUNT_OFFSET, TexInfo::MAX_MIP_MAP_COUNT),
+            (TexInfo::IMAGE_COUNT_OFFSET, i32::MAX),
+        ]);
+        assert!(matches!(
+            report.issues[..],
+            [ValidationIssue::DataTooShort { .. }]
+        ));
+
+        let report = patch(&[
+            (TexInfo::MIP_MAP_COUNT_OFFSET + 4, i32::MAX),
+            (TexInfo::MIP_MAP_COUNT_OFFSET + 8, i32::MAX),
+            (
+                TexInfo::FORMAT_OFFSET,
+                TexFormat::DxgiFormatR8G8B8A8Unorm as i32,
+            ),
+            (TexInfo::IMAGE_COUNT_OFFSET, 2),
+        ]);
+        assert!(report.issues.contains(&ValidationIssue::SizeOverflow));
+    }
+}
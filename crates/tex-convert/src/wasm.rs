@@ -0,0 +1,26 @@
+use std::io::Cursor;
+
+use image::ImageFormat;
+use wasm_bindgen::prelude::*;
+
+use crate::options::ConvertOptions;
+
+/// 将 tex 文件转换为 png
+#[wasm_bindgen(js_name = texToPng)]
+pub fn tex_to_png(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    let img = crate::load_tex_image_bytes(data)?;
+
+    let mut png_data = Cursor::new(vec![]);
+    img.write_to(&mut png_data, ImageFormat::Png)?;
+
+    Ok(png_data.into_inner())
+}
+
+/// 将 png 图片转换为 tex 文件，使用默认的 BC7 sRGB 格式
+#[wasm_bindgen(js_name = pngToTex)]
+pub fn png_to_tex(data: &[u8], mipmaps: bool) -> Result<Vec<u8>, JsError> {
+    let img = image::load_from_memory_with_format(data, ImageFormat::Png)?.to_rgba8();
+    let options = ConvertOptions::default().mipmaps(mipmaps);
+
+    Ok(crate::convert_image_to_tex(&img, &options)?)
+}